@@ -1,6 +1,7 @@
 use game_utils::{control_axis::{ControlAxis, AxisContribution}, dimension3::Dimension3,};
 use pid_controller::PID;
 use num::Float;
+use crate::{FcsError, optimization, control_axis_to_array, array_to_control_axis, propulsion_control::MassProperties};
 
 pub fn calculate<T>(
     goal_position: &ControlAxis<Dimension3<T>>,
@@ -71,4 +72,1534 @@ pub fn calculate<T>(
             )
         )
     )
-}
\ No newline at end of file
+}
+
+
+/// how the integrator is kept from winding up while the controller output is saturated
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AntiWindup<T>{
+    /// integrator runs freely. matches pid_controller::PID
+    None,
+    /// integration stops while the output is saturated and the error would push it further into saturation
+    ConditionalIntegration,
+    /// the difference between saturated and unsaturated output is fed back into the integrator, scaled by tracking gain.
+    /// higher tracking gain unwinds faster
+    BackCalculation{tracking_gain: T},
+}
+
+
+
+/// pid controller with output limits, anti-windup, derivative filtering, and derivative on measurement.
+/// with the defaults from new, behaves like pid_controller::PID, other than clamping its output
+pub struct PidController<T>{
+    kp: T,
+    ki: T,
+    kd: T,
+    anti_windup: AntiWindup<T>,
+    // time constant(s) of the first order low pass filter on the derivative term. zero disables filtering
+    derivative_filter_time_constant: T,
+    // differentiate position instead of error, so a step in goal position doesn't kick the output
+    derivative_on_measurement: bool,
+    // ki already applied, so gains can change without the output jumping
+    integral_term: T,
+    filtered_derivative: T,
+    previous_error: Option<T>,
+    previous_position: Option<T>,
+}
+impl<T> PidController<T>
+    where T: Float
+{
+    pub fn new(kp: T, ki: T, kd: T) -> Self{
+        Self{
+            kp,
+            ki,
+            kd,
+            anti_windup: AntiWindup::None,
+            derivative_filter_time_constant: num::zero(),
+            derivative_on_measurement: false,
+            integral_term: num::zero(),
+            filtered_derivative: num::zero(),
+            previous_error: None,
+            previous_position: None,
+        }
+    }
+
+    pub fn kp(&self) -> T{self.kp}
+    pub fn ki(&self) -> T{self.ki}
+    pub fn kd(&self) -> T{self.kd}
+
+    /// keeps integrator and derivative state, so the output stays continuous
+    pub fn set_gains(&mut self, kp: T, ki: T, kd: T){
+        self.kp = kp;
+        self.ki = ki;
+        self.kd = kd;
+    }
+
+    pub fn anti_windup(&self) -> AntiWindup<T>{self.anti_windup}
+
+    pub fn set_anti_windup(&mut self, anti_windup: AntiWindup<T>){
+        self.anti_windup = anti_windup;
+    }
+
+    pub fn derivative_filter_time_constant(&self) -> T{self.derivative_filter_time_constant}
+
+    /// negative values are treated as zero
+    pub fn set_derivative_filter_time_constant(&mut self, derivative_filter_time_constant: T){
+        self.derivative_filter_time_constant = derivative_filter_time_constant.max(num::zero());
+    }
+
+    pub fn derivative_on_measurement(&self) -> bool{self.derivative_on_measurement}
+
+    pub fn set_derivative_on_measurement(&mut self, derivative_on_measurement: bool){
+        self.derivative_on_measurement = derivative_on_measurement;
+    }
+
+    pub fn integral_term(&self) -> T{self.integral_term}
+
+    /// clears integrator and derivative history
+    pub fn reset(&mut self){
+        self.integral_term = num::zero();
+        self.filtered_derivative = num::zero();
+        self.previous_error = None;
+        self.previous_position = None;
+    }
+
+    /// output is clamped to [min_output, max_output]. with no time passed(delta_time <= 0, e.g. a paused frame),
+    /// only the proportional and integral terms are output, and no state changes
+    pub fn calculate(&mut self, goal: T, position: T, min_output: T, max_output: T, delta_time: T) -> T{
+        let error = goal - position;
+        if delta_time <= num::zero(){
+            return num::clamp((self.kp * error) + self.integral_term, min_output, max_output)
+        }
+
+        // no history on the first call, so there is nothing to differentiate
+        let derivative = if self.derivative_on_measurement{
+            match self.previous_position{
+                Some(previous_position) => -(position - previous_position) / delta_time,
+                None => num::zero()
+            }
+        }else{
+            match self.previous_error{
+                Some(previous_error) => (error - previous_error) / delta_time,
+                None => num::zero()
+            }
+        };
+        let alpha = delta_time / (self.derivative_filter_time_constant + delta_time);
+        self.filtered_derivative = self.filtered_derivative + (alpha * (derivative - self.filtered_derivative));
+        self.previous_error = Some(error);
+        self.previous_position = Some(position);
+
+        let proportional_term = self.kp * error;
+        let derivative_term = self.kd * self.filtered_derivative;
+        let integral_term = self.integral_term + (self.ki * error * delta_time);
+        let unsaturated_output = proportional_term + integral_term + derivative_term;
+        let output = num::clamp(unsaturated_output, min_output, max_output);
+
+        self.integral_term = match self.anti_windup{
+            AntiWindup::None => integral_term,
+            AntiWindup::ConditionalIntegration => {
+                let winding_up = (unsaturated_output > max_output && error > num::zero())
+                    || (unsaturated_output < min_output && error < num::zero());
+                if winding_up{self.integral_term}else{integral_term}
+            },
+            AntiWindup::BackCalculation{tracking_gain} => {
+                integral_term + (tracking_gain * (output - unsaturated_output) * delta_time)
+            },
+        };
+
+        output
+    }
+}
+
+
+
+/// like calculate, but output limits come from available acceleration. the controller saturates at whatever authority
+/// feedforward acceleration leaves on each side of an axis, so anti-windup knows when the ship can't do what the
+/// controller is asking for. output is still scaled by full available acceleration, so gains don't change with feedforward
+pub fn calculate_limited<T>(
+    goal_position: &ControlAxis<Dimension3<T>>,
+    position: &ControlAxis<Dimension3<T>>,
+    controllers: &mut ControlAxis<Dimension3<PidController<T>>>,
+    available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    feedforward_acceleration: &ControlAxis<Dimension3<T>>,
+    delta_time: T,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    ControlAxis::new(
+        Dimension3::new(
+            calculate_limited_axis(
+                controllers.linear_mut().x_mut(), 
+                goal_position.linear().x(), 
+                position.linear().x(), 
+                available_acceleration.linear().x(), 
+                feedforward_acceleration.linear().x(), 
+                delta_time
+            ),
+            calculate_limited_axis(
+                controllers.linear_mut().y_mut(), 
+                goal_position.linear().y(), 
+                position.linear().y(), 
+                available_acceleration.linear().y(), 
+                feedforward_acceleration.linear().y(), 
+                delta_time
+            ),
+            calculate_limited_axis(
+                controllers.linear_mut().z_mut(), 
+                goal_position.linear().z(), 
+                position.linear().z(), 
+                available_acceleration.linear().z(), 
+                feedforward_acceleration.linear().z(), 
+                delta_time
+            )
+        ),
+        Dimension3::new(
+            calculate_limited_axis(
+                controllers.rotational_mut().x_mut(), 
+                goal_position.rotational().x(), 
+                position.rotational().x(), 
+                available_acceleration.rotational().x(), 
+                feedforward_acceleration.rotational().x(), 
+                delta_time
+            ),
+            calculate_limited_axis(
+                controllers.rotational_mut().y_mut(), 
+                goal_position.rotational().y(), 
+                position.rotational().y(), 
+                available_acceleration.rotational().y(), 
+                feedforward_acceleration.rotational().y(), 
+                delta_time
+            ),
+            calculate_limited_axis(
+                controllers.rotational_mut().z_mut(), 
+                goal_position.rotational().z(), 
+                position.rotational().z(), 
+                available_acceleration.rotational().z(), 
+                feedforward_acceleration.rotational().z(), 
+                delta_time
+            )
+        )
+    )
+}
+
+fn calculate_limited_axis<T>(
+    controller: &mut PidController<T>,
+    goal_position: T,
+    position: T,
+    available_acceleration: AxisContribution<T>,
+    feedforward_acceleration: T,
+    delta_time: T,
+) -> T
+    where T: Float
+{
+    // as a fraction of available acceleration, which is what the controller outputs
+    let remaining = |available: T, remaining: T| {
+        if available > num::zero(){remaining.max(num::zero()) / available}else{num::zero()}
+    };
+    let max_output = remaining(available_acceleration.positive(), available_acceleration.positive() - feedforward_acceleration);
+    let min_output = -remaining(available_acceleration.negative(), available_acceleration.negative() + feedforward_acceleration);
+    crate::multiply_compare_zero(
+        controller.calculate(goal_position, position, min_output, max_output, delta_time),
+        available_acceleration.positive(),
+        available_acceleration.negative()
+    )
+}
+
+
+
+
+
+/// which feedback loops the fcs runs instead of its pid6dof
+pub enum FeedbackLoops<T>{
+    /// single loop from position error straight to acceleration. see calculate_limited
+    Single(ControlAxis<Dimension3<PidController<T>>>),
+    /// outer position loop commanding velocity, inner velocity loop commanding acceleration. see calculate_cascaded
+    Cascaded(ControlAxis<Dimension3<CascadedPid<T>>>),
+    /// state feedback on all six axes together. needs a fixed timestep. see calculate_lqr
+    Lqr(LqrController<T>),
+    /// plans ahead within acceleration and velocity limits. see calculate_mpc
+    Mpc(MpcController<T>),
+}
+
+
+
+/// position and velocity loops for a single axis, each with its own gains
+pub struct CascadedPid<T>{
+    // position error in, velocity setpoint(m/s or rad/s) out
+    position_controller: PidController<T>,
+    // velocity error in, fraction of available acceleration out
+    velocity_controller: PidController<T>,
+}
+impl<T> CascadedPid<T>
+    where T: Float
+{
+    pub fn new(position_controller: PidController<T>, velocity_controller: PidController<T>) -> Self{
+        Self{position_controller, velocity_controller}
+    }
+
+    pub fn position_controller(&self) -> &PidController<T>{&self.position_controller}
+    pub fn position_controller_mut(&mut self) -> &mut PidController<T>{&mut self.position_controller}
+
+    pub fn velocity_controller(&self) -> &PidController<T>{&self.velocity_controller}
+    pub fn velocity_controller_mut(&mut self) -> &mut PidController<T>{&mut self.velocity_controller}
+
+    pub fn reset(&mut self){
+        self.position_controller.reset();
+        self.velocity_controller.reset();
+    }
+}
+
+
+
+/// cascaded position -> velocity -> acceleration control. the position loop's correction is added to goal velocity,
+/// and the resulting velocity setpoint is limited to max velocity. the velocity loop's output is limited to the available
+/// acceleration feedforward acceleration leaves, so each loop's anti-windup sees its own saturation
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_cascaded<T>(
+    goal_position: &ControlAxis<Dimension3<T>>,
+    goal_velocity: &ControlAxis<Dimension3<T>>,
+    position: &ControlAxis<Dimension3<T>>,
+    velocity: &ControlAxis<Dimension3<T>>,
+    controllers: &mut ControlAxis<Dimension3<CascadedPid<T>>>,
+    max_velocity: &ControlAxis<Dimension3<T>>,
+    available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    feedforward_acceleration: &ControlAxis<Dimension3<T>>,
+    delta_time: T,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    ControlAxis::new(
+        Dimension3::new(
+            calculate_cascaded_axis(
+                controllers.linear_mut().x_mut(), 
+                goal_position.linear().x(), 
+                goal_velocity.linear().x(), 
+                position.linear().x(), 
+                velocity.linear().x(), 
+                max_velocity.linear().x(), 
+                available_acceleration.linear().x(), 
+                feedforward_acceleration.linear().x(), 
+                delta_time
+            ),
+            calculate_cascaded_axis(
+                controllers.linear_mut().y_mut(), 
+                goal_position.linear().y(), 
+                goal_velocity.linear().y(), 
+                position.linear().y(), 
+                velocity.linear().y(), 
+                max_velocity.linear().y(), 
+                available_acceleration.linear().y(), 
+                feedforward_acceleration.linear().y(), 
+                delta_time
+            ),
+            calculate_cascaded_axis(
+                controllers.linear_mut().z_mut(), 
+                goal_position.linear().z(), 
+                goal_velocity.linear().z(), 
+                position.linear().z(), 
+                velocity.linear().z(), 
+                max_velocity.linear().z(), 
+                available_acceleration.linear().z(), 
+                feedforward_acceleration.linear().z(), 
+                delta_time
+            )
+        ),
+        Dimension3::new(
+            calculate_cascaded_axis(
+                controllers.rotational_mut().x_mut(), 
+                goal_position.rotational().x(), 
+                goal_velocity.rotational().x(), 
+                position.rotational().x(), 
+                velocity.rotational().x(), 
+                max_velocity.rotational().x(), 
+                available_acceleration.rotational().x(), 
+                feedforward_acceleration.rotational().x(), 
+                delta_time
+            ),
+            calculate_cascaded_axis(
+                controllers.rotational_mut().y_mut(), 
+                goal_position.rotational().y(), 
+                goal_velocity.rotational().y(), 
+                position.rotational().y(), 
+                velocity.rotational().y(), 
+                max_velocity.rotational().y(), 
+                available_acceleration.rotational().y(), 
+                feedforward_acceleration.rotational().y(), 
+                delta_time
+            ),
+            calculate_cascaded_axis(
+                controllers.rotational_mut().z_mut(), 
+                goal_position.rotational().z(), 
+                goal_velocity.rotational().z(), 
+                position.rotational().z(), 
+                velocity.rotational().z(), 
+                max_velocity.rotational().z(), 
+                available_acceleration.rotational().z(), 
+                feedforward_acceleration.rotational().z(), 
+                delta_time
+            )
+        )
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn calculate_cascaded_axis<T>(
+    controller: &mut CascadedPid<T>,
+    goal_position: T,
+    goal_velocity: T,
+    position: T,
+    velocity: T,
+    max_velocity: T,
+    available_acceleration: AxisContribution<T>,
+    feedforward_acceleration: T,
+    delta_time: T,
+) -> T
+    where T: Float
+{
+    let max_velocity = max_velocity.abs();
+    // the position loop only gets whatever velocity authority goal velocity leaves it
+    let velocity_setpoint = goal_velocity + controller.position_controller.calculate(
+        goal_position, 
+        position, 
+        -max_velocity - goal_velocity, 
+        max_velocity - goal_velocity, 
+        delta_time
+    );
+    calculate_limited_axis(
+        &mut controller.velocity_controller, 
+        velocity_setpoint, 
+        velocity, 
+        available_acceleration, 
+        feedforward_acceleration, 
+        delta_time
+    )
+}
+
+
+
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PidGains<T>{
+    kp: T,
+    ki: T,
+    kd: T,
+}
+impl<T> PidGains<T>
+    where T: Float
+{
+    pub fn new(kp: T, ki: T, kd: T) -> Self{
+        Self{kp, ki, kd}
+    }
+
+    pub fn kp(&self) -> T{self.kp}
+
+    pub fn ki(&self) -> T{self.ki}
+
+    pub fn kd(&self) -> T{self.kd}
+}
+
+
+
+/// what a gain schedule is keyed on
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScheduleVariable{
+    /// total ship mass, the same for every axis
+    Mass,
+    /// mass for linear axes, moment of inertia about the axis for rotational axes
+    Inertia,
+    /// average of positive and negative available acceleration on each axis
+    AvailableAcceleration,
+}
+
+
+
+/// one row of a gain schedule. each axis has its own key, so rotational axes can be keyed on inertia while linear
+/// axes are keyed on mass
+pub struct GainSchedulePoint<T>{
+    key: ControlAxis<Dimension3<T>>,
+    gains: ControlAxis<Dimension3<PidGains<T>>>,
+}
+impl<T> GainSchedulePoint<T>
+    where T: Float
+{
+    pub fn new(key: ControlAxis<Dimension3<T>>, gains: ControlAxis<Dimension3<PidGains<T>>>) -> Self{
+        Self{key, gains}
+    }
+
+    pub fn key(&self) -> &ControlAxis<Dimension3<T>>{&self.key}
+    pub fn gains(&self) -> &ControlAxis<Dimension3<PidGains<T>>>{&self.gains}
+}
+
+
+
+/// pid gains interpolated from a table, so one set of tuning works across the range of loads a ship carries
+pub struct GainSchedule<T>{
+    variable: ScheduleVariable,
+    points: Vec<GainSchedulePoint<T>>,
+}
+impl<T> GainSchedule<T>
+    where T: Float
+{
+    /// points don't need to be sorted
+    pub fn new(variable: ScheduleVariable, points: Vec<GainSchedulePoint<T>>) -> Result<Self, FcsError<'static>>{
+        if points.is_empty(){
+            return Err(FcsError::new("Gain schedule needs at least one point."))
+        }
+        Ok(Self{variable, points})
+    }
+
+    pub fn variable(&self) -> ScheduleVariable{self.variable}
+    pub fn points(&self) -> &[GainSchedulePoint<T>]{&self.points}
+
+    /// value of the schedule variable for each axis
+    pub fn key(
+        &self,
+        mass_properties: &MassProperties<T>,
+        available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    ) -> ControlAxis<Dimension3<T>>{
+        match self.variable{
+            ScheduleVariable::Mass => ControlAxis::new(
+                Dimension3::default(mass_properties.mass()),
+                Dimension3::default(mass_properties.mass())
+            ),
+            ScheduleVariable::Inertia => {
+                let inertia_tensor = mass_properties.inertia_tensor();
+                ControlAxis::new(
+                    Dimension3::default(mass_properties.mass()),
+                    Dimension3::new(inertia_tensor[0][0], inertia_tensor[1][1], inertia_tensor[2][2])
+                )
+            },
+            ScheduleVariable::AvailableAcceleration => {
+                let two = T::one() + T::one();
+                let average = |available: AxisContribution<T>| (available.positive() + available.negative()) / two;
+                ControlAxis::new(
+                    Dimension3::new(
+                        average(available_acceleration.linear().x()),
+                        average(available_acceleration.linear().y()),
+                        average(available_acceleration.linear().z())
+                    ),
+                    Dimension3::new(
+                        average(available_acceleration.rotational().x()),
+                        average(available_acceleration.rotational().y()),
+                        average(available_acceleration.rotational().z())
+                    )
+                )
+            },
+        }
+    }
+
+    /// gains linearly interpolated between the points either side of the current key, per axis.
+    /// held at the nearest point outside the table
+    pub fn gains(
+        &self,
+        mass_properties: &MassProperties<T>,
+        available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    ) -> ControlAxis<Dimension3<PidGains<T>>>{
+        let key = control_axis_to_array(&self.key(mass_properties, available_acceleration));
+        let rows: Vec<([T; 6], [PidGains<T>; 6])> = self.points.iter()
+            .map(|point| (control_axis_to_array(&point.key), control_axis_to_array(&point.gains)))
+            .collect();
+        let gains: [PidGains<T>; 6] = std::array::from_fn(|axis| {
+            let mut column: Vec<(T, PidGains<T>)> = rows.iter().map(|(keys, gains)| (keys[axis], gains[axis])).collect();
+            column.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            interpolate_gains(&column, key[axis])
+        });
+        array_to_control_axis(&gains)
+    }
+}
+
+/// sets gains on each controller. PidController keeps its integrator and derivative history
+pub fn apply_gains<T>(controllers: &mut ControlAxis<Dimension3<PidController<T>>>, gains: &ControlAxis<Dimension3<PidGains<T>>>)
+    where T: Float
+{
+    let set = |controller: &mut PidController<T>, gains: PidGains<T>| controller.set_gains(gains.kp, gains.ki, gains.kd);
+    set(controllers.linear_mut().x_mut(), gains.linear().x());
+    set(controllers.linear_mut().y_mut(), gains.linear().y());
+    set(controllers.linear_mut().z_mut(), gains.linear().z());
+    set(controllers.rotational_mut().x_mut(), gains.rotational().x());
+    set(controllers.rotational_mut().y_mut(), gains.rotational().y());
+    set(controllers.rotational_mut().z_mut(), gains.rotational().z());
+}
+
+/// pid_controller::PID can't change gains in place, so each PID is replaced, resetting its integrator and derivative
+/// history. use FeedbackLoops to keep them
+pub fn pids_from_gains<T>(gains: &ControlAxis<Dimension3<PidGains<T>>>) -> ControlAxis<Dimension3<PID<T>>>
+    where T: Float
+{
+    let pid = |gains: PidGains<T>| PID::new(gains.kp, gains.ki, gains.kd);
+    ControlAxis::new(
+        Dimension3::new(pid(gains.linear().x()), pid(gains.linear().y()), pid(gains.linear().z())),
+        Dimension3::new(pid(gains.rotational().x()), pid(gains.rotational().y()), pid(gains.rotational().z()))
+    )
+}
+
+/// PidControllers with the given gains, and no history
+pub fn pid_controllers_from_gains<T>(gains: &ControlAxis<Dimension3<PidGains<T>>>) -> ControlAxis<Dimension3<PidController<T>>>
+    where T: Float
+{
+    let pid = |gains: PidGains<T>| PidController::new(gains.kp, gains.ki, gains.kd);
+    ControlAxis::new(
+        Dimension3::new(pid(gains.linear().x()), pid(gains.linear().y()), pid(gains.linear().z())),
+        Dimension3::new(pid(gains.rotational().x()), pid(gains.rotational().y()), pid(gains.rotational().z()))
+    )
+}
+
+pub(crate) fn gains_equal<T>(a: &ControlAxis<Dimension3<PidGains<T>>>, b: &ControlAxis<Dimension3<PidGains<T>>>) -> bool
+    where T: Float
+{
+    control_axis_to_array(a) == control_axis_to_array(b)
+}
+
+// column is sorted by key, and not empty
+fn interpolate_gains<T>(column: &[(T, PidGains<T>)], key: T) -> PidGains<T>
+    where T: Float
+{
+    let (first_key, first_gains) = column[0];
+    if key <= first_key{return first_gains}
+    for window in column.windows(2){
+        let (low_key, low) = window[0];
+        let (high_key, high) = window[1];
+        if key <= high_key{
+            if high_key <= low_key{return high}
+            let t = (key - low_key) / (high_key - low_key);
+            let lerp = |a: T, b: T| a + ((b - a) * t);
+            return PidGains::new(lerp(low.kp, high.kp), lerp(low.ki, high.ki), lerp(low.kd, high.kd))
+        }
+    }
+    column[column.len() - 1].1
+}
+
+
+
+
+
+/// how an axis is excited during auto-tune. amplitude is a fraction(0 to 1) of available acceleration
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Excitation<T>{
+    /// bang-bang on velocity, around zero. the ship oscillates in place, and the period and amplitude of the
+    /// oscillation give gain and delay
+    Relay{amplitude: T},
+    /// constant acceleration, then the same in reverse to bring the ship back to rest. gain and delay are read from
+    /// the velocity ramp
+    Step{amplitude: T},
+}
+
+
+
+/// rule used to turn an identified axis into gains
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TuningRule<T>{
+    /// aggressive, fast response with some overshoot
+    ZieglerNichols,
+    /// more conservative than Ziegler–Nichols, with less overshoot
+    TyreusLuyben,
+    /// internal model control. closed loop time constant(s) trades response speed for robustness
+    Imc{closed_loop_time_constant: T},
+}
+
+
+
+/// an axis modeled as acceleration = plant_gain * command, delayed by dead_time. command is a fraction of available
+/// acceleration, so plant_gain is roughly available acceleration
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct IdentifiedAxis<T>{
+    plant_gain: T,
+    dead_time: T,
+}
+impl<T> IdentifiedAxis<T>
+    where T: Float
+{
+    pub fn plant_gain(&self) -> T{self.plant_gain}
+
+    /// effective delay(s), including thruster spool up
+    pub fn dead_time(&self) -> T{self.dead_time}
+
+    /// command gain at which the velocity loop oscillates, under proportional control. Ku = π / (2 * k * L)
+    pub fn ultimate_gain(&self) -> T{
+        T::from(std::f64::consts::PI).unwrap() / ((T::one() + T::one()) * self.plant_gain * self.dead_time)
+    }
+
+    /// period(s) of that oscillation. Pu = 4 * L
+    pub fn ultimate_period(&self) -> T{
+        T::from(4.0).unwrap() * self.dead_time
+    }
+
+    /// the rule gives a PI controller for velocity. with the goal at rest, velocity error is the derivative of position
+    /// error, and its integral is position error, so the PI velocity gains are the kd and kp of a position PD. ki is
+    /// left at zero, a double integrator needs no integral action to reach its goal
+    pub fn gains(&self, rule: TuningRule<T>) -> PidGains<T>{
+        let (proportional_gain, integral_time) = match rule{
+            TuningRule::ZieglerNichols => (
+                T::from(0.45).unwrap() * self.ultimate_gain(),
+                self.ultimate_period() / T::from(1.2).unwrap()
+            ),
+            TuningRule::TyreusLuyben => (
+                self.ultimate_gain() / T::from(3.2).unwrap(),
+                T::from(2.2).unwrap() * self.ultimate_period()
+            ),
+            TuningRule::Imc{closed_loop_time_constant} => {
+                let time_constant = closed_loop_time_constant.max(num::zero()) + self.dead_time;
+                (
+                    T::one() / (self.plant_gain * time_constant),
+                    T::from(4.0).unwrap() * time_constant
+                )
+            },
+        };
+        PidGains::new(proportional_gain / integral_time, num::zero(), proportional_gain)
+    }
+}
+
+
+
+/// identifies each axis in turn against plant, then tunes it with rule. plant is given the acceleration(m/s^2, rad/s^2)
+/// to apply for delta time, and returns the resulting velocity. axes not being tuned are commanded zero acceleration
+pub fn auto_tune<T, F>(
+    plant: F,
+    available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    excitation: Excitation<T>,
+    rule: TuningRule<T>,
+    duration: T,
+    delta_time: T,
+) -> Result<ControlAxis<Dimension3<PID<T>>>, FcsError<'static>>
+    where 
+        T: Float,
+        F: FnMut(&ControlAxis<Dimension3<T>>, T) -> ControlAxis<Dimension3<T>>
+{
+    let identified = identify_axes(plant, available_acceleration, excitation, duration, delta_time)?;
+    let gains: [PidGains<T>; 6] = std::array::from_fn(|axis| identified[axis].gains(rule));
+    Ok(pids_from_gains(&array_to_control_axis(&gains)))
+}
+
+/// just the identification half of auto_tune, in linear x, y, z then rotational x, y, z order
+pub fn identify_axes<T, F>(
+    mut plant: F,
+    available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    excitation: Excitation<T>,
+    duration: T,
+    delta_time: T,
+) -> Result<[IdentifiedAxis<T>; 6], FcsError<'static>>
+    where 
+        T: Float,
+        F: FnMut(&ControlAxis<Dimension3<T>>, T) -> ControlAxis<Dimension3<T>>
+{
+    if delta_time.is_nan() || duration.is_nan() || delta_time <= num::zero() || duration <= delta_time{
+        return Err(FcsError::new("Auto-tune duration must be longer than delta time, and delta time must be positive."))
+    }
+    let available = control_axis_to_array(available_acceleration);
+    let steps = (duration / delta_time).to_usize().unwrap_or(0);
+    let mut identified = Vec::with_capacity(6);
+    for (axis, available) in available.iter().enumerate(){
+        if available.positive() <= num::zero() || available.negative() <= num::zero(){
+            return Err(FcsError::with_field("Axis needs available acceleration in both directions to auto-tune.", axis_name(axis).to_string()))
+        }
+        let mut apply = |command: T| {
+            let mut acceleration = [T::zero(); 6];
+            acceleration[axis] = crate::multiply_compare_zero(command, available.positive(), available.negative());
+            control_axis_to_array(&plant(
+                &array_to_control_axis(&acceleration), 
+                delta_time
+            ))[axis]
+        };
+        let result = match excitation{
+            Excitation::Relay{amplitude} => {
+                let mut velocity = T::zero();
+                let velocities: Vec<T> = (0..steps).map(|_| {
+                    velocity = apply(if velocity > num::zero(){-amplitude}else{amplitude});
+                    velocity
+                }).collect();
+                identify_relay(&velocities, amplitude, delta_time)
+            },
+            Excitation::Step{amplitude} => {
+                let velocities: Vec<T> = (0..steps).map(|_| apply(amplitude)).collect();
+                for _ in 0..steps{
+                    let _ = apply(-amplitude);
+                }
+                identify_step(&velocities, amplitude, delta_time)
+            },
+        };
+        match result{
+            Some(axis_model) => identified.push(axis_model),
+            None => return Err(FcsError::with_field("Could not identify axis. Try a longer duration.", axis_name(axis).to_string()))
+        }
+    }
+    Ok([identified[0], identified[1], identified[2], identified[3], identified[4], identified[5]])
+}
+
+fn axis_name(axis: usize) -> &'static str{
+    ["linear.x", "linear.y", "linear.z", "rotational.x", "rotational.y", "rotational.z"][axis]
+}
+
+/// gain and delay from the steady oscillation in the second half of the run. under relay control, velocity of a delayed
+/// integrator is a triangle wave with period 4 * L and amplitude k * d * L. more exact than the describing function
+/// approximation, which assumes the oscillation is sinusoidal
+fn identify_relay<T>(velocities: &[T], amplitude: T, delta_time: T) -> Option<IdentifiedAxis<T>>
+    where T: Float
+{
+    let settled = &velocities[velocities.len() / 2..];
+    // upward zero crossings
+    let crossings: Vec<usize> = settled.windows(2).enumerate()
+        .filter(|(_, pair)| pair[0] <= num::zero() && pair[1] > num::zero())
+        .map(|(index, _)| index)
+        .collect();
+    if crossings.len() < 3{return None}
+    let first = crossings[0];
+    let last = crossings[crossings.len() - 1];
+    let periods = T::from(crossings.len() - 1).unwrap();
+    let period = T::from(last - first).unwrap() * delta_time / periods;
+    let (min, max) = settled[first..=last].iter()
+        .fold((T::infinity(), T::neg_infinity()), |(min, max), velocity| (min.min(*velocity), max.max(*velocity)));
+    let oscillation_amplitude = (max - min) / (T::one() + T::one());
+    if oscillation_amplitude.is_nan() || oscillation_amplitude <= num::zero(){return None}
+
+    let dead_time = (period / T::from(4.0).unwrap()).max(delta_time);
+    Some(IdentifiedAxis{
+        plant_gain: oscillation_amplitude / (amplitude * dead_time),
+        dead_time,
+    })
+}
+
+/// least squares line through the second half of the velocity ramp. slope is gain, the time axis intercept is delay
+fn identify_step<T>(velocities: &[T], amplitude: T, delta_time: T) -> Option<IdentifiedAxis<T>>
+    where T: Float
+{
+    let start = velocities.len() / 2;
+    let samples: Vec<(T, T)> = velocities.iter().enumerate().skip(start)
+        .map(|(index, velocity)| (T::from(index + 1).unwrap() * delta_time, *velocity))
+        .collect();
+    if samples.len() < 2{return None}
+    let count = T::from(samples.len()).unwrap();
+    let mean_time = samples.iter().fold(T::zero(), |sum, (time, _)| sum + *time) / count;
+    let mean_velocity = samples.iter().fold(T::zero(), |sum, (_, velocity)| sum + *velocity) / count;
+    let (covariance, variance) = samples.iter().fold((T::zero(), T::zero()), |(covariance, variance), (time, velocity)| (
+        covariance + ((*time - mean_time) * (*velocity - mean_velocity)),
+        variance + (*time - mean_time).powi(2)
+    ));
+    let slope = covariance / variance;
+    if slope.is_nan() || slope <= num::zero(){return None}
+    let intercept = mean_time - (mean_velocity / slope);
+    Some(IdentifiedAxis{
+        plant_gain: slope / amplitude,
+        dead_time: intercept.max(delta_time),
+    })
+}
+
+
+
+
+
+/// linear-quadratic regulator treating the ship as a coupled 6 dof double integrator. state is position error and
+/// velocity error, input is force and torque, so heavier ships get gentler gains for the same weights. the discrete
+/// riccati equation is solved once, up front, for the rate the controller runs at. the gains are only right when
+/// the controller is run every delta_time seconds, so it needs a fixed timestep
+pub struct LqrController<T>{
+    position_weight: ControlAxis<Dimension3<T>>,
+    velocity_weight: ControlAxis<Dimension3<T>>,
+    effort_weight: ControlAxis<Dimension3<T>>,
+    delta_time: T,
+    // mass and inertia tensor the gains were solved for
+    design_mass: T,
+    design_inertia_tensor: [[T; 3]; 3],
+    // 6x6, force and torque to linear and rotational acceleration
+    inverse_mass_matrix: Vec<Vec<T>>,
+    // 6x12, position and velocity error to force and torque
+    gain: Vec<Vec<T>>,
+}
+impl<T> LqrController<T>
+    where T: Float
+{
+    /// weights are the diagonals of Q(position_weight, velocity_weight) and R(effort_weight, on force and torque).
+    /// effort weights must be positive, state weights non negative
+    pub fn new(
+        mass_properties: &MassProperties<T>,
+        position_weight: ControlAxis<Dimension3<T>>,
+        velocity_weight: ControlAxis<Dimension3<T>>,
+        effort_weight: ControlAxis<Dimension3<T>>,
+        delta_time: T,
+    ) -> Result<Self, FcsError<'static>>{
+        if control_axis_to_array(&position_weight).iter().chain(control_axis_to_array(&velocity_weight).iter()).any(|weight| weight.is_nan() || *weight < num::zero()){
+            return Err(FcsError::new("LQR state weights must be non negative."))
+        }
+        if control_axis_to_array(&effort_weight).iter().any(|weight| weight.is_nan() || *weight <= num::zero()){
+            return Err(FcsError::new("LQR effort weights must be positive."))
+        }
+        if delta_time.is_nan() || delta_time <= num::zero(){
+            return Err(FcsError::new("LQR delta time must be positive."))
+        }
+        let mut controller = Self{
+            position_weight,
+            velocity_weight,
+            effort_weight,
+            delta_time,
+            design_mass: mass_properties.mass(),
+            design_inertia_tensor: *mass_properties.inertia_tensor(),
+            inverse_mass_matrix: Vec::new(),
+            gain: Vec::new(),
+        };
+        controller.set_mass_properties(mass_properties)?;
+        Ok(controller)
+    }
+
+    pub fn position_weight(&self) -> &ControlAxis<Dimension3<T>>{&self.position_weight}
+    pub fn velocity_weight(&self) -> &ControlAxis<Dimension3<T>>{&self.velocity_weight}
+    pub fn effort_weight(&self) -> &ControlAxis<Dimension3<T>>{&self.effort_weight}
+    /// the timestep the gains were discretised for
+    pub fn delta_time(&self) -> T{self.delta_time}
+    pub fn design_mass(&self) -> T{self.design_mass}
+    pub fn design_inertia_tensor(&self) -> &[[T; 3]; 3]{&self.design_inertia_tensor}
+
+    /// true if mass, or any element of the inertia tensor, is more than tolerance(a fraction) away from what the
+    /// gains were solved for. inertia tensor elements are compared against its largest element
+    pub fn mass_properties_changed(&self, mass_properties: &MassProperties<T>, tolerance: T) -> bool{
+        if (mass_properties.mass() - self.design_mass).abs() > tolerance * self.design_mass{return true}
+        let scale = self.design_inertia_tensor.iter().flatten().fold(T::zero(), |scale, value| scale.max(value.abs()));
+        self.design_inertia_tensor.iter().flatten()
+            .zip(mass_properties.inertia_tensor().iter().flatten())
+            .any(|(design, current)| (*current - *design).abs() > tolerance * scale)
+    }
+
+    /// 6x12 gain matrix. rows are force x, y, z then torque x, y, z. columns are position error then velocity error,
+    /// linear x, y, z then rotational x, y, z
+    pub fn gain(&self) -> &[Vec<T>]{&self.gain}
+
+    /// re-solves the gains for new mass properties
+    pub fn set_mass_properties(&mut self, mass_properties: &MassProperties<T>) -> Result<(), FcsError<'static>>{
+        let mut inverse_mass_matrix = vec![vec![T::zero(); 6]; 6];
+        for (axis, row) in inverse_mass_matrix.iter_mut().take(3).enumerate(){
+            row[axis] = T::one() / mass_properties.mass();
+        }
+        // columns of I⁻¹, from the angular acceleration of unit torques
+        for column in 0..3{
+            let mut unit_torque = [T::zero(); 3];
+            unit_torque[column] = T::one();
+            let angular_acceleration = mass_properties.angular_acceleration(&Dimension3::new(unit_torque[0], unit_torque[1], unit_torque[2]));
+            inverse_mass_matrix[3][3 + column] = angular_acceleration.x();
+            inverse_mass_matrix[4][3 + column] = angular_acceleration.y();
+            inverse_mass_matrix[5][3 + column] = angular_acceleration.z();
+        }
+
+        let gain = solve_lqr_gain(
+            &inverse_mass_matrix, 
+            &control_axis_to_array(&self.position_weight), 
+            &control_axis_to_array(&self.velocity_weight), 
+            &control_axis_to_array(&self.effort_weight), 
+            self.delta_time
+        ).ok_or(FcsError::new("LQR riccati equation did not converge."))?;
+        self.inverse_mass_matrix = inverse_mass_matrix;
+        self.gain = gain;
+        self.design_mass = mass_properties.mass();
+        self.design_inertia_tensor = *mass_properties.inertia_tensor();
+        Ok(())
+    }
+}
+
+/// K = (R + BᵀPB)⁻¹BᵀPA, with P from the structure-preserving doubling algorithm, which converges in a few dozen
+/// iterations where plain riccati iteration can take thousands
+fn solve_lqr_gain<T>(
+    inverse_mass_matrix: &[Vec<T>],
+    position_weight: &[T; 6],
+    velocity_weight: &[T; 6],
+    effort_weight: &[T; 6],
+    delta_time: T,
+) -> Option<Vec<Vec<T>>>
+    where T: Float
+{
+    let half: T = num::cast(0.5).unwrap();
+    let tolerance: T = num::cast(1e-10).unwrap();
+    // x(k+1) = A·x(k) + B·u(k), x = [position error, velocity error]
+    let mut a = optimization::identity_matrix(12);
+    let mut b = vec![vec![T::zero(); 6]; 12];
+    for row in 0..6{
+        a[row][6 + row] = delta_time;
+        for column in 0..6{
+            b[row][column] = half * delta_time * delta_time * inverse_mass_matrix[row][column];
+            b[6 + row][column] = delta_time * inverse_mass_matrix[row][column];
+        }
+    }
+    let mut q = vec![vec![T::zero(); 12]; 12];
+    let mut r = vec![vec![T::zero(); 6]; 6];
+    for axis in 0..6{
+        q[axis][axis] = position_weight[axis];
+        q[6 + axis][6 + axis] = velocity_weight[axis];
+        r[axis][axis] = effort_weight[axis];
+    }
+
+    let b_transpose = optimization::transpose(&b);
+    let mut a_k = a.clone();
+    let mut g_k = optimization::multiply(&optimization::multiply(&b, &optimization::invert(&r)?), &b_transpose);
+    let mut h_k = q;
+    let identity = optimization::identity_matrix(12);
+    let mut converged = false;
+    for _ in 0..64{
+        let w = optimization::invert(&optimization::add(&identity, &optimization::multiply(&g_k, &h_k)))?;
+        let a_w = optimization::multiply(&a_k, &w);
+        let a_transpose = optimization::transpose(&a_k);
+        let h_next = optimization::add(&h_k, &optimization::multiply(&optimization::multiply(&a_transpose, &h_k), &optimization::multiply(&w, &a_k)));
+        let g_next = optimization::add(&g_k, &optimization::multiply(&optimization::multiply(&a_w, &g_k), &a_transpose));
+        let a_next = optimization::multiply(&a_w, &a_k);
+
+        let scale = h_next.iter().flatten().fold(T::one(), |max, value| max.max(value.abs()));
+        let change = h_next.iter().flatten().zip(h_k.iter().flatten()).fold(T::zero(), |max, (next, current)| max.max((*next - *current).abs()));
+        a_k = a_next;
+        g_k = g_next;
+        h_k = h_next;
+        if change <= tolerance * scale{
+            converged = true;
+            break;
+        }
+    }
+    if !converged || h_k.iter().flatten().any(|value| !value.is_finite()){return None}
+
+    let b_transpose_p = optimization::multiply(&b_transpose, &h_k);
+    let effort = optimization::add(&r, &optimization::multiply(&b_transpose_p, &b));
+    Some(optimization::multiply(&optimization::invert(&effort)?, &optimization::multiply(&b_transpose_p, &a)))
+}
+
+/// state feedback acceleration, clamped to available acceleration. a drop in replacement for calculate, that also
+/// needs velocity. meant to be called every controller.delta_time() seconds, which is what the gains assume
+pub fn calculate_lqr<T>(
+    goal_position: &ControlAxis<Dimension3<T>>,
+    goal_velocity: &ControlAxis<Dimension3<T>>,
+    position: &ControlAxis<Dimension3<T>>,
+    velocity: &ControlAxis<Dimension3<T>>,
+    controller: &LqrController<T>,
+    available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    let mut state = [T::zero(); 12];
+    let (goal_position, position) = (control_axis_to_array(goal_position), control_axis_to_array(position));
+    let (goal_velocity, velocity) = (control_axis_to_array(goal_velocity), control_axis_to_array(velocity));
+    for axis in 0..6{
+        state[axis] = position[axis] - goal_position[axis];
+        state[6 + axis] = velocity[axis] - goal_velocity[axis];
+    }
+    // u = -K·x
+    let wrench: Vec<T> = optimization::multiply_vector(&controller.gain, &state).iter().map(|effort| -*effort).collect();
+    let acceleration = optimization::multiply_vector(&controller.inverse_mass_matrix, &wrench);
+    let available = control_axis_to_array(available_acceleration);
+    let clamped: [T; 6] = std::array::from_fn(|axis| num::clamp(acceleration[axis], -available[axis].negative(), available[axis].positive()));
+    array_to_control_axis(&clamped)
+}
+
+
+
+
+
+/// model predictive controller. plans a short horizon of accelerations for each axis, within the positive and
+/// negative limits of available acceleration and within max velocity, and applies the first one. unlike PID, it sees
+/// that a weak reverse thruster needs braking to start early. axes are independent double integrators, so each is
+/// its own small quadratic program
+pub struct MpcController<T>{
+    // number of steps planned ahead. horizon * time_step should cover the time it takes to stop
+    horizon: usize,
+    // length of a planning step(s), can be coarser than the frame rate
+    time_step: T,
+    position_weight: ControlAxis<Dimension3<T>>,
+    velocity_weight: ControlAxis<Dimension3<T>>,
+    effort_weight: ControlAxis<Dimension3<T>>,
+    max_iterations: usize,
+    // one per axis. cost and constraint matrices only depend on weights, horizon and time step, so they are set up once
+    programs: Vec<optimization::QuadraticProgram<T>>,
+    // last plan for each axis, used to warm start the next solve
+    plans: [Vec<T>; 6],
+}
+impl<T> MpcController<T>
+    where T: Float
+{
+    /// weights are per step costs on squared position error, velocity error, and acceleration. effort weights must be
+    /// positive, state weights non negative
+    pub fn new(
+        horizon: usize,
+        time_step: T,
+        position_weight: ControlAxis<Dimension3<T>>,
+        velocity_weight: ControlAxis<Dimension3<T>>,
+        effort_weight: ControlAxis<Dimension3<T>>,
+    ) -> Result<Self, FcsError<'static>>{
+        if horizon == 0{
+            return Err(FcsError::new("MPC horizon must be at least one step."))
+        }
+        if time_step.is_nan() || time_step <= num::zero(){
+            return Err(FcsError::new("MPC time step must be positive."))
+        }
+        if control_axis_to_array(&position_weight).iter().chain(control_axis_to_array(&velocity_weight).iter()).any(|weight| weight.is_nan() || *weight < num::zero()){
+            return Err(FcsError::new("MPC state weights must be non negative."))
+        }
+        if control_axis_to_array(&effort_weight).iter().any(|weight| weight.is_nan() || *weight <= num::zero()){
+            return Err(FcsError::new("MPC effort weights must be positive."))
+        }
+        let (position_weights, velocity_weights) = (control_axis_to_array(&position_weight), control_axis_to_array(&velocity_weight));
+        let effort_weights = control_axis_to_array(&effort_weight);
+        let mut programs = Vec::with_capacity(6);
+        for axis in 0..6{
+            let (p, a) = axis_program(position_weights[axis], velocity_weights[axis], effort_weights[axis], horizon, time_step);
+            programs.push(optimization::QuadraticProgram::new(p, a).ok_or(FcsError::new("MPC cost matrix is singular."))?);
+        }
+        Ok(Self{
+            horizon,
+            time_step,
+            position_weight,
+            velocity_weight,
+            effort_weight,
+            max_iterations: 200,
+            programs,
+            plans: std::array::from_fn(|_| vec![T::zero(); horizon]),
+        })
+    }
+
+    pub fn horizon(&self) -> usize{self.horizon}
+    pub fn time_step(&self) -> T{self.time_step}
+    pub fn position_weight(&self) -> &ControlAxis<Dimension3<T>>{&self.position_weight}
+    pub fn velocity_weight(&self) -> &ControlAxis<Dimension3<T>>{&self.velocity_weight}
+    pub fn effort_weight(&self) -> &ControlAxis<Dimension3<T>>{&self.effort_weight}
+
+    pub fn max_iterations(&self) -> usize{self.max_iterations}
+
+    /// solver iterations per axis per call. defaults to 200, which is plenty when called every frame
+    pub fn set_max_iterations(&mut self, max_iterations: usize){
+        self.max_iterations = max_iterations;
+    }
+
+    /// planned accelerations for an axis, from the last call. linear x, y, z then rotational x, y, z
+    pub fn plan(&self, axis: usize) -> &[T]{&self.plans[axis]}
+}
+
+/// first acceleration of the optimal plan for each axis. goal velocity is how fast the goal is moving, for rendezvous
+/// with a moving target. zero for station-keeping
+pub fn calculate_mpc<T>(
+    goal_position: &ControlAxis<Dimension3<T>>,
+    goal_velocity: &ControlAxis<Dimension3<T>>,
+    position: &ControlAxis<Dimension3<T>>,
+    velocity: &ControlAxis<Dimension3<T>>,
+    controller: &mut MpcController<T>,
+    max_velocity: &ControlAxis<Dimension3<T>>,
+    available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    let (goal_position, goal_velocity) = (control_axis_to_array(goal_position), control_axis_to_array(goal_velocity));
+    let (position, velocity) = (control_axis_to_array(position), control_axis_to_array(velocity));
+    let (max_velocity, available) = (control_axis_to_array(max_velocity), control_axis_to_array(available_acceleration));
+    let (position_weight, velocity_weight) = (control_axis_to_array(&controller.position_weight), control_axis_to_array(&controller.velocity_weight));
+    let mut acceleration = [T::zero(); 6];
+    for axis in 0..6{
+        let max_positive = available[axis].positive().max(num::zero());
+        let max_negative = available[axis].negative().max(num::zero());
+        let (q, l, u) = axis_bounds(
+            position[axis] - goal_position[axis],
+            velocity[axis] - goal_velocity[axis],
+            velocity[axis],
+            max_velocity[axis].abs(),
+            max_positive,
+            max_negative,
+            position_weight[axis],
+            velocity_weight[axis],
+            controller.horizon,
+            controller.time_step
+        );
+        // last plan moved one step along
+        let previous = &controller.plans[axis];
+        let mut warm_start: Vec<T> = previous.iter().skip(1).copied().collect();
+        warm_start.push(previous[previous.len() - 1]);
+
+        let plan = controller.programs[axis].solve(&q, &l, &u, Some(&warm_start), controller.max_iterations);
+        acceleration[axis] = num::clamp(plan[0], -max_negative, max_positive);
+        controller.plans[axis] = plan;
+    }
+    array_to_control_axis(&acceleration)
+}
+
+/// errors after k steps are linear in the planned accelerations a(j):
+/// velocity error(k) = velocity error + dt * Σ(j<k) a(j)
+/// position error(k) = position error + k * dt * velocity error + dt² * Σ(j<k) (k - j - ½) * a(j)
+/// returned as rows of coefficients on a, for k = 1 to horizon
+fn prediction_rows<T>(horizon: usize, time_step: T) -> (Vec<Vec<T>>, Vec<Vec<T>>)
+    where T: Float
+{
+    let half: T = num::cast(0.5).unwrap();
+    let position_rows = (1..=horizon).map(|step| {
+        let steps = T::from(step).unwrap();
+        (0..horizon).map(|j| if j < step{(steps - T::from(j).unwrap() - half) * time_step * time_step}else{T::zero()}).collect()
+    }).collect();
+    let velocity_rows = (1..=horizon).map(|step| {
+        (0..horizon).map(|j| if j < step{time_step}else{T::zero()}).collect()
+    }).collect();
+    (position_rows, velocity_rows)
+}
+
+/// quadratic cost on the planned accelerations, and constraint rows: each acceleration, then velocity after each step
+fn axis_program<T>(
+    position_weight: T,
+    velocity_weight: T,
+    effort_weight: T,
+    horizon: usize,
+    time_step: T,
+) -> (Vec<Vec<T>>, Vec<Vec<T>>)
+    where T: Float
+{
+    let two = T::one() + T::one();
+    let (position_rows, velocity_rows) = prediction_rows(horizon, time_step);
+    let mut p = vec![vec![T::zero(); horizon]; horizon];
+    for (position_row, velocity_row) in position_rows.iter().zip(velocity_rows.iter()){
+        for row in 0..horizon{
+            for column in 0..horizon{
+                p[row][column] = p[row][column] + (two * (
+                    (position_weight * position_row[row] * position_row[column])
+                    + (velocity_weight * velocity_row[row] * velocity_row[column])
+                ));
+            }
+        }
+    }
+    for (step, row) in p.iter_mut().enumerate(){
+        row[step] = row[step] + (two * effort_weight);
+    }
+
+    let mut a = optimization::identity_matrix(horizon);
+    a.extend(velocity_rows);
+    (p, a)
+}
+
+/// linear cost, and bounds for the rows from axis_program. if the ship is already over max velocity, the velocity
+/// limit is loosened to the fastest it could possibly slow down, so the problem stays feasible
+#[allow(clippy::too_many_arguments)]
+fn axis_bounds<T>(
+    position_error: T,
+    velocity_error: T,
+    velocity: T,
+    max_velocity: T,
+    max_positive: T,
+    max_negative: T,
+    position_weight: T,
+    velocity_weight: T,
+    horizon: usize,
+    time_step: T,
+) -> (Vec<T>, Vec<T>, Vec<T>)
+    where T: Float
+{
+    let two = T::one() + T::one();
+    let (position_rows, velocity_rows) = prediction_rows(horizon, time_step);
+    let mut q = vec![T::zero(); horizon];
+    for (step, (position_row, velocity_row)) in position_rows.iter().zip(velocity_rows.iter()).enumerate(){
+        let position_constant = position_error + (T::from(step + 1).unwrap() * time_step * velocity_error);
+        for (row, q) in q.iter_mut().enumerate(){
+            *q = *q + (two * (
+                (position_weight * position_row[row] * position_constant)
+                + (velocity_weight * velocity_row[row] * velocity_error)
+            ));
+        }
+    }
+
+    let mut l = vec![-max_negative; horizon];
+    let mut u = vec![max_positive; horizon];
+    for step in 1..=horizon{
+        let elapsed = T::from(step).unwrap() * time_step;
+        l.push((-max_velocity).min(velocity + (elapsed * max_positive)) - velocity);
+        u.push(max_velocity.max(velocity - (elapsed * max_negative)) - velocity);
+    }
+    (q, l, u)
+}
+
+
+
+
+
+#[test]
+fn test_pid_controller_anti_windup(){
+    // held far from the goal for a long time, as if the ship couldn't move
+    let mut free = PidController::new(0.1, 0.1, 0.0);
+    let mut conditional = PidController::new(0.1, 0.1, 0.0);
+    conditional.set_anti_windup(AntiWindup::ConditionalIntegration);
+    let mut back_calculation = PidController::new(0.1, 0.1, 0.0);
+    back_calculation.set_anti_windup(AntiWindup::BackCalculation{tracking_gain: 1.0});
+    for _ in 0..100{
+        assert_eq!(free.calculate(100.0, 0.0, -1.0, 1.0, 1.0), 1.0);
+        assert_eq!(conditional.calculate(100.0, 0.0, -1.0, 1.0, 1.0), 1.0);
+        assert_eq!(back_calculation.calculate(100.0, 0.0, -1.0, 1.0, 1.0), 1.0);
+    }
+    assert!(free.integral_term() > 900.0);
+    assert!(conditional.integral_term().abs() < 0.001);
+    assert!(back_calculation.integral_term() < 1.0);
+
+    // overshoot the goal slightly. the wound up integrator keeps pushing the wrong way
+    assert_eq!(free.calculate(100.0, 101.0, -1.0, 1.0, 1.0), 1.0);
+    assert!(conditional.calculate(100.0, 101.0, -1.0, 1.0, 1.0) < 0.0);
+    assert!(back_calculation.calculate(100.0, 101.0, -1.0, 1.0, 1.0) < 0.0);
+}
+
+#[test]
+fn test_calculate_limited_leaves_feedforward_authority(){
+    let pid = || {
+        let mut controller = PidController::new(0.1, 0.1, 0.0);
+        controller.set_anti_windup(AntiWindup::ConditionalIntegration);
+        controller
+    };
+    let mut controllers = ControlAxis::new(
+        Dimension3::new(pid(), pid(), pid()),
+        Dimension3::new(pid(), pid(), pid())
+    );
+    let available_acceleration = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(10.0, 10.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    );
+    // feedforward already uses all of +x, and 8 of +y
+    let feedforward_acceleration = ControlAxis::new(Dimension3::new(10.0, 8.0, 0.0), Dimension3::default(0.0));
+    // close enough that the controller alone wouldn't saturate at full available acceleration
+    let goal_position = ControlAxis::new(Dimension3::new(5.0, 5.0, 0.0), Dimension3::default(0.0));
+    let position = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    for _ in 0..100{
+        let acceleration = calculate_limited(
+            &goal_position, 
+            &position, 
+            &mut controllers, 
+            &available_acceleration, 
+            &feedforward_acceleration, 
+            1.0
+        );
+        assert!(acceleration.linear().x().abs() < 0.001);
+        assert!((acceleration.linear().y() - 2.0).abs() < 0.001);
+    }
+    // nothing was left for the controller to use, so its integrator didn't wind up
+    assert!(controllers.linear_mut().x_mut().integral_term().abs() < 0.001);
+    assert!(controllers.linear_mut().y_mut().integral_term().abs() < 0.001);
+}
+
+#[test]
+fn test_pid_controller_zero_delta_time(){
+    let mut controller = PidController::new(1.0, 0.1, 1.0);
+    controller.set_derivative_filter_time_constant(0.0);
+    let _ = controller.calculate(1.0, 0.0, -10.0, 10.0, 1.0);
+    let integral_term = controller.integral_term();
+    // a paused frame outputs P + I, and doesn't touch the integrator or derivative history
+    assert!((controller.calculate(1.0, 0.5, -10.0, 10.0, 0.0) - (0.5 + integral_term)).abs() < 0.001);
+    assert!((controller.integral_term() - integral_term).abs() < 0.001);
+    let output = controller.calculate(1.0, 0.5, -10.0, 10.0, 1.0);
+    assert!(output.is_finite());
+    // derivative is taken against the last real frame, error fell by 0.5 over one second
+    assert!((output - (0.5 + controller.integral_term() - 0.5)).abs() < 0.001);
+}
+
+#[test]
+fn test_pid_controller_derivative(){
+    let mut on_error = PidController::new(0.0, 0.0, 1.0);
+    let mut on_measurement = PidController::new(0.0, 0.0, 1.0);
+    on_measurement.set_derivative_on_measurement(true);
+    let _ = on_error.calculate(0.0, 0.0, -10.0, 10.0, 1.0);
+    let _ = on_measurement.calculate(0.0, 0.0, -10.0, 10.0, 1.0);
+    // goal steps while the ship is still
+    assert!((on_error.calculate(5.0, 0.0, -10.0, 10.0, 1.0) - 5.0).abs() < 0.001);
+    assert!(on_measurement.calculate(5.0, 0.0, -10.0, 10.0, 1.0).abs() < 0.001);
+
+    // filter with time constant equal to delta time passes half of a step change
+    let mut filtered = PidController::new(0.0, 0.0, 1.0);
+    filtered.set_derivative_on_measurement(true);
+    filtered.set_derivative_filter_time_constant(1.0);
+    let _ = filtered.calculate(0.0, 0.0, -10.0, 10.0, 1.0);
+    assert!((filtered.calculate(0.0, -4.0, -10.0, 10.0, 1.0) - 2.0).abs() < 0.001);
+    assert!((filtered.calculate(0.0, -8.0, -10.0, 10.0, 1.0) - 3.0).abs() < 0.001);
+}
+
+#[test]
+fn test_cascaded_pid_respects_max_velocity(){
+    let cascaded = || CascadedPid::new(PidController::new(1.0, 0.0, 0.0), PidController::new(2.0, 0.0, 0.0));
+    let mut controllers = ControlAxis::new(
+        Dimension3::new(cascaded(), cascaded(), cascaded()),
+        Dimension3::new(cascaded(), cascaded(), cascaded())
+    );
+    let goal_position = ControlAxis::new(Dimension3::new(100.0, 0.0, 0.0), Dimension3::default(0.0));
+    let max_velocity = ControlAxis::new(Dimension3::default(5.0), Dimension3::default(1.0));
+    let available_acceleration = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(10.0, 10.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    );
+    // double integrator
+    let delta_time = 0.1;
+    let mut position = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    let mut velocity = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    let mut peak_velocity: f64 = 0.0;
+    for _ in 0..1_000{
+        let acceleration = calculate_cascaded(
+            &goal_position, 
+            &ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0)), 
+            &position, 
+            &velocity, 
+            &mut controllers, 
+            &max_velocity, 
+            &available_acceleration, 
+            &ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0)), 
+            delta_time
+        );
+        assert!(acceleration.linear().x().abs() <= 10.0);
+        velocity = crate::map_control_axes(&velocity, &acceleration, |velocity, acceleration| velocity + (acceleration * delta_time));
+        position = crate::map_control_axes(&position, &velocity, |position, velocity| position + (velocity * delta_time));
+        peak_velocity = peak_velocity.max(velocity.linear().x());
+    }
+    // small overshoot from the inner loop lagging the setpoint
+    assert!(peak_velocity < 5.5);
+    assert!((position.linear().x() - 100.0).abs() < 0.01);
+}
+
+#[test]
+fn test_gain_schedule_interpolates_on_mass(){
+    let gains = |kp: f64| ControlAxis::new(Dimension3::default(PidGains::new(kp, 0.0, kp)), Dimension3::default(PidGains::new(kp, 0.0, 0.0)));
+    let schedule = GainSchedule::new(
+        ScheduleVariable::Mass, 
+        vec![
+            // full, listed first on purpose
+            GainSchedulePoint::new(ControlAxis::new(Dimension3::default(10_000.0), Dimension3::default(10_000.0)), gains(3.0)),
+            GainSchedulePoint::new(ControlAxis::new(Dimension3::default(2_000.0), Dimension3::default(2_000.0)), gains(1.0)),
+        ]
+    ).unwrap();
+    let available_acceleration = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(1.0, 1.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    );
+    let inertia_tensor = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    let half_full = MassProperties::new(6_000.0, Dimension3::default(0.0), inertia_tensor).unwrap();
+    let scheduled = schedule.gains(&half_full, &available_acceleration);
+    assert!((scheduled.linear().x().kp() - 2.0).abs() < 0.001);
+    assert!((scheduled.linear().x().kd() - 2.0).abs() < 0.001);
+    assert!((scheduled.rotational().z().kp() - 2.0).abs() < 0.001);
+
+    // held at the end of the table
+    let overloaded = MassProperties::new(20_000.0, Dimension3::default(0.0), inertia_tensor).unwrap();
+    assert!((schedule.gains(&overloaded, &available_acceleration).linear().y().kp() - 3.0).abs() < 0.001);
+
+    assert!(GainSchedule::<f64>::new(ScheduleVariable::Mass, Vec::new()).is_err());
+}
+
+#[cfg(test)]
+/// double integrator with thruster dead time, the same on every axis
+fn delayed_plant(dead_time_steps: usize) -> impl FnMut(&ControlAxis<Dimension3<f64>>, f64) -> ControlAxis<Dimension3<f64>>{
+    let mut pending: std::collections::VecDeque<[f64; 6]> = std::iter::repeat_n([0.0; 6], dead_time_steps).collect();
+    let mut velocity = [0.0; 6];
+    move |acceleration, delta_time| {
+        pending.push_back(control_axis_to_array(acceleration));
+        let delivered = pending.pop_front().unwrap();
+        for axis in 0..6{
+            velocity[axis] += delivered[axis] * delta_time;
+        }
+        array_to_control_axis(&velocity)
+    }
+}
+
+#[test]
+fn test_identify_axes_recovers_plant(){
+    let available_acceleration = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(10.0, 10.0)),
+        Dimension3::default(AxisContribution::new(2.0, 2.0))
+    );
+    // 0.1s dead time
+    let step = identify_axes(delayed_plant(10), &available_acceleration, Excitation::Step{amplitude: 0.5}, 5.0, 0.01).unwrap();
+    assert!((step[0].plant_gain() - 10.0).abs() < 0.01);
+    assert!((step[0].dead_time() - 0.1).abs() < 0.01);
+    assert!((step[5].plant_gain() - 2.0).abs() < 0.01);
+
+    let relay = identify_axes(delayed_plant(10), &available_acceleration, Excitation::Relay{amplitude: 0.5}, 5.0, 0.01).unwrap();
+    assert!((relay[0].plant_gain() - 10.0).abs() < 0.5);
+    assert!((relay[0].dead_time() - 0.1).abs() < 0.02);
+
+    let no_negative = ControlAxis::new(
+        Dimension3::new(AxisContribution::new(10.0, 0.0), AxisContribution::new(10.0, 10.0), AxisContribution::new(10.0, 10.0)),
+        Dimension3::default(AxisContribution::new(2.0, 2.0))
+    );
+    let error = identify_axes(delayed_plant(10), &no_negative, Excitation::Step{amplitude: 0.5}, 5.0, 0.01).err().unwrap();
+    assert_eq!(error.field(), Some("linear.x"));
+}
+
+#[test]
+fn test_auto_tune_gains_settle_plant(){
+    let available_acceleration = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(10.0, 10.0)),
+        Dimension3::default(AxisContribution::new(2.0, 2.0))
+    );
+    for (excitation, rule) in [
+        (Excitation::Relay{amplitude: 0.5}, TuningRule::ZieglerNichols),
+        (Excitation::Relay{amplitude: 0.5}, TuningRule::TyreusLuyben),
+        (Excitation::Step{amplitude: 0.5}, TuningRule::Imc{closed_loop_time_constant: 0.2}),
+    ]{
+        let mut pids = auto_tune(delayed_plant(10), &available_acceleration, excitation, rule, 5.0, 0.01).unwrap();
+        let mut plant = delayed_plant(10);
+        let goal_position = ControlAxis::new(Dimension3::new(10.0, 0.0, 0.0), Dimension3::new(0.0, 0.0, 1.0));
+        let mut position = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+        for _ in 0..2_000{
+            let acceleration = calculate(&goal_position, &position, &mut pids, &available_acceleration, 0.01);
+            let velocity = plant(&acceleration, 0.01);
+            position = crate::map_control_axes(&position, &velocity, |position, velocity| position + (velocity * 0.01));
+        }
+        assert!((position.linear().x() - 10.0).abs() < 0.05);
+        assert!((position.rotational().z() - 1.0).abs() < 0.05);
+    }
+}
+
+#[test]
+fn test_lqr_settles_coupled_double_integrator(){
+    let inertia_tensor = [[1_000.0, 200.0, 0.0], [200.0, 2_000.0, 0.0], [0.0, 0.0, 3_000.0]];
+    let mass_properties = MassProperties::new(1_000.0, Dimension3::default(0.0), inertia_tensor).unwrap();
+    let weights = |weight: f64| ControlAxis::new(Dimension3::default(weight), Dimension3::default(weight));
+    let controller = LqrController::new(&mass_properties, weights(1.0), weights(1.0), weights(1e-6), 0.01).unwrap();
+    let available_acceleration = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(100.0, 100.0)),
+        Dimension3::default(AxisContribution::new(100.0, 100.0))
+    );
+    let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    let goal_position = ControlAxis::new(Dimension3::new(1.0, -2.0, 0.5), Dimension3::new(0.5, 0.0, -0.5));
+    let mut position = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    let mut velocity = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    for _ in 0..2_000{
+        let acceleration = calculate_lqr(&goal_position, &zero, &position, &velocity, &controller, &available_acceleration);
+        position = crate::map_control_axes(&position, &velocity, |position, velocity| position + (velocity * 0.01));
+        velocity = crate::map_control_axes(&velocity, &acceleration, |velocity, acceleration| velocity + (acceleration * 0.01));
+    }
+    let error = crate::map_control_axes(&goal_position, &position, |goal, position| (goal - position).abs());
+    for error in control_axis_to_array(&error){
+        assert!(error < 0.001);
+    }
+
+    // same weights on a heavier ship, so the same force gives less acceleration
+    let heavy = MassProperties::new(10_000.0, Dimension3::default(0.0), inertia_tensor).unwrap();
+    let heavy_controller = LqrController::new(&heavy, weights(1.0), weights(1.0), weights(1e-6), 0.01).unwrap();
+    let light = calculate_lqr(&goal_position, &zero, &zero, &zero, &controller, &available_acceleration);
+    let heavy = calculate_lqr(&goal_position, &zero, &zero, &zero, &heavy_controller, &available_acceleration);
+    assert!(heavy.linear().x() < light.linear().x());
+    assert!(heavy.linear().x() > 0.0);
+
+    assert!(LqrController::new(&mass_properties, weights(1.0), weights(1.0), weights(0.0), 0.01).is_err());
+}
+
+#[test]
+fn test_mpc_plans_around_weak_reverse_thrust(){
+    let weights = |weight: f64| ControlAxis::new(Dimension3::default(weight), Dimension3::default(weight));
+    let mut controller = MpcController::new(20, 0.5, weights(1.0), weights(1.0), weights(0.01)).unwrap();
+    // big main engine, weak reverse thrusters
+    let available_acceleration = ControlAxis::new(
+        Dimension3::new(AxisContribution::new(10.0, 1.0), AxisContribution::new(1.0, 1.0), AxisContribution::new(1.0, 1.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    );
+    let max_velocity = ControlAxis::new(Dimension3::default(5.0), Dimension3::default(1.0));
+    let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    let goal_position = ControlAxis::new(Dimension3::new(50.0, 0.0, 0.0), Dimension3::default(0.0));
+
+    // planning step matches the frame rate, so the plan is exact
+    let delta_time = 0.5;
+    let mut position = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    let mut velocity = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+    let mut peak_position: f64 = 0.0;
+    for _ in 0..120{
+        let acceleration = calculate_mpc(&goal_position, &zero, &position, &velocity, &mut controller, &max_velocity, &available_acceleration);
+        assert!(acceleration.linear().x() <= 10.0 && acceleration.linear().x() >= -1.0);
+        let travelled = crate::map_control_axes(&velocity, &acceleration, |velocity, acceleration| (velocity * delta_time) + (0.5 * acceleration * delta_time * delta_time));
+        position = crate::map_control_axes(&position, &travelled, |position, travelled| position + travelled);
+        velocity = crate::map_control_axes(&velocity, &acceleration, |velocity, acceleration| velocity + (acceleration * delta_time));
+        assert!(velocity.linear().x() <= 5.0 + 0.05);
+        peak_position = peak_position.max(position.linear().x());
+    }
+    // started braking early enough, with only 1m/s^2 to stop from 5m/s. braking at the last moment, as symmetric
+    // limits would suggest, overshoots by over 10m. the quadratic cost trades a little overshoot for a faster approach
+    assert!(peak_position < 51.0);
+    assert!((position.linear().x() - 50.0).abs() < 0.1);
+    assert!(velocity.linear().x().abs() < 0.05);
+
+    assert!(MpcController::new(0, 0.5, weights(1.0), weights(1.0), weights(0.01)).is_err());
+}
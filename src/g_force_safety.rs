@@ -8,6 +8,21 @@
 use game_utils::dimension3::Dimension3;
 use game_utils::control_axis::{ControlAxis, AxisContribution};
 use num::Float;
+use crate::cross3;
+
+
+
+
+
+/// how g-force safety limits are applied to linear acceleration
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GSafetyMode{
+    /// each axis is clamped to its own limit. a diagonal maneuver can exceed a single axis limit by up to √3
+    PerAxis,
+    /// the total acceleration at the pilot seat, including that from rotation, is kept inside the ellipsoid whose
+    /// semi-axes are the per axis limits. the whole command is scaled down together, so its direction is kept
+    VectorMagnitude,
+}
 
 
 
@@ -55,4 +70,601 @@ pub fn process<T>(
             ), 
         )
     )
-}
\ No newline at end of file
+}
+
+
+
+/// limits desired acceleration so the acceleration felt at the pilot seat stays within gsafety_max_acceleration.linear()
+/// by vector magnitude, with each direction's limit as a semi-axis(+Gx can be given a larger limit than -Gz, etc.).
+/// rotational acceleration is first clamped per axis to gsafety_max_acceleration.rotational().
+/// angular_velocity is the ship's current rotation, and pilot_seat is relative to the center of mass.
+/// centripetal acceleration from the current rotation can't be scaled away, so if it alone exceeds the limits
+/// only the rotational acceleration that slows the spin is kept
+pub fn process_vector_magnitude<T>(
+    desired_acceleration: &ControlAxis<Dimension3<T>>,
+    gsafety_max_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    angular_velocity: &Dimension3<T>,
+    pilot_seat: &Dimension3<T>,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    let clamped = process(desired_acceleration, gsafety_max_acceleration);
+    let zero = Dimension3::new(num::zero(), num::zero(), num::zero());
+    // part of the seat acceleration that scales with the command, and the part that doesn't
+    let commanded = seat_acceleration(clamped.linear(), clamped.rotational(), &zero, pilot_seat);
+    let centripetal = seat_acceleration(&zero, &zero, angular_velocity, pilot_seat);
+    match largest_scale_within_limits(&commanded, &centripetal, gsafety_max_acceleration.linear(), GSafetyMode::VectorMagnitude){
+        Some(scale) => crate::map_control_axes(&clamped, &clamped, |acceleration, _| acceleration * scale),
+        None => despin(&clamped, angular_velocity)
+    }
+}
+
+/// what is left of desired acceleration once the current rotation alone is past the limits: no linear acceleration,
+/// and only the rotational axes that reduce |ω|. zeroing everything would leave the ship stuck in the spin
+fn despin<T>(desired_acceleration: &ControlAxis<Dimension3<T>>, angular_velocity: &Dimension3<T>) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    let slowing = |acceleration: T, angular_velocity: T| {
+        if acceleration * angular_velocity < num::zero(){acceleration}else{num::zero()}
+    };
+    let rotational = desired_acceleration.rotational();
+    ControlAxis::new(
+        Dimension3::new(num::zero(), num::zero(), num::zero()),
+        Dimension3::new(
+            slowing(rotational.x(), angular_velocity.x()),
+            slowing(rotational.y(), angular_velocity.y()),
+            slowing(rotational.z(), angular_velocity.z())
+        )
+    )
+}
+
+const BISECTION_ITERATIONS: usize = 50;
+
+/// acceleration of a point fixed to the ship, relative to the center of mass.
+/// a + α × r + ω × (ω × r)
+pub fn seat_acceleration<T>(
+    linear_acceleration: &Dimension3<T>,
+    angular_acceleration: &Dimension3<T>,
+    angular_velocity: &Dimension3<T>,
+    seat: &Dimension3<T>,
+) -> Dimension3<T>
+    where T: Float
+{
+    let seat = [seat.x(), seat.y(), seat.z()];
+    let angular_velocity = [angular_velocity.x(), angular_velocity.y(), angular_velocity.z()];
+    let tangential = cross3(&[angular_acceleration.x(), angular_acceleration.y(), angular_acceleration.z()], &seat);
+    let centripetal = cross3(&angular_velocity, &cross3(&angular_velocity, &seat));
+    Dimension3::new(
+        linear_acceleration.x() + tangential[0] + centripetal[0],
+        linear_acceleration.y() + tangential[1] + centripetal[1],
+        linear_acceleration.z() + tangential[2] + centripetal[2]
+    )
+}
+
+/// how far out acceleration is relative to the limit ellipsoid. <= 1 inside, scales linearly with acceleration
+pub fn ellipsoid_load<T>(acceleration: &Dimension3<T>, limits: &Dimension3<AxisContribution<T>>) -> T
+    where T: Float
+{
+    let mut sum: T = num::zero();
+    for (acceleration, limit) in [
+        (acceleration.x(), limits.x()),
+        (acceleration.y(), limits.y()),
+        (acceleration.z(), limits.z()),
+    ]{
+        if acceleration == num::zero(){continue}
+        let limit = if acceleration > num::zero(){limit.positive()}else{limit.negative()};
+        if limit <= num::zero(){return T::infinity()}
+        sum = sum + (acceleration / limit).powi(2);
+    }
+    sum.sqrt()
+}
+
+/// g limits for an occupant or piece of cargo somewhere on the ship
+pub struct GLimitProfile<T>{
+    name: String,
+    // in the ship frame, like the pilot seat. taken relative to the center of mass when processed
+    position: Dimension3<T>,
+    // acceleration(m/s^2) the occupant or cargo can take along each ship axis
+    max_acceleration: Dimension3<AxisContribution<T>>,
+}
+impl<T> GLimitProfile<T>
+    where T: Float
+{
+    pub fn new(name: String, position: Dimension3<T>, max_acceleration: Dimension3<AxisContribution<T>>) -> Self{
+        Self{name, position, max_acceleration}
+    }
+
+    pub fn name(&self) -> &str{&self.name}
+
+    pub fn position(&self) -> &Dimension3<T>{&self.position}
+
+    pub fn set_position(&mut self, position: Dimension3<T>){
+        self.position = position;
+    }
+
+    pub fn max_acceleration(&self) -> &Dimension3<AxisContribution<T>>{&self.max_acceleration}
+}
+
+/// acceleration after limiting to every g limit profile
+pub struct GLimitOutput<T>{
+    acceleration: ControlAxis<Dimension3<T>>,
+    // index of the most restrictive profile, None if no profile needed to limit the command
+    limiting_profile: Option<usize>,
+}
+impl<T> GLimitOutput<T>{
+    pub fn acceleration(&self) -> &ControlAxis<Dimension3<T>>{&self.acceleration}
+    pub fn limiting_profile(&self) -> Option<usize>{self.limiting_profile}
+    pub fn into_parts(self) -> (ControlAxis<Dimension3<T>>, Option<usize>){(self.acceleration, self.limiting_profile)}
+}
+
+/// scales desired acceleration down until every occupant and piece of cargo is within its limits, accounting for the
+/// tangential and centripetal acceleration at its position. every profile rides on the same ship, so the whole command
+/// is scaled rather than clamped per axis. mode decides whether limits apply per axis, or by vector magnitude.
+/// if the current rotation alone puts a profile past its limits, only the rotational acceleration that slows the spin is kept
+pub fn process_profiles<T>(
+    desired_acceleration: &ControlAxis<Dimension3<T>>,
+    g_limit_profiles: &[GLimitProfile<T>],
+    angular_velocity: &Dimension3<T>,
+    center_of_mass: &Dimension3<T>,
+    mode: GSafetyMode,
+) -> GLimitOutput<T>
+    where T: Float
+{
+    let zero = Dimension3::new(num::zero(), num::zero(), num::zero());
+    let mut scale = Some(T::one());
+    let mut limiting_profile = None;
+    for (index, profile) in g_limit_profiles.iter().enumerate(){
+        let position = Dimension3::new(
+            profile.position.x() - center_of_mass.x(),
+            profile.position.y() - center_of_mass.y(),
+            profile.position.z() - center_of_mass.z()
+        );
+        let commanded = seat_acceleration(desired_acceleration.linear(), desired_acceleration.rotational(), &zero, &position);
+        let centripetal = seat_acceleration(&zero, &zero, angular_velocity, &position);
+        let profile_scale = largest_scale_within_limits(&commanded, &centripetal, &profile.max_acceleration, mode);
+        let more_restrictive = match (profile_scale, scale){
+            (None, None) => false,
+            (None, Some(_)) => true,
+            (Some(_), None) => false,
+            (Some(profile_scale), Some(scale)) => profile_scale < scale
+        };
+        if more_restrictive{
+            scale = profile_scale;
+            limiting_profile = Some(index);
+        }
+    }
+
+    GLimitOutput{
+        acceleration: match scale{
+            Some(scale) => crate::map_control_axes(desired_acceleration, desired_acceleration, |acceleration, _| acceleration * scale),
+            None => despin(desired_acceleration, angular_velocity)
+        },
+        limiting_profile,
+    }
+}
+
+/// largest scale in [0, 1] for which commanded * scale + fixed is within limits. None if fixed alone is past them.
+/// load is convex in scale, so the limit is crossed at most once between 0 and 1
+fn largest_scale_within_limits<T>(
+    commanded: &Dimension3<T>,
+    fixed: &Dimension3<T>,
+    limits: &Dimension3<AxisContribution<T>>,
+    mode: GSafetyMode,
+) -> Option<T>
+    where T: Float
+{
+    let load = |scale: T| {
+        let acceleration = Dimension3::new(
+            (commanded.x() * scale) + fixed.x(),
+            (commanded.y() * scale) + fixed.y(),
+            (commanded.z() * scale) + fixed.z()
+        );
+        match mode{
+            GSafetyMode::PerAxis => box_load(&acceleration, limits),
+            GSafetyMode::VectorMagnitude => ellipsoid_load(&acceleration, limits),
+        }
+    };
+
+    if load(num::one()) <= num::one(){return Some(num::one())}
+    if load(num::zero()) > num::one(){return None}
+    let mut low: T = num::zero();
+    let mut high: T = num::one();
+    for _ in 0..BISECTION_ITERATIONS{
+        let middle = (low + high) / (T::one() + T::one());
+        if load(middle) <= num::one(){low = middle}else{high = middle}
+    }
+    Some(low)
+}
+
+/// like ellipsoid_load, but each axis is checked on its own. <= 1 inside
+pub fn box_load<T>(acceleration: &Dimension3<T>, limits: &Dimension3<AxisContribution<T>>) -> T
+    where T: Float
+{
+    let mut load: T = num::zero();
+    for (acceleration, limit) in [
+        (acceleration.x(), limits.x()),
+        (acceleration.y(), limits.y()),
+        (acceleration.z(), limits.z()),
+    ]{
+        if acceleration == num::zero(){continue}
+        let limit = if acceleration > num::zero(){limit.positive()}else{limit.negative()};
+        if limit <= num::zero(){return T::infinity()}
+        load = load.max(acceleration.abs() / limit);
+    }
+    load
+}
+
+
+
+/// pilot condition under +Gz(acceleration toward the head, which drains blood from it)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PilotState{
+    Nominal,
+    /// loss of peripheral and color vision
+    Greyout,
+    /// loss of vision, still conscious
+    Blackout,
+    /// g-induced loss of consciousness. pilot is incapacitated for a while even after the load is removed
+    GLoc,
+}
+
+// g·seconds above tolerance at which each symptom sets in, roughly the brain's few seconds of oxygen reserve
+const GREYOUT_DOSE: f64 = 2.0;
+const BLACKOUT_DOSE: f64 = 4.0;
+const GLOC_DOSE: f64 = 6.0;
+// g·seconds of dose recovered per second while below tolerance
+const RECOVERY_RATE: f64 = 1.0;
+// extra tolerance the cardiovascular reflexes add under sustained load, and how quickly(seconds) they respond
+const BARORECEPTOR_COMPENSATION: f64 = 1.0;
+const BARORECEPTOR_TIME_CONSTANT: f64 = 8.0;
+// seconds a pilot stays unconscious after G-LOC
+const GLOC_INCAPACITATION_TIME: f64 = 15.0;
+
+/// tracks a pilot's +Gz exposure over time. tolerance depends on duration and onset rate, not just the instantaneous
+/// load: short spikes are tolerated well above the sustained limit, but the time to G-LOC shrinks the further above it
+/// the pilot is(time to G-LOC ≈ dose / (Gz - tolerance), a hyperbolic tolerance curve in the style of Stoll).
+/// rapid onset gets less tolerance than gradual onset, since the cardiovascular reflexes haven't caught up yet.
+/// all tolerances are in g(multiples of standard gravity)
+pub struct GToleranceModel<T>{
+    // unit vector from seat toward pilot's head, in the ship frame
+    head_direction: [T; 3],
+    // +Gz tolerable indefinitely, before reflexes compensate
+    relaxed_tolerance: T,
+    // +Gz tolerable for a couple of seconds
+    instantaneous_tolerance: T,
+    // -Gz tolerance. not tracked over time, always enforced as is
+    negative_tolerance: T,
+    g_dose: T,
+    compensation: T,
+    incapacitation_time: T,
+    gz: T,
+    pilot_state: PilotState,
+}
+impl<T> GToleranceModel<T>
+    where T: Float
+{
+    pub fn new(head_direction: Dimension3<T>, relaxed_tolerance: T, instantaneous_tolerance: T, negative_tolerance: T) -> Self{
+        let magnitude = (head_direction.x().powi(2) + head_direction.y().powi(2) + head_direction.z().powi(2)).sqrt();
+        let head_direction = if magnitude > T::epsilon(){
+            [head_direction.x() / magnitude, head_direction.y() / magnitude, head_direction.z() / magnitude]
+        }else{
+            [num::zero(), num::zero(), num::one()]
+        };
+        Self{
+            head_direction,
+            relaxed_tolerance: relaxed_tolerance.abs(),
+            instantaneous_tolerance: instantaneous_tolerance.abs().max(relaxed_tolerance.abs()),
+            negative_tolerance: negative_tolerance.abs(),
+            g_dose: num::zero(),
+            compensation: num::zero(),
+            incapacitation_time: num::zero(),
+            gz: num::zero(),
+            pilot_state: PilotState::Nominal,
+        }
+    }
+
+    pub fn pilot_state(&self) -> PilotState{self.pilot_state}
+
+    /// most recent +Gz load, in g
+    pub fn gz(&self) -> T{self.gz}
+
+    /// accumulated g·seconds above tolerance
+    pub fn g_dose(&self) -> T{self.g_dose}
+
+    /// current sustained +Gz tolerance, including cardiovascular compensation
+    pub fn tolerance(&self) -> T{self.relaxed_tolerance + self.compensation}
+
+    /// advances the model by delta_time with the pilot experiencing seat_acceleration(m/s^2, ship frame)
+    pub fn update(&mut self, seat_acceleration: &Dimension3<T>, delta_time: T) -> PilotState{
+        let standard_gravity: T = num::cast(crate::propulsion_control::STANDARD_GRAVITY).unwrap();
+        self.gz = (
+            (seat_acceleration.x() * self.head_direction[0]) + 
+            (seat_acceleration.y() * self.head_direction[1]) + 
+            (seat_acceleration.z() * self.head_direction[2])
+        ) / standard_gravity;
+
+        // reflexes only kick in under load, and fade once it is removed
+        let time_constant: T = num::cast(BARORECEPTOR_TIME_CONSTANT).unwrap();
+        let target: T = if self.gz > T::one(){num::cast(BARORECEPTOR_COMPENSATION).unwrap()}else{num::zero()};
+        self.compensation = self.compensation + ((target - self.compensation) * (T::one() - (-delta_time / time_constant).exp()));
+
+        let tolerance = self.tolerance();
+        if self.gz > tolerance{
+            self.g_dose = self.g_dose + ((self.gz - tolerance) * delta_time);
+        }else{
+            let recovery_rate: T = num::cast(RECOVERY_RATE).unwrap();
+            self.g_dose = (self.g_dose - (recovery_rate * delta_time)).max(num::zero());
+        }
+
+        let gloc_dose: T = num::cast(GLOC_DOSE).unwrap();
+        self.g_dose = self.g_dose.min(gloc_dose);
+        if self.g_dose >= gloc_dose && self.pilot_state != PilotState::GLoc{
+            self.incapacitation_time = num::cast(GLOC_INCAPACITATION_TIME).unwrap();
+        }
+        self.incapacitation_time = (self.incapacitation_time - delta_time).max(num::zero());
+
+        self.pilot_state = if self.g_dose >= gloc_dose || self.incapacitation_time > num::zero(){
+            PilotState::GLoc
+        }else if self.g_dose >= num::cast(BLACKOUT_DOSE).unwrap(){
+            PilotState::Blackout
+        }else if self.g_dose >= num::cast(GREYOUT_DOSE).unwrap(){
+            PilotState::Greyout
+        }else{
+            PilotState::Nominal
+        };
+        self.pilot_state
+    }
+
+    /// allowed acceleration(m/s^2) along head_direction, positive toward the head. a rested pilot can use up to the
+    /// instantaneous tolerance, which narrows toward the sustained tolerance as g-dose builds up, and relaxes again
+    /// as the pilot recovers
+    pub fn acceleration_limit(&self) -> AxisContribution<T>{
+        let standard_gravity: T = num::cast(crate::propulsion_control::STANDARD_GRAVITY).unwrap();
+        let greyout_dose: T = num::cast(GREYOUT_DOSE).unwrap();
+        let tolerance = self.tolerance().min(self.instantaneous_tolerance);
+        let margin = (self.instantaneous_tolerance - tolerance) * (T::one() - (self.g_dose / greyout_dose)).max(num::zero());
+        AxisContribution::new(
+            (tolerance + margin) * standard_gravity,
+            self.negative_tolerance * standard_gravity
+        )
+    }
+
+    /// per axis linear limits tightened so that no single axis can exceed acceleration_limit along head_direction
+    pub fn apply_to_limits(
+        &self, 
+        gsafety_max_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>
+    ) -> ControlAxis<Dimension3<AxisContribution<T>>>{
+        let limit = self.acceleration_limit();
+        let tighten = |existing: AxisContribution<T>, head_component: T| {
+            if head_component.abs() <= T::epsilon(){return AxisContribution::new(existing.positive(), existing.negative())}
+            // accelerating along +axis loads the pilot toward the head when the head points along +axis
+            let (toward_head, toward_feet) = if head_component > num::zero(){
+                (limit.positive(), limit.negative())
+            }else{
+                (limit.negative(), limit.positive())
+            };
+            AxisContribution::new(
+                existing.positive().min(toward_head / head_component.abs()),
+                existing.negative().min(toward_feet / head_component.abs())
+            )
+        };
+        let linear = gsafety_max_acceleration.linear();
+        let rotational = gsafety_max_acceleration.rotational();
+        ControlAxis::new(
+            Dimension3::new(
+                tighten(linear.x(), self.head_direction[0]),
+                tighten(linear.y(), self.head_direction[1]),
+                tighten(linear.z(), self.head_direction[2])
+            ),
+            Dimension3::new(
+                AxisContribution::new(rotational.x().positive(), rotational.x().negative()),
+                AxisContribution::new(rotational.y().positive(), rotational.y().negative()),
+                AxisContribution::new(rotational.z().positive(), rotational.z().negative())
+            )
+        )
+    }
+}
+
+
+
+
+
+
+#[test]
+fn test_process_vector_magnitude_limits_diagonal(){
+    let limits = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(10.0, 10.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    );
+    let zero = Dimension3::default(0.0);
+
+    // per axis, each of these is within limits
+    let output = process_vector_magnitude(
+        &ControlAxis::new(Dimension3::new(10.0, 10.0, 0.0), Dimension3::default(0.0)), 
+        &limits, 
+        &zero, 
+        &zero
+    );
+    assert!((output.linear().x() - 50.0_f64.sqrt()).abs() < 0.001);
+    assert!((output.linear().y() - 50.0_f64.sqrt()).abs() < 0.001);
+}
+
+#[test]
+fn test_process_vector_magnitude_direction_dependent_limits_and_centripetal(){
+    // tolerates 10 in +x, only 2 in -z
+    let limits = ControlAxis::new(
+        Dimension3::new(AxisContribution::new(10.0, 5.0), AxisContribution::new(5.0, 5.0), AxisContribution::new(5.0, 2.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    );
+    let zero = Dimension3::default(0.0);
+    let output = process_vector_magnitude(
+        &ControlAxis::new(Dimension3::new(8.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &limits, 
+        &zero, 
+        &zero
+    );
+    assert!((output.linear().x() - 8.0).abs() < 0.001);
+    let output = process_vector_magnitude(
+        &ControlAxis::new(Dimension3::new(0.0, 0.0, -8.0), Dimension3::default(0.0)), 
+        &limits, 
+        &zero, 
+        &zero
+    );
+    assert!((output.linear().z() - (-2.0)).abs() < 0.001);
+
+    // spinning about z with the seat 5m out on x adds 5m/s^2 toward the center
+    let output = process_vector_magnitude(
+        &ControlAxis::new(Dimension3::new(-5.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &limits, 
+        &Dimension3::new(0.0, 0.0, 1.0), 
+        &Dimension3::new(5.0, 0.0, 0.0)
+    );
+    assert!(output.linear().x().abs() < 0.001);
+    let output = process_vector_magnitude(
+        &ControlAxis::new(Dimension3::new(10.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &limits, 
+        &Dimension3::new(0.0, 0.0, 1.0), 
+        &Dimension3::new(5.0, 0.0, 0.0)
+    );
+    // seat feels 10 - 5 = 5 in +x, within limits
+    assert!((output.linear().x() - 10.0).abs() < 0.001);
+}
+
+#[test]
+fn test_process_vector_magnitude_recovers_from_over_limit_spin(){
+    let limits = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(2.0, 2.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    );
+    let seat = Dimension3::new(5.0, 0.0, 0.0);
+    // 1rad/s about z pulls the seat 5m/s^2 toward the center, past the 2m/s^2 limit
+    let mut angular_velocity: f64 = 1.0;
+
+    // spinning up further, or adding linear acceleration, is refused
+    let output = process_vector_magnitude(
+        &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::new(0.5, 0.0, 0.5)), 
+        &limits, 
+        &Dimension3::new(0.0, 0.0, angular_velocity), 
+        &seat
+    );
+    assert!(output.linear().x().abs() < 0.001);
+    assert!(output.rotational().x().abs() < 0.001);
+    assert!(output.rotational().z().abs() < 0.001);
+
+    // slowing the spin is let through until the seat is back within limits
+    let mut steps = 0;
+    while 5.0 * angular_velocity.powi(2) > 2.0{
+        let output = process_vector_magnitude(
+            &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::new(0.0, 0.0, -0.5)), 
+            &limits, 
+            &Dimension3::new(0.0, 0.0, angular_velocity), 
+            &seat
+        );
+        assert!((output.rotational().z() - (-0.5)).abs() < 0.001);
+        angular_velocity += output.rotational().z() * 0.1;
+        steps += 1;
+        assert!(steps < 100);
+    }
+    // back within limits, so the command gets through scaled rather than dropped
+    let output = process_vector_magnitude(
+        &ControlAxis::new(Dimension3::new(0.5, 0.0, 0.0), Dimension3::default(0.0)), 
+        &limits, 
+        &Dimension3::new(0.0, 0.0, angular_velocity), 
+        &seat
+    );
+    assert!(output.linear().x() > 0.0);
+}
+
+#[test]
+fn test_g_tolerance_model(){
+    // head toward +z. 5g sustained, 9g for a few seconds, 2g negative
+    let mut model: GToleranceModel<f64> = GToleranceModel::new(Dimension3::new(0.0, 0.0, 1.0), 5.0, 9.0, 2.0);
+    let standard_gravity = crate::propulsion_control::STANDARD_GRAVITY;
+    assert!((model.acceleration_limit().positive() - (9.0 * standard_gravity)).abs() < 0.001);
+    assert!((model.acceleration_limit().negative() - (2.0 * standard_gravity)).abs() < 0.001);
+
+    // a brief 8g spike is fine
+    assert_eq!(model.update(&Dimension3::new(0.0, 0.0, 8.0 * standard_gravity), 0.5), PilotState::Nominal);
+    // held, rapid onset 8g greys out, then blacks out, then G-LOC
+    let mut states = Vec::new();
+    for _ in 0..10{
+        states.push(model.update(&Dimension3::new(0.0, 0.0, 8.0 * standard_gravity), 0.25));
+    }
+    assert!(states.contains(&PilotState::Greyout));
+    assert!(states.contains(&PilotState::Blackout));
+    assert_eq!(model.pilot_state(), PilotState::GLoc);
+    // envelope has narrowed to the sustained tolerance
+    assert!(model.acceleration_limit().positive() < 6.0 * standard_gravity);
+
+    // incapacitated for a while after the load is removed, then recovers
+    assert_eq!(model.update(&Dimension3::default(0.0), 5.0), PilotState::GLoc);
+    for _ in 0..20{
+        model.update(&Dimension3::default(0.0), 1.0);
+    }
+    assert_eq!(model.pilot_state(), PilotState::Nominal);
+    assert!((model.acceleration_limit().positive() - (9.0 * standard_gravity)).abs() < 0.01);
+
+    let limits = model.apply_to_limits(&ControlAxis::new(
+        Dimension3::default(AxisContribution::new(100.0, 100.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    ));
+    assert!((limits.linear().z().positive() - (9.0 * standard_gravity)).abs() < 0.01);
+    assert!((limits.linear().z().negative() - (2.0 * standard_gravity)).abs() < 0.01);
+    assert!((limits.linear().x().positive() - 100.0).abs() < 0.001);
+}
+
+#[test]
+fn test_process_profiles_enforces_most_restrictive(){
+    let profiles = vec![
+        GLimitProfile::new(String::from("pilot"), Dimension3::default(0.0), Dimension3::default(AxisContribution::new(50.0, 50.0))),
+        // fragile cargo far out on x, feels yaw as acceleration in y
+        GLimitProfile::new(String::from("cargo"), Dimension3::new(10.0, 0.0, 0.0), Dimension3::default(AxisContribution::new(5.0, 5.0))),
+    ];
+    let zero = Dimension3::default(0.0);
+
+    let output = process_profiles(
+        &ControlAxis::new(Dimension3::new(2.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &profiles, 
+        &zero, 
+        &zero, 
+        GSafetyMode::PerAxis
+    );
+    assert!((output.acceleration().linear().x() - 2.0).abs() < 0.001);
+    assert_eq!(output.limiting_profile(), None);
+
+    // 1rad/s^2 yaw at 10m is 10m/s^2 for the cargo, on top of 2m/s^2 linear
+    let output = process_profiles(
+        &ControlAxis::new(Dimension3::new(0.0, 2.0, 0.0), Dimension3::new(0.0, 0.0, 1.0)), 
+        &profiles, 
+        &zero, 
+        &zero, 
+        GSafetyMode::PerAxis
+    );
+    assert!((output.acceleration().linear().y() - (2.0 * 5.0 / 12.0)).abs() < 0.001);
+    assert!((output.acceleration().rotational().z() - (5.0 / 12.0)).abs() < 0.001);
+    assert_eq!(output.limiting_profile(), Some(1));
+    assert_eq!(profiles[1].name(), "cargo");
+
+    // with the center of mass shifted out to the cargo, yaw no longer loads it
+    let output = process_profiles(
+        &ControlAxis::new(Dimension3::new(0.0, 2.0, 0.0), Dimension3::new(0.0, 0.0, 1.0)), 
+        &profiles, 
+        &zero, 
+        &Dimension3::new(10.0, 0.0, 0.0), 
+        GSafetyMode::PerAxis
+    );
+    assert!((output.acceleration().linear().y() - 2.0).abs() < 0.001);
+    assert_eq!(output.limiting_profile(), None);
+
+    // spinning at 1rad/s already pulls the cargo 10m/s^2 toward the center. only slowing the spin is let through
+    let output = process_profiles(
+        &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::new(0.0, 0.0, -0.5)), 
+        &profiles, 
+        &Dimension3::new(0.0, 0.0, 1.0), 
+        &zero, 
+        GSafetyMode::PerAxis
+    );
+    assert!(output.acceleration().linear().x().abs() < 0.001);
+    assert!((output.acceleration().rotational().z() - (-0.5)).abs() < 0.001);
+    assert_eq!(output.limiting_profile(), Some(1));
+}
@@ -126,111 +126,128 @@ fn pilot_input_to_acceleration<T>(
 
 
 ///////////////////////////////// Autonomous Mode /////////////////////////////
-//pub fn process_autonomous_mode_input<T>(
-//    goal_position: &ControlAxis<Dimension3<T>>,
-//    position: &ControlAxis<Dimension3<T>>,
-//    max_velocity: &ControlAxis<Dimension3<T>>,
-//    velocity: &ControlAxis<Dimension3<T>>,
-//    available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
-//    delta_time: T,
-//) -> ControlAxis<Dimension3<T>>
-//    where T: Float
-//{
-//    ControlAxis::new(
-//        Dimension3::new(
-//            autonomous_input_to_acceleration(
-//                goal_position.linear().x(), 
-//                position.linear().x(), 
-//                max_velocity.linear().x(), 
-//                velocity.linear().x(), 
-//                available_acceleration.linear().x(),
-//                delta_time, 
-//            ), 
-//            autonomous_input_to_acceleration(
-//                goal_position.linear().y(), 
-//                position.linear().y(), 
-//                max_velocity.linear().y(), 
-//                velocity.linear().y(), 
-//                available_acceleration.linear().y(),
-//                delta_time, 
-//            ), 
-//            autonomous_input_to_acceleration(
-//                goal_position.linear().z(), 
-//                position.linear().z(), 
-//                max_velocity.linear().z(), 
-//                velocity.linear().z(), 
-//                available_acceleration.linear().z(),
-//                delta_time, 
-//            )
-//        ),
-//        Dimension3::new(
-//            autonomous_input_to_acceleration(
-//                goal_position.rotational().x(), 
-//                position.rotational().x(), 
-//                max_velocity.rotational().x(), 
-//                velocity.rotational().x(), 
-//                available_acceleration.rotational().x(),
-//                delta_time, 
-//            ),
-//            autonomous_input_to_acceleration(
-//                goal_position.rotational().y(), 
-//                position.rotational().y(), 
-//                max_velocity.rotational().y(), 
-//                velocity.rotational().y(), 
-//                available_acceleration.rotational().y(),
-//                delta_time, 
-//            ),
-//            autonomous_input_to_acceleration(
-//                goal_position.rotational().z(), 
-//                position.rotational().z(), 
-//                max_velocity.rotational().z(), 
-//                velocity.rotational().z(), 
-//                available_acceleration.rotational().z(),
-//                delta_time, 
-//            )
-//        )
-//    )
-//}
-
-
-
-//fn autonomous_input_to_acceleration<T>(
-//    goal_position: T,
-//    position: T,
-//    max_velocity: T,
-//    velocity: T,
-//    available_acceleration: AxisContribution<T>,
-//    delta_time: T
-//) -> T
-//    where T: Float
-//{
-//    //derivative of position is velocity
-//    let desired_velocity = (goal_position - position) / delta_time;
-//    
-//    let clamped_desired_velocity = if desired_velocity > max_velocity{
-//        max_velocity
-//    }
-//    else if desired_velocity < -(max_velocity){
-//        -(max_velocity)
-//    }
-//    else{
-//        desired_velocity
-//    };
-//
-//
-//    //derivative of velocity is acceleration
-//    let desired_acceleration = (clamped_desired_velocity - velocity) / delta_time;
-//
-//    if desired_acceleration > available_acceleration.positive(){
-//        available_acceleration.positive()
-//    }
-//    else if desired_acceleration < -(available_acceleration.negative()){
-//        -(available_acceleration.negative())
-//    }
-//    else{
-//        desired_acceleration
-//    }
-//}
+pub fn process_autonomous_mode_input<T>(
+    goal_position: &ControlAxis<Dimension3<T>>,
+    position: &ControlAxis<Dimension3<T>>,
+    max_velocity: &ControlAxis<Dimension3<T>>,
+    velocity: &ControlAxis<Dimension3<T>>,
+    available_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    delta_time: T,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    ControlAxis::new(
+        Dimension3::new(
+            autonomous_input_to_acceleration(
+                goal_position.linear().x(), 
+                position.linear().x(), 
+                max_velocity.linear().x(), 
+                velocity.linear().x(), 
+                available_acceleration.linear().x(),
+                delta_time, 
+            ), 
+            autonomous_input_to_acceleration(
+                goal_position.linear().y(), 
+                position.linear().y(), 
+                max_velocity.linear().y(), 
+                velocity.linear().y(), 
+                available_acceleration.linear().y(),
+                delta_time, 
+            ), 
+            autonomous_input_to_acceleration(
+                goal_position.linear().z(), 
+                position.linear().z(), 
+                max_velocity.linear().z(), 
+                velocity.linear().z(), 
+                available_acceleration.linear().z(),
+                delta_time, 
+            )
+        ),
+        Dimension3::new(
+            autonomous_input_to_acceleration(
+                goal_position.rotational().x(), 
+                position.rotational().x(), 
+                max_velocity.rotational().x(), 
+                velocity.rotational().x(), 
+                available_acceleration.rotational().x(),
+                delta_time, 
+            ),
+            autonomous_input_to_acceleration(
+                goal_position.rotational().y(), 
+                position.rotational().y(), 
+                max_velocity.rotational().y(), 
+                velocity.rotational().y(), 
+                available_acceleration.rotational().y(),
+                delta_time, 
+            ),
+            autonomous_input_to_acceleration(
+                goal_position.rotational().z(), 
+                position.rotational().z(), 
+                max_velocity.rotational().z(), 
+                velocity.rotational().z(), 
+                available_acceleration.rotational().z(),
+                delta_time, 
+            )
+        )
+    )
+}
+
+
+
+/// desired velocity is the fastest speed from which the ship can still come to a stop at the goal
+/// using the acceleration available on the braking side of the axis. v = sqrt(2 * a * d)
+/// so a ship with a big main engine and weak reverse thrusters starts braking earlier when moving forward.
+fn autonomous_input_to_acceleration<T>(
+    goal_position: T,
+    position: T,
+    max_velocity: T,
+    velocity: T,
+    available_acceleration: AxisContribution<T>,
+    delta_time: T
+) -> T
+    where T: Float
+{
+    // a paused frame(or a repeated tick) has no time to change velocity in
+    if delta_time <= num::zero(){return num::zero()}
+    let distance = goal_position - position;
+    
+    // moving toward a goal in the positive direction is stopped by the negative side of the axis, and vice versa
+    let braking_acceleration = if distance > num::zero(){
+        available_acceleration.negative()
+    }
+    else{
+        available_acceleration.positive()
+    };
+    let two: T = num::cast(2.0).unwrap();
+    let stopping_velocity = (two * braking_acceleration * distance.abs()).sqrt();
+
+    // don't request a velocity that would carry the ship past the goal within a single frame
+    let desired_speed = max_velocity
+        .min(stopping_velocity)
+        .min(distance.abs() / delta_time);
+    let desired_velocity = if distance > num::zero(){
+        desired_speed
+    }
+    else if distance < num::zero(){
+        -desired_speed
+    }
+    else{
+        num::zero()
+    };
+
+    //derivative of velocity is acceleration
+    let desired_acceleration = (desired_velocity - velocity) / delta_time;
+
+    if desired_acceleration > available_acceleration.positive(){
+        available_acceleration.positive()
+    }
+    else if desired_acceleration < -(available_acceleration.negative()){
+        -(available_acceleration.negative())
+    }
+    else{
+        desired_acceleration
+    }
+}
 
 
 
@@ -324,9 +341,98 @@ fn axis_pilot_control_output_valid_when_input_zero_and_assist_disabled(){
 ///////////////////////////////////////////////////////////////////////////////
 
 // axis autonomous control mode
-// test
-// test
-// test
+#[test]
+fn axis_autonomous_control_output_valid_when_goal_ahead(){
+    let output: f64 = autonomous_input_to_acceleration(
+        100.0, 
+        0.0, 
+        50.0, 
+        0.0, 
+        AxisContribution::new(1.0, 1.0), 
+        1.0
+    );
+    assert!((output - 1.0).abs() < 0.001);
+}
+#[test]
+fn axis_autonomous_control_output_valid_when_goal_behind(){
+    let output: f64 = autonomous_input_to_acceleration(
+        -100.0, 
+        0.0, 
+        50.0, 
+        0.0, 
+        AxisContribution::new(1.0, 1.0), 
+        1.0
+    );
+    assert!((output - (-1.0)).abs() < 0.001);
+}
+#[test]
+fn axis_autonomous_control_output_valid_when_at_goal(){
+    let output: f64 = autonomous_input_to_acceleration(
+        0.0, 
+        0.0, 
+        50.0, 
+        0.0, 
+        AxisContribution::new(1.0, 1.0), 
+        1.0
+    );
+    assert!((output - 0.0).abs() < 0.001);
+}
+#[test]
+fn axis_autonomous_control_brakes_before_goal(){
+    // 10m from goal at 10m/s with 1m/s^2 of braking needs 50m to stop, so must brake fully
+    let output: f64 = autonomous_input_to_acceleration(
+        10.0, 
+        0.0, 
+        50.0, 
+        10.0, 
+        AxisContribution::new(5.0, 1.0), 
+        0.1
+    );
+    assert!((output - (-1.0)).abs() < 0.001);
+}
+#[test]
+fn axis_autonomous_control_zero_delta_time(){
+    let output: f64 = autonomous_input_to_acceleration(
+        10.0, 
+        0.0, 
+        50.0, 
+        10.0, 
+        AxisContribution::new(5.0, 1.0), 
+        0.0
+    );
+    assert_eq!(output, 0.0);
+}
+#[test]
+fn axis_autonomous_control_does_not_overshoot_with_asymmetric_acceleration(){
+    let available_acceleration = AxisContribution::new(10.0, 1.0);
+    let delta_time = 0.01;
+    let goal = 100.0;
+    let mut position: f64 = 0.0;
+    let mut velocity: f64 = 0.0;
+    let mut max_position = position;
+
+    for _ in 0..10_000{
+        let acceleration = autonomous_input_to_acceleration(
+            goal, 
+            position, 
+            20.0, 
+            velocity, 
+            available_acceleration, 
+            delta_time
+        );
+        assert!(acceleration <= available_acceleration.positive() + 0.001);
+        assert!(acceleration >= -available_acceleration.negative() - 0.001);
+        assert!(velocity <= 20.0 + 0.001);
+
+        velocity += acceleration * delta_time;
+        position += velocity * delta_time;
+        max_position = max_position.max(position);
+    }
+
+    assert!(max_position < goal + 0.1);
+    assert!((position - goal).abs() < 0.01);
+    assert!(velocity.abs() < 0.01);
+}
 
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -472,5 +578,41 @@ mod tests{
 
     // autonomous control mode
     #[test]
-    fn test_autonomous_mode_does_something(){}
+    fn test_autonomous_mode_accelerates_toward_goal(){
+        let goal_position = ControlAxis::new(
+            Dimension3::new(100.0, -100.0, 0.0),
+            Dimension3::new(1.0, -1.0, 0.0)
+        );
+        let position = ControlAxis::new(
+            Dimension3::default(0.0), 
+            Dimension3::default(0.0)
+        );
+        let max_velocity = ControlAxis::new(
+            Dimension3::default(50.0),
+            Dimension3::default(50.0)
+        );
+        let velocity = ControlAxis::new(
+            Dimension3::default(0.0), 
+            Dimension3::default(0.0)
+        );
+        let available_acceleration = ControlAxis::new(
+            Dimension3::default(AxisContribution::new(2.0, 1.0)),
+            Dimension3::default(AxisContribution::new(2.0, 1.0)),
+        );
+
+        let output: ControlAxis<Dimension3<f64>> = input_processing::process_autonomous_mode_input(
+            &goal_position, 
+            &position, 
+            &max_velocity, 
+            &velocity, 
+            &available_acceleration, 
+            1.0
+        );
+        assert!((output.linear().x() - 2.0).abs() < 0.001);
+        assert!((output.linear().y() - (-1.0)).abs() < 0.001);
+        assert!((output.linear().z() - 0.0).abs() < 0.001);
+        assert!((output.rotational().x() - 1.0).abs() < 0.001);
+        assert!((output.rotational().y() - (-1.0)).abs() < 0.001);
+        assert!((output.rotational().z() - 0.0).abs() < 0.001);
+    }
 }
\ No newline at end of file
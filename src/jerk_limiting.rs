@@ -0,0 +1,185 @@
+//! # jerk limiting
+//! limits how quickly desired acceleration can change from one frame to the next.
+//!
+//! pilot input can step from no acceleration to full acceleration in a single frame, which is uncomfortable for
+//! occupants, and not something real thrusters can do anyway. the jerk limiter sits right after input processing, and
+//! ramps the feedforward command toward its target at no more than the configured max jerk. feedback is added after,
+//! so disturbances are still corrected without delay.
+
+use game_utils::{
+    control_axis::ControlAxis,
+    dimension3::Dimension3,
+};
+use num::Float;
+
+
+
+
+
+/// how max jerk is applied to the change in acceleration
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum JerkLimitMode{
+    /// each axis ramps toward its target independently. a command that changes on several axes can change direction
+    /// while ramping
+    PerAxis,
+    /// the change in linear acceleration, and the change in rotational acceleration, are each kept inside the ellipsoid
+    /// whose semi-axes are the per axis max jerk. the change is scaled down together, so its direction is kept
+    VectorMagnitude,
+}
+
+
+
+pub struct JerkLimiter<T>{
+    // m/s^3 for linear axes, rad/s^3 for rotational axes
+    max_jerk: ControlAxis<Dimension3<T>>,
+    mode: JerkLimitMode,
+    // acceleration output last frame
+    previous_acceleration: ControlAxis<Dimension3<T>>,
+}
+impl<T> JerkLimiter<T>
+    where T: Float
+{
+    /// starts from zero acceleration
+    pub fn new(max_jerk: ControlAxis<Dimension3<T>>, mode: JerkLimitMode) -> Self{
+        Self{
+            max_jerk,
+            mode,
+            previous_acceleration: ControlAxis::new(
+                Dimension3::default(num::zero()),
+                Dimension3::default(num::zero())
+            ),
+        }
+    }
+
+    pub fn max_jerk(&self) -> &ControlAxis<Dimension3<T>>{&self.max_jerk}
+    pub fn max_jerk_mut(&mut self) -> &mut ControlAxis<Dimension3<T>>{&mut self.max_jerk}
+
+    pub fn mode(&self) -> JerkLimitMode{self.mode}
+
+    pub fn set_mode(&mut self, mode: JerkLimitMode){
+        self.mode = mode;
+    }
+
+    pub fn previous_acceleration(&self) -> &ControlAxis<Dimension3<T>>{&self.previous_acceleration}
+
+    /// the command actually sent after later limiting(g-force safety, saturation). keeps the limiter from ramping past
+    /// what the ship is allowed to do, then taking just as long to ramp back down
+    pub fn set_previous_acceleration(&mut self, previous_acceleration: ControlAxis<Dimension3<T>>){
+        self.previous_acceleration = previous_acceleration;
+    }
+
+    /// forget the previous command, so the next one ramps up from zero
+    pub fn reset(&mut self){
+        self.previous_acceleration = ControlAxis::new(
+            Dimension3::default(num::zero()),
+            Dimension3::default(num::zero())
+        );
+    }
+
+    /// moves from the previous output toward desired acceleration, by no more than max jerk * delta time
+    pub fn process(&mut self, desired_acceleration: &ControlAxis<Dimension3<T>>, delta_time: T) -> ControlAxis<Dimension3<T>>{
+        let acceleration = ControlAxis::new(
+            limit_change(
+                self.previous_acceleration.linear(),
+                desired_acceleration.linear(),
+                self.max_jerk.linear(),
+                self.mode,
+                delta_time
+            ),
+            limit_change(
+                self.previous_acceleration.rotational(),
+                desired_acceleration.rotational(),
+                self.max_jerk.rotational(),
+                self.mode,
+                delta_time
+            )
+        );
+        self.previous_acceleration = crate::copy_control_axis(&acceleration);
+        acceleration
+    }
+}
+
+fn limit_change<T>(
+    previous: &Dimension3<T>,
+    desired: &Dimension3<T>,
+    max_jerk: &Dimension3<T>,
+    mode: JerkLimitMode,
+    delta_time: T,
+) -> Dimension3<T>
+    where T: Float
+{
+    let change = [
+        (desired.x() - previous.x(), max_jerk.x() * delta_time),
+        (desired.y() - previous.y(), max_jerk.y() * delta_time),
+        (desired.z() - previous.z(), max_jerk.z() * delta_time),
+    ];
+    let limited = match mode{
+        JerkLimitMode::PerAxis => change.map(|(change, max_change)| {
+            let max_change = max_change.max(num::zero());
+            num::clamp(change, -max_change, max_change)
+        }),
+        JerkLimitMode::VectorMagnitude => {
+            let mut load: T = num::zero();
+            for (change, max_change) in change{
+                if change == num::zero(){continue}
+                if max_change <= num::zero(){load = T::infinity(); break;}
+                load = load + (change / max_change).powi(2);
+            }
+            let load = load.sqrt();
+            let scale = if load > num::one(){T::one() / load}else{T::one()};
+            change.map(|(change, _)| change * scale)
+        },
+    };
+
+    Dimension3::new(
+        previous.x() + limited[0],
+        previous.y() + limited[1],
+        previous.z() + limited[2]
+    )
+}
+
+
+
+
+
+#[test]
+fn test_jerk_limiter_ramps_step_input(){
+    let mut jerk_limiter = JerkLimiter::new(
+        ControlAxis::new(Dimension3::new(10.0, 5.0, 10.0), Dimension3::default(1.0)),
+        JerkLimitMode::PerAxis
+    );
+    let step = ControlAxis::new(Dimension3::new(20.0, 20.0, 0.0), Dimension3::new(0.0, 0.0, -0.5));
+
+    let output = jerk_limiter.process(&step, 0.5);
+    assert!((output.linear().x() - 5.0).abs() < 0.001);
+    assert!((output.linear().y() - 2.5).abs() < 0.001);
+    assert!((output.rotational().z() - (-0.5)).abs() < 0.001);
+
+    for _ in 0..3{
+        let _ = jerk_limiter.process(&step, 0.5);
+    }
+    let output = jerk_limiter.process(&step, 0.5);
+    // x reached its target after 2 seconds, y is still ramping
+    assert!((output.linear().x() - 20.0).abs() < 0.001);
+    assert!((output.linear().y() - 12.5).abs() < 0.001);
+
+    jerk_limiter.reset();
+    let output = jerk_limiter.process(&step, 0.5);
+    assert!((output.linear().x() - 5.0).abs() < 0.001);
+}
+
+#[test]
+fn test_jerk_limiter_vector_magnitude_keeps_direction(){
+    let mut jerk_limiter = JerkLimiter::new(
+        ControlAxis::new(Dimension3::default(10.0), Dimension3::default(1.0)),
+        JerkLimitMode::VectorMagnitude
+    );
+    let output = jerk_limiter.process(
+        &ControlAxis::new(Dimension3::new(20.0, 20.0, 0.0), Dimension3::default(0.0)),
+        1.0
+    );
+    // 10m/s^3 total, split evenly between x and y
+    assert!((output.linear().x() - 50.0_f64.sqrt()).abs() < 0.001);
+    assert!((output.linear().y() - 50.0_f64.sqrt()).abs() < 0.001);
+    assert!(output.linear().z().abs() < 0.001);
+}
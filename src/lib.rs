@@ -1,19 +1,43 @@
 //! # Flight Control System(FCS)
-//use game_utils::{
-//    //control_axis::{ControlAxis, AxisContribution},
-//    //dimension3::Dimension3,
-//    toggle::TToggle,
-//};
-//use pid_controller::PID;
+use game_utils::{
+    control_axis::{ControlAxis, AxisContribution},
+    dimension3::Dimension3,
+    toggle::Toggle,
+};
+use pid_controller::PID;
 use num::Float;
-//use crate::propulsion_control::ThrusterMountPoint;
+use crate::propulsion_control::{
+    Thruster, 
+    ThrusterMountPoint, 
+    ThrusterHealth, 
+    MassProperties, 
+    ThrusterOutput, 
+    AllocationMethod, 
+    AuthorityReport,
+    ControllabilityReport,
+    ThrusterDynamics,
+    DeliveredThrust,
+    PropellantTank,
+    PropellantUsage,
+};
+use crate::momentum_devices::MomentumDevice;
+use crate::saturation::SaturationStrategy;
+use crate::feedback_controller::{FeedbackLoops, GainSchedule, PidGains};
+use crate::jerk_limiting::JerkLimiter;
+use crate::g_force_safety::{GSafetyMode, GToleranceModel, PilotState, GLimitProfile};
 
 
 pub mod input_processing;
 pub mod feedback_controller;
 //pub mod feedforward_controller;
+pub mod jerk_limiting;
 pub mod g_force_safety;
+pub mod saturation;
 pub mod propulsion_control;
+pub mod momentum_devices;
+#[cfg(feature = "serde")]
+pub mod ship_definition;
+mod optimization;
 
 
 
@@ -27,110 +51,660 @@ pub mod propulsion_control;
 
 
 // can use fcs struct or call functions independently
-//pub struct FlightControlSystem<'a, T>{
-//    linear_assist: Toggle,
-//    rotational_assist: Toggle,
-//    autonomous_mode: Toggle,
-//    max_velocity: ControlAxis<Dimension3<T>>,
-//    gsafety: Toggle,
-//    gsafety_max_acceleration: ControlAxis<Dimension3<AxisContribution<T>>>,
-//    available_acceleration: ControlAxis<Dimension3<AxisContribution<T>>>,
-//    pid6dof: ControlAxis<Dimension3<PID<T>>>, 
-//    thruster_mount_points: &'a[ThrusterMountPoint<'a, T>],
-//    //calculated expected position
-//}
-//impl<'a, T> FlightControlSystem<'a, T>
-//    where T: Float
-//{
-//    pub fn new(
-//        linear_assist: Toggle,
-//        rotational_assist: Toggle,
-//        autonomous_mode: Toggle,
-//        max_velocity: ControlAxis<Dimension3<T>>,
-//        gsafety: Toggle,
-//        gsafety_max_acceleration: ControlAxis<Dimension3<AxisContribution<T>>>,
-//        available_acceleration: ControlAxis<Dimension3<AxisContribution<T>>>,
-//        pid6dof: ControlAxis<Dimension3<PID<T>>>, 
-//        thruster_mount_points: &'a[ThrusterMountPoint<T>],
-//    ) -> Self{
-//        Self{
-//            linear_assist,
-//            rotational_assist,
-//            autonomous_mode,
-//            max_velocity,
-//            gsafety, 
-//            gsafety_max_acceleration,
-//            available_acceleration, //this will be determined by passing the thruster suite to the propulsion control system for calculation
-//            pid6dof, 
-//            thruster_mount_points,
-//        }
-//    }
-//
-//    pub fn process(&mut self, 
-//        input: &ControlAxis<Dimension3<T>>, 
-//        velocity: &ControlAxis<Dimension3<T>>,
-//        position: &ControlAxis<Dimension3<T>>,
-//        delta_time: T,
-//    ){
-//        let mut desired_acceleration = game_utils::sum_d3_control_axes(
-//            if self.autonomous_mode.enabled(){
-//                feedforward_controller::calculate_autonomous_mode_acceleration(
-//                    &input,
-//                    position,
-//                    velocity,
-//                    &self.max_velocity,
-//                    &self.available_acceleration,
-//                    delta_time,
-//                )
-//            }else{
-//                feedforward_controller::calculate_pilot_control_mode_acceleration(
-//                    input,
-//                    &self.linear_assist,
-//                    &self.rotational_assist,
-//                    &self.max_velocity,
-//                    velocity,
-//                    &self.available_acceleration,
-//                    delta_time,
-//                )
-//            },
-//            feedback_controller::calculate(
-//                // calculated expected position
-//                &ControlAxis::new(
-//                    Dimension3::default(num::zero()), 
-//                    Dimension3::default(num::zero())
-//                ), 
-//                position,
-//                &mut self.pid6dof, 
-//                &self.available_acceleration,
-//                delta_time, 
-//            )
-//        );
-//    
-//        if self.gsafety.enabled(){
-//            desired_acceleration = g_force_safety::process(
-//                &desired_acceleration, 
-//                &self.gsafety_max_acceleration
-//            )
-//        }
-//    
-//        // if advanced propulsion simulation is desired, feed desired accel values to propulsion control
-//        // otherwise feed desired accel values directly to physics sim
-//
-//        //Once the desired linear and rotational accelerations are established from the combined feedforward
-//        //and feedback control signals, the PCS must calculate the output of individual thrusters, as well as other
-//        //devices tasked with generating motion, so that these accelerations will be achieved to within a
-//        //reasonable degree of accuracy.
-//        let _ = propulsion_control::calculate_thruster_output(
-//            &desired_acceleration, 
-//            self.thruster_mount_points,
-//            num::zero()//mass
-//        );
-//    
-//        /////////////////////////////////////////////////////////////////////////////
-//        // calculate expected position from resultant thrusts/accelerations/velocities
-//        /////////////////////////////////////////////////////////////////////////////
-//    }
-//}
+pub struct FlightControlSystem<T>{
+    linear_assist: Toggle,
+    rotational_assist: Toggle,
+    // when enabled, input passed to process is treated as a goal position instead of pilot input
+    autonomous_mode: Toggle,
+    max_velocity: ControlAxis<Dimension3<T>>,
+    // ramps feedforward acceleration before feedback is added and g-force safety is applied. None disables jerk limiting
+    jerk_limiter: Option<JerkLimiter<T>>,
+    gsafety: Toggle,
+    gsafety_max_acceleration: ControlAxis<Dimension3<AxisContribution<T>>>,
+    gsafety_mode: GSafetyMode,
+    // tracks the pilot's g exposure over time, and tightens g-force safety limits as they tire
+    g_tolerance_model: Option<GToleranceModel<T>>,
+    // same frame as mount point locations and center of mass
+    pilot_seat: Dimension3<T>,
+    // occupants and cargo with their own g limits, enforced along with gsafety_max_acceleration
+    g_limit_profiles: Vec<GLimitProfile<T>>,
+    available_acceleration: ControlAxis<Dimension3<AxisContribution<T>>>,
+    pid6dof: ControlAxis<Dimension3<PID<T>>>, 
+    // used instead of pid6dof when set. supports anti-windup, derivative filtering, derivative on measurement, and
+    // cascaded position/velocity loops
+    feedback_loops: Option<FeedbackLoops<T>>,
+    // retunes feedback gains whenever mass properties or available acceleration change
+    gain_schedule: Option<GainSchedule<T>>,
+    // last gains applied from gain_schedule
+    scheduled_gains: Option<ControlAxis<Dimension3<PidGains<T>>>>,
+    thruster_mount_points: Vec<ThrusterMountPoint<T>>,
+    // mass properties of the ship without propellant
+    dry_mass_properties: MassProperties<T>,
+    // dry mass properties plus propellant tank contents, recalculated as propellant is burned
+    mass_properties: MassProperties<T>,
+    propellant_tanks: Vec<PropellantTank<T>>,
+    allocation_method: AllocationMethod<T>,
+    // how commands exceeding g-force safety limits or thruster authority are brought back within them
+    saturation_strategy: SaturationStrategy,
+    thruster_dynamics: ThrusterDynamics<T>,
+    // reaction wheels and control moment gyros, given rotation before the thrusters
+    momentum_devices: Vec<MomentumDevice<T>>,
+    // device saturation(0 to 1) above which thrusters are used to dump stored momentum
+    momentum_dump_threshold: T,
+    // where the ship should be next frame, given this frame's commanded acceleration.
+    // None until the first call to process, so the feedback controller starts with zero error
+    expected_position: Option<ControlAxis<Dimension3<T>>>,
+    // how fast the ship should be moving next frame, given this frame's commanded acceleration
+    expected_velocity: Option<ControlAxis<Dimension3<T>>>,
+}
+impl<T> FlightControlSystem<T>
+    where T: Float
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        linear_assist: Toggle,
+        rotational_assist: Toggle,
+        autonomous_mode: Toggle,
+        max_velocity: ControlAxis<Dimension3<T>>,
+        gsafety: Toggle,
+        gsafety_max_acceleration: ControlAxis<Dimension3<AxisContribution<T>>>,
+        pid6dof: ControlAxis<Dimension3<PID<T>>>, 
+        thruster_mount_points: Vec<ThrusterMountPoint<T>>,
+        dry_mass_properties: MassProperties<T>,
+    ) -> Self{
+        Self{
+            linear_assist,
+            rotational_assist,
+            autonomous_mode,
+            max_velocity,
+            jerk_limiter: None,
+            gsafety, 
+            gsafety_max_acceleration,
+            gsafety_mode: GSafetyMode::PerAxis,
+            g_tolerance_model: None,
+            pilot_seat: Dimension3::default(num::zero()),
+            g_limit_profiles: Vec::new(),
+            available_acceleration: propulsion_control::calculate_available_acceleration(
+                &propulsion_control::calculate_available_thrust(&thruster_mount_points, dry_mass_properties.center_of_mass()), 
+                &dry_mass_properties
+            ),
+            pid6dof, 
+            feedback_loops: None,
+            gain_schedule: None,
+            scheduled_gains: None,
+            thruster_mount_points,
+            // no propellant tanks yet, so wet and dry are the same
+            mass_properties: propulsion_control::calculate_wet_mass_properties(&dry_mass_properties, &[])
+                .expect("dry mass properties are already validated"),
+            dry_mass_properties,
+            propellant_tanks: Vec::new(),
+            allocation_method: AllocationMethod::LeastSquares,
+            saturation_strategy: SaturationStrategy::PerAxis,
+            thruster_dynamics: ThrusterDynamics::new(),
+            momentum_devices: Vec::new(),
+            momentum_dump_threshold: num::cast(0.8).unwrap(),
+            expected_position: None,
+            expected_velocity: None,
+        }
+    }
+
+    pub fn linear_assist(&self) -> &Toggle{&self.linear_assist}
+    pub fn linear_assist_mut(&mut self) -> &mut Toggle{&mut self.linear_assist}
+
+    pub fn rotational_assist(&self) -> &Toggle{&self.rotational_assist}
+    pub fn rotational_assist_mut(&mut self) -> &mut Toggle{&mut self.rotational_assist}
+
+    pub fn autonomous_mode(&self) -> &Toggle{&self.autonomous_mode}
+    pub fn autonomous_mode_mut(&mut self) -> &mut Toggle{&mut self.autonomous_mode}
+
+    pub fn gsafety(&self) -> &Toggle{&self.gsafety}
+    pub fn gsafety_mut(&mut self) -> &mut Toggle{&mut self.gsafety}
+
+    pub fn max_velocity(&self) -> &ControlAxis<Dimension3<T>>{&self.max_velocity}
+    pub fn max_velocity_mut(&mut self) -> &mut ControlAxis<Dimension3<T>>{&mut self.max_velocity}
+
+    pub fn gsafety_max_acceleration(&self) -> &ControlAxis<Dimension3<AxisContribution<T>>>{&self.gsafety_max_acceleration}
+    pub fn gsafety_max_acceleration_mut(&mut self) -> &mut ControlAxis<Dimension3<AxisContribution<T>>>{&mut self.gsafety_max_acceleration}
+
+    pub fn jerk_limiter(&self) -> &Option<JerkLimiter<T>>{&self.jerk_limiter}
+
+    /// None(the default) passes feedforward acceleration through unchanged. feedback corrections are never jerk limited
+    pub fn set_jerk_limiter(&mut self, jerk_limiter: Option<JerkLimiter<T>>){
+        self.jerk_limiter = jerk_limiter;
+    }
+
+    pub fn gsafety_mode(&self) -> GSafetyMode{self.gsafety_mode}
+
+    /// defaults to GSafetyMode::PerAxis
+    pub fn set_gsafety_mode(&mut self, gsafety_mode: GSafetyMode){
+        self.gsafety_mode = gsafety_mode;
+    }
+
+    pub fn g_tolerance_model(&self) -> &Option<GToleranceModel<T>>{&self.g_tolerance_model}
+
+    /// None(default) uses g-force safety limits as they are
+    pub fn set_g_tolerance_model(&mut self, g_tolerance_model: Option<GToleranceModel<T>>){
+        self.g_tolerance_model = g_tolerance_model;
+    }
+
+    /// Nominal when there is no g tolerance model
+    pub fn pilot_state(&self) -> PilotState{
+        match &self.g_tolerance_model{
+            Some(model) => model.pilot_state(),
+            None => PilotState::Nominal
+        }
+    }
+
+    pub fn pilot_seat(&self) -> &Dimension3<T>{&self.pilot_seat}
+
+    /// location of the pilot seat, used by GSafetyMode::VectorMagnitude. defaults to the origin
+    pub fn set_pilot_seat(&mut self, pilot_seat: Dimension3<T>){
+        self.pilot_seat = pilot_seat;
+    }
+
+    pub fn g_limit_profiles(&self) -> &[GLimitProfile<T>]{&self.g_limit_profiles}
+
+    /// g limits for individual occupants and cargo. positions are in the ship frame, like the pilot seat, and are taken
+    /// relative to the current center of mass. only enforced while gsafety is enabled, using gsafety_mode to decide
+    /// between per axis and vector magnitude limits
+    pub fn set_g_limit_profiles(&mut self, g_limit_profiles: Vec<GLimitProfile<T>>){
+        self.g_limit_profiles = g_limit_profiles;
+    }
+
+    pub fn pid6dof_mut(&mut self) -> &mut ControlAxis<Dimension3<PID<T>>>{&mut self.pid6dof}
+
+    pub fn feedback_loops(&self) -> &Option<FeedbackLoops<T>>{&self.feedback_loops}
+    pub fn feedback_loops_mut(&mut self) -> &mut Option<FeedbackLoops<T>>{&mut self.feedback_loops}
+
+    /// None(the default) uses pid6dof, or scheduled PidControllers while there is a gain schedule. LQR and MPC
+    /// can't take scheduled gains, so are rejected while there is one. LQR gains are discretised for the controller's
+    /// delta_time, so process should be called with that same delta_time while LQR feedback loops are in use
+    pub fn set_feedback_loops(&mut self, feedback_loops: Option<FeedbackLoops<T>>) -> Result<(), FcsError<'static>>{
+        if self.gain_schedule.is_some() && !accepts_gain_schedule(&feedback_loops){
+            return Err(FcsError::new("LQR and MPC feedback loops can't be used with a gain schedule."))
+        }
+        self.feedback_loops = feedback_loops;
+        // new controllers need the scheduled gains too
+        self.scheduled_gains = None;
+        self.apply_gain_schedule();
+
+        Ok(())
+    }
+
+    pub fn gain_schedule(&self) -> &Option<GainSchedule<T>>{&self.gain_schedule}
+
+    /// gains are applied right away, and again whenever mass properties or available acceleration change.
+    /// with cascaded feedback loops, the schedule sets the velocity loop gains. PID can't change gains in place, so
+    /// with the default pid6dof, it is swapped once for single PidController feedback loops, which keep their
+    /// integrators through later gain changes. rejected with LQR, which re-solves its own gains as mass changes,
+    /// or MPC, which works in acceleration directly
+    pub fn set_gain_schedule(&mut self, gain_schedule: Option<GainSchedule<T>>) -> Result<(), FcsError<'static>>{
+        if gain_schedule.is_some() && !accepts_gain_schedule(&self.feedback_loops){
+            return Err(FcsError::new("A gain schedule can't be used with LQR or MPC feedback loops."))
+        }
+        self.gain_schedule = gain_schedule;
+        self.scheduled_gains = None;
+        self.apply_gain_schedule();
+
+        Ok(())
+    }
+
+    /// gains currently applied from the gain schedule
+    pub fn scheduled_gains(&self) -> &Option<ControlAxis<Dimension3<PidGains<T>>>>{&self.scheduled_gains}
+
+    fn apply_gain_schedule(&mut self){
+        let gain_schedule = match &self.gain_schedule{
+            Some(gain_schedule) => gain_schedule,
+            None => return
+        };
+        let gains = gain_schedule.gains(&self.mass_properties, &self.available_acceleration);
+        if let Some(scheduled_gains) = &self.scheduled_gains{
+            if feedback_controller::gains_equal(scheduled_gains, &gains){return}
+        }
+        match self.feedback_loops.as_mut(){
+            Some(FeedbackLoops::Single(controllers)) => feedback_controller::apply_gains(controllers, &gains),
+            Some(FeedbackLoops::Cascaded(controllers)) => {
+                let set = |controller: &mut feedback_controller::CascadedPid<T>, gains: PidGains<T>| {
+                    controller.velocity_controller_mut().set_gains(gains.kp(), gains.ki(), gains.kd())
+                };
+                set(controllers.linear_mut().x_mut(), gains.linear().x());
+                set(controllers.linear_mut().y_mut(), gains.linear().y());
+                set(controllers.linear_mut().z_mut(), gains.linear().z());
+                set(controllers.rotational_mut().x_mut(), gains.rotational().x());
+                set(controllers.rotational_mut().y_mut(), gains.rotational().y());
+                set(controllers.rotational_mut().z_mut(), gains.rotational().z());
+            },
+            // checked when the schedule or feedback loops are set
+            Some(FeedbackLoops::Lqr(_)) | Some(FeedbackLoops::Mpc(_)) => {},
+            None => self.feedback_loops = Some(FeedbackLoops::Single(feedback_controller::pid_controllers_from_gains(&gains)))
+        }
+        self.scheduled_gains = Some(gains);
+    }
+
+    pub fn available_acceleration(&self) -> &ControlAxis<Dimension3<AxisContribution<T>>>{&self.available_acceleration}
+
+    pub fn thruster_mount_points(&self) -> &[ThrusterMountPoint<T>]{&self.thruster_mount_points}
+
+    /// dry mass properties plus current propellant
+    pub fn mass_properties(&self) -> &MassProperties<T>{&self.mass_properties}
+
+    pub fn dry_mass_properties(&self) -> &MassProperties<T>{&self.dry_mass_properties}
+    
+    /// updates ship dry mass properties(cargo loaded, hull damage, etc.) and recalculates available acceleration.
+    /// on error, the previous dry mass properties are kept
+    pub fn set_dry_mass_properties(&mut self, dry_mass_properties: MassProperties<T>) -> Result<(), FcsError<'static>>{
+        let previous = std::mem::replace(&mut self.dry_mass_properties, dry_mass_properties);
+        if let Err(error) = self.update_mass_properties(){
+            self.dry_mass_properties = previous;
+            return Err(error)
+        }
+
+        Ok(())
+    }
+
+    pub fn propellant_tanks(&self) -> &[PropellantTank<T>]{&self.propellant_tanks}
+
+    /// replaces the ship's propellant tanks. mount points reference these by index in their propellant feed, so every
+    /// feed must still name one of the new tanks. on error, the previous tanks are kept
+    pub fn set_propellant_tanks(&mut self, propellant_tanks: Vec<PropellantTank<T>>) -> Result<(), FcsError<'static>>{
+        for (index, mount_point) in self.thruster_mount_points.iter().enumerate(){
+            propulsion_control::validate_propellant_feed(
+                mount_point.propellant_feed(), 
+                propellant_tanks.len(), 
+                &format!("thruster_mount_points[{}].propellant_feed", index)
+            )?;
+        }
+        let previous = std::mem::replace(&mut self.propellant_tanks, propellant_tanks);
+        if let Err(error) = self.update_mass_properties(){
+            self.propellant_tanks = previous;
+            return Err(error)
+        }
+
+        Ok(())
+    }
+
+    /// sets which propellant tanks the mount point at the given index draws from, and recalculates available acceleration.
+    /// tanks are set first, every index must name one of them
+    pub fn set_propellant_feed(&mut self, mount_point_index: usize, propellant_feed: Vec<usize>) -> Result<(), FcsError<'static>>{
+        propulsion_control::validate_propellant_feed(&propellant_feed, self.propellant_tanks.len(), "propellant_feed")?;
+        match self.thruster_mount_points.get_mut(mount_point_index){
+            Some(mount_point) => mount_point.set_propellant_feed(propellant_feed),
+            None => return Err(FcsError::new("Tried to set propellant feed of a mount point that does not exist."))
+        }
+        self.update_available_acceleration();
+
+        Ok(())
+    }
+
+    // everything that can fail happens before anything is changed, so an error leaves the fcs as it was
+    fn update_mass_properties(&mut self) -> Result<(), FcsError<'static>>{
+        let mass_properties = propulsion_control::calculate_wet_mass_properties(&self.dry_mass_properties, &self.propellant_tanks)?;
+        if let Some(FeedbackLoops::Lqr(controller)) = self.feedback_loops.as_mut(){
+            // re-solving the riccati equation every frame of a burn is wasteful, small changes barely move the gains
+            let threshold: T = num::cast(0.01).unwrap();
+            if controller.mass_properties_changed(&mass_properties, threshold){
+                controller.set_mass_properties(&mass_properties)?;
+            }
+        }
+        self.mass_properties = mass_properties;
+        self.update_available_acceleration();
+
+        Ok(())
+    }
+
+    pub fn allocation_method(&self) -> &AllocationMethod<T>{&self.allocation_method}
+
+    /// defaults to AllocationMethod::LeastSquares
+    pub fn set_allocation_method(&mut self, allocation_method: AllocationMethod<T>) -> Result<(), FcsError<'static>>{
+        allocation_method.validate(self.thruster_mount_points.len())?;
+        self.allocation_method = allocation_method;
+
+        Ok(())
+    }
+
+    pub fn saturation_strategy(&self) -> SaturationStrategy{self.saturation_strategy}
+
+    /// defaults to SaturationStrategy::PerAxis
+    pub fn set_saturation_strategy(&mut self, saturation_strategy: SaturationStrategy){
+        self.saturation_strategy = saturation_strategy;
+    }
+
+    /// attaches a thruster to the mount point at the given index and recalculates available acceleration
+    pub fn change_thruster(&mut self, mount_point_index: usize, thruster: Thruster<T>) -> Result<(), FcsError<'static>>{
+        match self.thruster_mount_points.get_mut(mount_point_index){
+            Some(mount_point) => mount_point.change_thruster(thruster)?,
+            None => return Err(FcsError::new("Tried to attach a thruster to a mount point that does not exist."))
+        }
+        self.update_available_acceleration();
+
+        Ok(())
+    }
+
+    /// updates the health of the thruster at the given mount point and recalculates available acceleration.
+    /// allocation picks up the change on the next call to process
+    pub fn set_thruster_health(&mut self, mount_point_index: usize, thruster_health: ThrusterHealth<T>) -> Result<(), FcsError<'static>>{
+        match self.thruster_mount_points.get_mut(mount_point_index){
+            Some(mount_point) => mount_point.set_thruster_health(thruster_health),
+            None => return Err(FcsError::new("Tried to set health of a mount point that does not exist."))
+        }
+        self.update_available_acceleration();
+
+        Ok(())
+    }
+
+    /// which control axes have lost authority due to thruster damage
+    pub fn authority_report(&self) -> AuthorityReport<T>{
+        propulsion_control::calculate_authority_report(&self.thruster_mount_points, self.mass_properties.center_of_mass())
+    }
+
+    pub fn momentum_devices(&self) -> &[MomentumDevice<T>]{&self.momentum_devices}
+
+    /// replaces the ship's reaction wheels and control moment gyros and recalculates available acceleration
+    pub fn set_momentum_devices(&mut self, momentum_devices: Vec<MomentumDevice<T>>){
+        self.momentum_devices = momentum_devices;
+        self.update_available_acceleration();
+    }
+
+    pub fn momentum_dump_threshold(&self) -> T{self.momentum_dump_threshold}
+
+    /// defaults to 0.8
+    pub fn set_momentum_dump_threshold(&mut self, momentum_dump_threshold: T){
+        self.momentum_dump_threshold = num::clamp(momentum_dump_threshold, num::zero(), num::one());
+    }
+
+    /// which axes the thruster layout can control independently, and which thrusters couple translation into rotation
+    pub fn controllability_report(&self) -> ControllabilityReport<T>{
+        propulsion_control::calculate_controllability_report(&self.thruster_mount_points, &self.mass_properties)
+    }
+
+    fn update_available_acceleration(&mut self){
+        // mount points whose tanks ran dry(or were refilled) drop out of(or rejoin) the thruster suite
+        propulsion_control::update_propellant_starvation(&mut self.thruster_mount_points, &self.propellant_tanks);
+        let mut available_thrust = propulsion_control::calculate_available_thrust(&self.thruster_mount_points, self.mass_properties.center_of_mass());
+        let device_torque = momentum_devices::calculate_available_torque(&self.momentum_devices);
+        let rotational = available_thrust.rotational();
+        let combined = Dimension3::new(
+            AxisContribution::new(
+                rotational.x().positive() + device_torque.x().positive(), 
+                rotational.x().negative() + device_torque.x().negative()
+            ),
+            AxisContribution::new(
+                rotational.y().positive() + device_torque.y().positive(), 
+                rotational.y().negative() + device_torque.y().negative()
+            ),
+            AxisContribution::new(
+                rotational.z().positive() + device_torque.z().positive(), 
+                rotational.z().negative() + device_torque.z().negative()
+            )
+        );
+        *available_thrust.rotational_mut() = combined;
+        self.available_acceleration = propulsion_control::calculate_available_acceleration(&available_thrust, &self.mass_properties);
+        self.apply_gain_schedule();
+    }
+
+    pub fn process(&mut self, 
+        input: &ControlAxis<Dimension3<T>>, 
+        velocity: &ControlAxis<Dimension3<T>>,
+        position: &ControlAxis<Dimension3<T>>,
+        delta_time: T,
+    ) -> FcsOutput<T>{
+        let feedforward_acceleration = if self.autonomous_mode.enabled(){
+            input_processing::process_autonomous_mode_input(
+                input,
+                position,
+                &self.max_velocity,
+                velocity,
+                &self.available_acceleration,
+                delta_time,
+            )
+        }else{
+            input_processing::process_pilot_input(
+                input,
+                &self.linear_assist,
+                &self.rotational_assist,
+                &self.max_velocity,
+                velocity,
+                &self.available_acceleration,
+                delta_time,
+            )
+        };
+        // only the input is ramped, so feedback can still correct disturbances right away
+        let feedforward_acceleration = match self.jerk_limiter.as_mut(){
+            Some(jerk_limiter) => jerk_limiter.process(&feedforward_acceleration, delta_time),
+            None => feedforward_acceleration
+        };
+
+        let goal_position = match &self.expected_position{
+            Some(expected_position) => expected_position,
+            None => position
+        };
+        let feedback_acceleration = match self.feedback_loops.as_mut(){
+            Some(FeedbackLoops::Single(controllers)) => feedback_controller::calculate_limited(
+                goal_position,
+                position,
+                controllers, 
+                &self.available_acceleration,
+                &feedforward_acceleration,
+                delta_time, 
+            ),
+            Some(FeedbackLoops::Cascaded(controllers)) => feedback_controller::calculate_cascaded(
+                goal_position,
+                match &self.expected_velocity{
+                    Some(expected_velocity) => expected_velocity,
+                    None => velocity
+                },
+                position,
+                velocity,
+                controllers, 
+                &self.max_velocity,
+                &self.available_acceleration,
+                &feedforward_acceleration,
+                delta_time, 
+            ),
+            Some(FeedbackLoops::Lqr(controller)) => feedback_controller::calculate_lqr(
+                goal_position,
+                match &self.expected_velocity{
+                    Some(expected_velocity) => expected_velocity,
+                    None => velocity
+                },
+                position,
+                velocity,
+                controller, 
+                &self.available_acceleration,
+            ),
+            Some(FeedbackLoops::Mpc(controller)) => feedback_controller::calculate_mpc(
+                goal_position,
+                match &self.expected_velocity{
+                    Some(expected_velocity) => expected_velocity,
+                    None => velocity
+                },
+                position,
+                velocity,
+                controller, 
+                &self.max_velocity,
+                &self.available_acceleration,
+            ),
+            None => feedback_controller::calculate(
+                goal_position,
+                position,
+                &mut self.pid6dof, 
+                &self.available_acceleration,
+                delta_time, 
+            )
+        };
+    
+        let mut desired_acceleration = game_utils::sum_d3_control_axes(
+            copy_control_axis(&feedforward_acceleration), 
+            copy_control_axis(&feedback_acceleration)
+        );
+        let center_of_mass = self.mass_properties.center_of_mass();
+        let pilot_seat = Dimension3::new(
+            self.pilot_seat.x() - center_of_mass.x(),
+            self.pilot_seat.y() - center_of_mass.y(),
+            self.pilot_seat.z() - center_of_mass.z()
+        );
+        let mut limiting_g_limit_profile = None;
+        if self.gsafety.enabled(){
+            // a fatigued pilot gets tighter limits
+            let tightened_max_acceleration = self.g_tolerance_model.as_ref()
+                .map(|model| model.apply_to_limits(&self.gsafety_max_acceleration));
+            let gsafety_max_acceleration = tightened_max_acceleration.as_ref().unwrap_or(&self.gsafety_max_acceleration);
+            desired_acceleration = match self.gsafety_mode{
+                GSafetyMode::PerAxis => saturation::saturate(
+                    &desired_acceleration, 
+                    gsafety_max_acceleration,
+                    self.saturation_strategy
+                ),
+                GSafetyMode::VectorMagnitude => g_force_safety::process_vector_magnitude(
+                    &desired_acceleration, 
+                    gsafety_max_acceleration, 
+                    velocity.rotational(), 
+                    &pilot_seat
+                )
+            };
+            if !self.g_limit_profiles.is_empty(){
+                (desired_acceleration, limiting_g_limit_profile) = g_force_safety::process_profiles(
+                    &desired_acceleration, 
+                    &self.g_limit_profiles, 
+                    velocity.rotational(), 
+                    center_of_mass,
+                    self.gsafety_mode
+                ).into_parts();
+            }
+        }
+        if self.saturation_strategy != SaturationStrategy::PerAxis{
+            desired_acceleration = propulsion_control::saturate_to_attainable(
+                &desired_acceleration, 
+                &self.thruster_mount_points, 
+                &self.momentum_devices, 
+                &self.mass_properties, 
+                self.saturation_strategy
+            )
+        }
+        if let Some(jerk_limiter) = self.jerk_limiter.as_mut(){
+            // feedforward that made it through limiting, taken as what is left after feedback. only ever shrinks it
+            // toward zero, so feedback can't drag the ramp past, or to the other side of, the input
+            let delivered_feedforward = map_control_axes(&desired_acceleration, &feedback_acceleration, |desired, feedback| desired - feedback);
+            jerk_limiter.set_previous_acceleration(map_control_axes(
+                &feedforward_acceleration, 
+                &delivered_feedforward, 
+                |feedforward, delivered| num::clamp(delivered, feedforward.min(num::zero()), feedforward.max(num::zero()))
+            ));
+        }
+    
+        // if advanced propulsion simulation is desired, feed desired accel values to propulsion control
+        // otherwise feed desired accel values directly to physics sim
+
+        //Once the desired linear and rotational accelerations are established from the combined feedforward
+        //and feedback control signals, the PCS must calculate the output of individual thrusters, as well as other
+        //devices tasked with generating motion, so that these accelerations will be achieved to within a
+        //reasonable degree of accuracy.
+        let blended_output = propulsion_control::calculate_blended_output(
+            &desired_acceleration, 
+            &self.thruster_mount_points,
+            &self.momentum_devices,
+            &self.mass_properties,
+            &self.allocation_method,
+            self.momentum_dump_threshold,
+            delta_time
+        ).expect("allocation method is validated against the mount points when set");
+        for (device, torque) in self.momentum_devices.iter_mut().zip(blended_output.momentum_device_torques().iter()){
+            device.apply_torque(*torque, delta_time);
+        }
+        if !self.momentum_devices.is_empty(){
+            // stored momentum changed, so devices may have saturated or come out of saturation
+            self.update_available_acceleration();
+        }
+        let (thruster_output, momentum_device_torques) = blended_output.into_parts();
+        let delivered_thrust = self.thruster_dynamics.update(
+            &self.thruster_mount_points, 
+            thruster_output.throttles(), 
+            thruster_output.gimbal_angles(), 
+            self.mass_properties.center_of_mass(), 
+            delta_time
+        );
+
+        if let Some(model) = self.g_tolerance_model.as_mut(){
+            let wrench = delivered_thrust.wrench();
+            let mass = self.mass_properties.mass();
+            model.update(
+                &g_force_safety::seat_acceleration(
+                    &Dimension3::new(wrench.linear().x() / mass, wrench.linear().y() / mass, wrench.linear().z() / mass),
+                    &self.mass_properties.angular_acceleration(wrench.rotational()),
+                    velocity.rotational(),
+                    &pilot_seat
+                ), 
+                delta_time
+            );
+        }
+
+        let propellant_usage = propulsion_control::consume_propellant(
+            &self.thruster_mount_points, 
+            delivered_thrust.throttles(), 
+            &mut self.propellant_tanks, 
+            delta_time
+        );
+        // mass properties stay as they were if they can't be updated(e.g. LQR gains fail to re-solve)
+        let mass_properties_error = if propellant_usage.mass_flow() > num::zero(){
+            self.update_mass_properties().err()
+        }else{None};
+    
+        // calculate expected position from commanded acceleration and current velocity
+        // p = p0 + v * dt + 0.5 * a * dt^2
+        let half: T = num::cast(0.5).unwrap();
+        let velocity_term = map_control_axes(position, velocity, |position, velocity| position + (velocity * delta_time));
+        self.expected_position = Some(map_control_axes(
+            &velocity_term, 
+            &desired_acceleration, 
+            |position, acceleration| position + (half * acceleration * delta_time * delta_time)
+        ));
+        // v = v0 + a * dt
+        self.expected_velocity = Some(map_control_axes(velocity, &desired_acceleration, |velocity, acceleration| velocity + (acceleration * delta_time)));
+
+        FcsOutput{
+            feedforward_acceleration,
+            feedback_acceleration,
+            desired_acceleration,
+            thruster_output,
+            delivered_thrust,
+            propellant_usage,
+            momentum_device_torques,
+            limiting_g_limit_profile,
+            mass_properties_error,
+        }
+    }
+}
+
+
+
+/// results of a single FlightControlSystem::process call
+pub struct FcsOutput<T>{
+    feedforward_acceleration: ControlAxis<Dimension3<T>>,
+    feedback_acceleration: ControlAxis<Dimension3<T>>,
+    // sum of feedforward and feedback, after g-force safety is applied(if enabled)
+    desired_acceleration: ControlAxis<Dimension3<T>>,
+    thruster_output: ThrusterOutput<T>,
+    // what the thrusters actually produced this frame, after spool up/down, dead time, etc.
+    delivered_thrust: DeliveredThrust<T>,
+    propellant_usage: PropellantUsage<T>,
+    // torque produced by each momentum exchange device, along its torque axis
+    momentum_device_torques: Vec<T>,
+    // index into FlightControlSystem::g_limit_profiles of the profile that limited desired acceleration, if any
+    limiting_g_limit_profile: Option<usize>,
+    // why mass properties couldn't be updated after burning propellant, if they couldn't
+    mass_properties_error: Option<FcsError<'static>>,
+}
+impl<T> FcsOutput<T>{
+    pub fn feedforward_acceleration(&self) -> &ControlAxis<Dimension3<T>>{&self.feedforward_acceleration}
+    pub fn feedback_acceleration(&self) -> &ControlAxis<Dimension3<T>>{&self.feedback_acceleration}
+    pub fn desired_acceleration(&self) -> &ControlAxis<Dimension3<T>>{&self.desired_acceleration}
+    pub fn thruster_output(&self) -> &ThrusterOutput<T>{&self.thruster_output}
+    pub fn delivered_thrust(&self) -> &DeliveredThrust<T>{&self.delivered_thrust}
+    pub fn propellant_usage(&self) -> &PropellantUsage<T>{&self.propellant_usage}
+    pub fn momentum_device_torques(&self) -> &[T]{&self.momentum_device_torques}
+    pub fn limiting_g_limit_profile(&self) -> Option<usize>{self.limiting_g_limit_profile}
+    pub fn mass_properties_error(&self) -> Option<&FcsError<'static>>{self.mass_properties_error.as_ref()}
+}
 
 
 
@@ -140,15 +714,28 @@ pub mod propulsion_control;
 pub struct FcsError<'a>{
     //source: //line and column number where error is returned
     message: &'a str,
+    // path to the offending value, for errors caused by invalid configuration. e.g. "thruster_mount_points[2].thruster.max_thrust"
+    field: Option<String>,
 }
 impl<'a> FcsError<'a>{
     pub fn new(message: &'a str) -> Self{
-        Self{message}
+        Self{message, field: None}
     }
+
+    pub fn with_field(message: &'a str, field: String) -> Self{
+        Self{message, field: Some(field)}
+    }
+
+    pub fn message(&self) -> &str{self.message}
+
+    pub fn field(&self) -> Option<&str>{self.field.as_deref()}
 }
 impl<'a> std::fmt::Display for FcsError<'a>{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
-        write!(f, "{}", self.message)
+        match &self.field{
+            Some(field) => write!(f, "{}: {}", field, self.message),
+            None => write!(f, "{}", self.message)
+        }
     }
 }
 impl<'a> std::error::Error for FcsError<'a>{}
@@ -165,4 +752,727 @@ pub fn multiply_compare_zero<T: Float + num::Zero>(input: T, high_multiplier: T,
         input * low_multiplier
     }
     else{num::zero()}
-}
\ No newline at end of file
+}
+
+
+
+/// applies f to each matching pair of components from a and b
+pub(crate) fn map_control_axes<T, F>(
+    a: &ControlAxis<Dimension3<T>>, 
+    b: &ControlAxis<Dimension3<T>>, 
+    f: F
+) -> ControlAxis<Dimension3<T>>
+    where 
+        T: Float,
+        F: Fn(T, T) -> T
+{
+    ControlAxis::new(
+        Dimension3::new(
+            f(a.linear().x(), b.linear().x()),
+            f(a.linear().y(), b.linear().y()),
+            f(a.linear().z(), b.linear().z())
+        ),
+        Dimension3::new(
+            f(a.rotational().x(), b.rotational().x()),
+            f(a.rotational().y(), b.rotational().y()),
+            f(a.rotational().z(), b.rotational().z())
+        )
+    )
+}
+
+
+
+
+
+pub(crate) fn cross3<T: Float>(a: &[T; 3], b: &[T; 3]) -> [T; 3]{
+    [
+        (a[1] * b[2]) - (a[2] * b[1]),
+        (a[2] * b[0]) - (a[0] * b[2]),
+        (a[0] * b[1]) - (a[1] * b[0])
+    ]
+}
+
+pub(crate) fn dot3<T: Float>(a: &[T; 3], b: &[T; 3]) -> T{
+    (a[0] * b[0]) + (a[1] * b[1]) + (a[2] * b[2])
+}
+
+/// unchanged if a is (close to) zero length
+pub(crate) fn normalize3<T: Float>(a: [T; 3]) -> [T; 3]{
+    let magnitude = dot3(&a, &a).sqrt();
+    if magnitude <= T::epsilon(){return a}
+    [a[0] / magnitude, a[1] / magnitude, a[2] / magnitude]
+}
+
+
+
+
+
+// PID based feedback loops(including the default, None) can take scheduled gains
+fn accepts_gain_schedule<T>(feedback_loops: &Option<FeedbackLoops<T>>) -> bool{
+    !matches!(feedback_loops, Some(FeedbackLoops::Lqr(_)) | Some(FeedbackLoops::Mpc(_)))
+}
+
+/// ControlAxis isn't Clone, so values that are both kept and passed on by value need copying
+pub(crate) fn copy_control_axis<T>(control_axis: &ControlAxis<Dimension3<T>>) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    map_control_axes(control_axis, control_axis, |value, _| value)
+}
+
+/// flattens into [linear x, linear y, linear z, rotational x, rotational y, rotational z]
+pub(crate) fn control_axis_to_array<T>(control_axis: &ControlAxis<Dimension3<T>>) -> [T; 6]
+    where T: Copy
+{
+    [
+        control_axis.linear().x(), 
+        control_axis.linear().y(), 
+        control_axis.linear().z(),
+        control_axis.rotational().x(), 
+        control_axis.rotational().y(), 
+        control_axis.rotational().z()
+    ]
+}
+
+/// inverse of control_axis_to_array
+pub(crate) fn array_to_control_axis<T>(array: &[T; 6]) -> ControlAxis<Dimension3<T>>
+    where T: Copy
+{
+    ControlAxis::new(
+        Dimension3::new(array[0], array[1], array[2]),
+        Dimension3::new(array[3], array[4], array[5])
+    )
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests{
+    use game_utils::{
+        control_axis::{ControlAxis, AxisContribution}, 
+        dimension3::{Dimension3, Vector3},
+        toggle::Toggle,
+    };
+    use pid_controller::PID;
+    use crate::{
+        FlightControlSystem,
+        map_control_axes,
+        propulsion_control::{
+            Thruster, 
+            ThrusterSize, 
+            ThrusterMountPoint, 
+            ThrusterHealth, 
+            ThrustDirection, 
+            MassProperties, 
+            AllocationMethod,
+            PropellantConsumption,
+            PropellantTank,
+            STANDARD_GRAVITY,
+        },
+        momentum_devices::{MomentumDevice, ReactionWheel},
+        saturation::SaturationStrategy,
+        g_force_safety::{GSafetyMode, GToleranceModel, PilotState, GLimitProfile},
+        jerk_limiting::{JerkLimiter, JerkLimitMode},
+        feedback_controller::{PidController, AntiWindup, FeedbackLoops, LqrController, MpcController, GainSchedule, GainSchedulePoint, PidGains, ScheduleVariable},
+    };
+
+    fn thruster_suite() -> Vec<ThrusterMountPoint<f64>>{
+        [
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            Vector3::new(-1.0, 0.0, 0.0, 1.0),
+            Vector3::new(0.0, 1.0, 0.0, 1.0),
+            Vector3::new(0.0, -1.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, 1.0, 1.0),
+            Vector3::new(0.0, 0.0, -1.0, 1.0),
+        ].into_iter().map(|thrust_direction| 
+            ThrusterMountPoint::new(
+                Some(Thruster::new(20_000.0, ThrusterSize::Small)),
+                thrust_direction,
+                ThrusterSize::Small,
+                Dimension3::default(0.0),
+            )
+        ).collect()
+    }
+
+    fn pid6dof(kp: f64) -> ControlAxis<Dimension3<PID<f64>>>{
+        ControlAxis::new(
+            Dimension3::new(PID::new(kp, 0.0, 0.0), PID::new(kp, 0.0, 0.0), PID::new(kp, 0.0, 0.0)),
+            Dimension3::new(PID::new(kp, 0.0, 0.0), PID::new(kp, 0.0, 0.0), PID::new(kp, 0.0, 0.0))
+        )
+    }
+
+    fn flight_control_system(assists: bool, gsafety: bool) -> FlightControlSystem<f64>{
+        FlightControlSystem::new(
+            Toggle::new(assists),
+            Toggle::new(assists),
+            Toggle::new(false),
+            ControlAxis::new(Dimension3::default(50.0), Dimension3::default(50.0)),
+            Toggle::new(gsafety),
+            ControlAxis::new(
+                Dimension3::default(AxisContribution::new(5.0, 5.0)), 
+                Dimension3::default(AxisContribution::new(5.0, 5.0))
+            ),
+            pid6dof(0.1),
+            thruster_suite(),
+            MassProperties::new(
+                2_000.0, 
+                Dimension3::default(0.0), 
+                [[1_000.0, 0.0, 0.0], [0.0, 1_000.0, 0.0], [0.0, 0.0, 1_000.0]]
+            ).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_process_full_input_saturates_available_acceleration(){
+        let mut fcs = flight_control_system(true, false);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::default(1.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+
+        // 6 x 20_000N thrusters / 2_000kg = 10m/s^2 available on each linear axis
+        assert!((output.desired_acceleration().linear().x() - 10.0).abs() < 0.001);
+        assert!((output.desired_acceleration().linear().y() - 10.0).abs() < 0.001);
+        assert!((output.desired_acceleration().linear().z() - 10.0).abs() < 0.001);
+        assert!((output.feedback_acceleration().linear().x() - 0.0).abs() < 0.001);
+
+        assert!((output.thruster_output().achieved_wrench().linear().x() - 20_000.0).abs() < 0.001);
+        assert!((output.thruster_output().achieved_wrench().linear().y() - 20_000.0).abs() < 0.001);
+        assert!((output.thruster_output().achieved_wrench().linear().z() - 20_000.0).abs() < 0.001);
+        assert!((output.thruster_output().throttles()[0] - 1.0).abs() < 0.001);
+        assert!((output.thruster_output().throttles()[1] - 0.0).abs() < 0.001);
+        // thrusters respond instantly by default
+        assert!((output.delivered_thrust().wrench().linear().x() - 20_000.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_process_gsafety_limits_thruster_output(){
+        let mut fcs = flight_control_system(true, true);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::default(-1.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+
+        assert!((output.feedforward_acceleration().linear().x() - (-10.0)).abs() < 0.001);
+        assert!((output.desired_acceleration().linear().x() - (-5.0)).abs() < 0.001);
+        assert!((output.thruster_output().achieved_wrench().linear().x() - (-10_000.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_process_with_minimum_fuel_allocation(){
+        let mut fcs = flight_control_system(true, false);
+        fcs.set_allocation_method(AllocationMethod::MinimumFuel).unwrap();
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(0.1, -0.1, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+
+        // 0.1 * 50m/s in 1s = 5m/s^2 * 2000kg = 10_000N
+        assert!((output.thruster_output().throttles()[0] - 0.5).abs() < 0.001);
+        assert!((output.thruster_output().throttles()[1] - 0.0).abs() < 0.001);
+        assert!((output.thruster_output().throttles()[2] - 0.0).abs() < 0.001);
+        assert!((output.thruster_output().throttles()[3] - 0.5).abs() < 0.001);
+
+        // one weight short of the six mount points
+        let error = fcs.set_allocation_method(AllocationMethod::WeightedThrottle(vec![1.0; 5])).unwrap_err();
+        assert_eq!(error.field(), Some("WeightedThrottle"));
+        assert!(fcs.set_allocation_method(AllocationMethod::WeightedThrottle(vec![1.0; 6])).is_ok());
+    }
+
+    #[test]
+    fn test_process_feedback_corrects_deviation_from_expected_position(){
+        let mut fcs = flight_control_system(false, false);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let _ = fcs.process(&zero, &zero, &zero, 1.0);
+
+        // no input was given, so ship was expected to stay at origin, but has drifted in -x
+        let output = fcs.process(
+            &zero, 
+            &zero, 
+            &ControlAxis::new(Dimension3::new(-1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+            1.0
+        );
+
+        assert!(output.feedback_acceleration().linear().x() > 0.0);
+        assert!(output.thruster_output().achieved_wrench().linear().x() > 0.0);
+        assert!((output.thruster_output().achieved_wrench().linear().y() - 0.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_process_autonomous_mode_moves_toward_goal(){
+        let mut fcs = flight_control_system(true, false);
+        *fcs.autonomous_mode_mut() = Toggle::new(true);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(100.0, -100.0, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+
+        assert!((output.thruster_output().achieved_wrench().linear().x() - 20_000.0).abs() < 0.001);
+        assert!((output.thruster_output().achieved_wrench().linear().y() - (-20_000.0)).abs() < 0.001);
+        assert!((output.thruster_output().achieved_wrench().linear().z() - 0.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_change_thruster_updates_available_acceleration(){
+        let mut fcs = flight_control_system(true, false);
+
+        assert!(fcs.change_thruster(0, Thruster::new(40_000.0, ThrusterSize::Small)).is_ok());
+        assert!((fcs.available_acceleration().linear().x().positive() - 20.0).abs() < 0.001);
+        assert!((fcs.available_acceleration().linear().x().negative() - 10.0).abs() < 0.001);
+
+        assert!(fcs.change_thruster(0, Thruster::new(40_000.0, ThrusterSize::Large)).is_err());
+        assert!(fcs.change_thruster(6, Thruster::new(40_000.0, ThrusterSize::Small)).is_err());
+    }
+
+    #[test]
+    fn test_process_burns_propellant_and_updates_mass(){
+        let mut fcs = flight_control_system(true, false);
+        let mut thruster = Thruster::new(20_000.0, ThrusterSize::Small);
+        thruster.set_propellant_consumption(Some(PropellantConsumption::FuelFlow{minimum: 10.0, maximum: 100.0}));
+        assert!(fcs.change_thruster(0, thruster).is_ok());
+        assert!(fcs.set_propellant_tanks(vec![PropellantTank::new(2_000.0, 2_000.0, Dimension3::default(0.0))]).is_ok());
+        assert!(fcs.set_propellant_feed(0, vec![0]).is_ok());
+        assert!((fcs.mass_properties().mass() - 4_000.0).abs() < 0.001);
+        assert!((fcs.available_acceleration().linear().x().positive() - 5.0).abs() < 0.001);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+
+        assert!((output.propellant_usage().mass_flow() - 100.0).abs() < 0.001);
+        assert!((fcs.propellant_tanks()[0].level() - 1_900.0).abs() < 0.001);
+        assert!((fcs.mass_properties().mass() - 3_900.0).abs() < 0.001);
+        assert!(fcs.available_acceleration().linear().x().positive() > 5.0);
+        assert!(output.mass_properties_error().is_none());
+    }
+
+    #[test]
+    fn test_propellant_feed_must_reference_tanks(){
+        let mut fcs = flight_control_system(true, false);
+        let error = fcs.set_propellant_feed(0, vec![0]).unwrap_err();
+        assert_eq!(error.field(), Some("propellant_feed[0]"));
+
+        assert!(fcs.set_propellant_tanks(vec![PropellantTank::new(2_000.0, 2_000.0, Dimension3::default(0.0))]).is_ok());
+        let error = fcs.set_propellant_feed(0, vec![0, 1]).unwrap_err();
+        assert_eq!(error.field(), Some("propellant_feed[1]"));
+        assert!(fcs.thruster_mount_points()[0].propellant_feed().is_empty());
+        assert!(fcs.set_propellant_feed(0, vec![0]).is_ok());
+
+        // removing a tank a mount point still draws from is rejected, and the tank is kept
+        let error = fcs.set_propellant_tanks(Vec::new()).unwrap_err();
+        assert_eq!(error.field(), Some("thruster_mount_points[0].propellant_feed[0]"));
+        assert_eq!(fcs.propellant_tanks().len(), 1);
+    }
+
+    #[test]
+    fn test_process_starved_thruster_stops_thrusting(){
+        let mut fcs = flight_control_system(true, false);
+        let mut thruster = Thruster::new(20_000.0, ThrusterSize::Small);
+        thruster.set_propellant_consumption(Some(PropellantConsumption::FuelFlow{minimum: 10.0, maximum: 100.0}));
+        // no propellant feed, so the thruster is starved from the start
+        assert!(fcs.change_thruster(0, thruster).is_ok());
+        assert!(fcs.thruster_mount_points()[0].propellant_starved());
+        assert!(fcs.available_acceleration().linear().x().positive().abs() < 0.001);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+        let forward = ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(&forward, &zero, &zero, 1.0);
+        assert!(output.delivered_thrust().wrench().linear().x().abs() < 0.001);
+        assert_eq!(output.propellant_usage().starved(), &[0]);
+
+        // 150kg is enough for one full throttle second, then the tank runs dry
+        assert!(fcs.set_propellant_tanks(vec![PropellantTank::new(150.0, 150.0, Dimension3::default(0.0))]).is_ok());
+        assert!(fcs.set_propellant_feed(0, vec![0]).is_ok());
+        assert!(!fcs.thruster_mount_points()[0].propellant_starved());
+        let output = fcs.process(&forward, &zero, &zero, 1.0);
+        assert!(output.delivered_thrust().wrench().linear().x() > 0.0);
+        let _ = fcs.process(&forward, &zero, &zero, 1.0);
+        assert!(fcs.propellant_tanks()[0].is_empty());
+        assert!(fcs.thruster_mount_points()[0].propellant_starved());
+        let output = fcs.process(&forward, &zero, &zero, 1.0);
+        assert!(output.delivered_thrust().wrench().linear().x().abs() < 0.001);
+        assert!(output.thruster_output().throttles()[0].abs() < 0.001);
+    }
+
+    #[test]
+    fn test_set_thruster_health_reconfigures_allocation(){
+        let mut fcs = flight_control_system(true, false);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        assert!(fcs.set_thruster_health(1, ThrusterHealth::FailedOff).is_ok());
+        assert!(fcs.set_thruster_health(6, ThrusterHealth::FailedOff).is_err());
+        assert!((fcs.available_acceleration().linear().x().negative() - 0.0).abs() < 0.001);
+        assert_eq!(fcs.authority_report().lost_authority(), &[ThrustDirection::LinXNeg]);
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(-1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+        assert!((output.thruster_output().throttles()[1] - 0.0).abs() < 0.001);
+        assert!((output.desired_acceleration().linear().x() - 0.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_process_prefers_reaction_wheels_for_rotation(){
+        let mut fcs = flight_control_system(true, false);
+        // thrusters are all mounted at the center of mass, so only the wheel can rotate the ship
+        assert!((fcs.available_acceleration().rotational().z().positive() - 0.0).abs() < 0.001);
+        fcs.set_momentum_devices(vec![MomentumDevice::ReactionWheel(ReactionWheel::new(Dimension3::new(0.0, 0.0, 1.0), 100.0, 150.0))]);
+        assert!((fcs.available_acceleration().rotational().z().positive() - 0.1).abs() < 0.001);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::default(0.0), Dimension3::new(0.0, 0.0, 1.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+        assert!((output.momentum_device_torques()[0] - 100.0).abs() < 0.001);
+        assert!(output.thruster_output().throttles().iter().all(|throttle| throttle.abs() < 0.001));
+
+        // the wheel can only absorb another 50N·m·s
+        let _ = fcs.process(
+            &ControlAxis::new(Dimension3::default(0.0), Dimension3::new(0.0, 0.0, 1.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+        assert!((fcs.momentum_devices()[0].saturation() - 1.0).abs() < 0.001);
+        assert!((fcs.available_acceleration().rotational().z().positive() - 0.0).abs() < 0.001);
+        assert!((fcs.available_acceleration().rotational().z().negative() - 0.1).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_process_uniform_scaling_keeps_command_direction(){
+        let mut fcs = flight_control_system(true, true);
+        fcs.set_saturation_strategy(SaturationStrategy::UniformScaling);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        // 10m/s^2 in x and 2.5m/s^2 in y, x is over the 5m/s^2 g-force safety limit
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(0.2, 0.05, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+        assert!((output.desired_acceleration().linear().x() - 5.0).abs() < 0.001);
+        assert!((output.desired_acceleration().linear().y() - 1.25).abs() < 0.001);
+        assert!((output.thruster_output().achieved_wrench().linear().y() - 2_500.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_process_vector_magnitude_gsafety(){
+        let mut fcs = flight_control_system(true, true);
+        fcs.set_gsafety_mode(GSafetyMode::VectorMagnitude);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(0.1, 0.1, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+        // 5m/s^2 in both x and y is ~7.07m/s^2 total, brought back to 5
+        assert!((output.desired_acceleration().linear().x() - 12.5_f64.sqrt()).abs() < 0.001);
+        assert!((output.desired_acceleration().linear().y() - 12.5_f64.sqrt()).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_process_feedback_loops_replace_pid6dof(){
+        let mut fcs = flight_control_system(false, false);
+        let controller = || {
+            let mut controller = PidController::new(1.0, 1.0, 0.0);
+            controller.set_anti_windup(AntiWindup::ConditionalIntegration);
+            controller
+        };
+        assert!(fcs.set_feedback_loops(Some(FeedbackLoops::Single(ControlAxis::new(
+            Dimension3::new(controller(), controller(), controller()), 
+            Dimension3::new(controller(), controller(), controller())
+        )))).is_ok());
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        // the ship never moves, so it falls further behind expected position every frame
+        let _ = fcs.process(&ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), &zero, &zero, 1.0);
+        for _ in 0..10{
+            let output = fcs.process(&zero, &zero, &zero, 1.0);
+            // saturated at full available acceleration
+            assert!((output.feedback_acceleration().linear().x() - fcs.available_acceleration().linear().x().positive()).abs() < 0.001);
+        }
+        match fcs.feedback_loops_mut(){
+            Some(FeedbackLoops::Single(controllers)) => assert!(controllers.linear_mut().x_mut().integral_term().abs() < 1.0),
+            _ => panic!("feedback loops should be single")
+        }
+    }
+
+    #[test]
+    fn test_process_lqr_feedback_loops(){
+        let mut fcs = flight_control_system(false, false);
+        let weights = |weight: f64| ControlAxis::new(Dimension3::default(weight), Dimension3::default(weight));
+        let controller = LqrController::new(fcs.mass_properties(), weights(1.0), weights(1.0), weights(1e-6), 1.0).unwrap();
+        assert!(fcs.set_feedback_loops(Some(FeedbackLoops::Lqr(controller))).is_ok());
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let _ = fcs.process(&zero, &zero, &zero, 1.0);
+        // pushed off course, feedback pushes back toward expected position
+        let output = fcs.process(&zero, &zero, &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), 1.0);
+        assert!(output.feedback_acceleration().linear().x() < 0.0);
+        assert!(output.feedback_acceleration().linear().y().abs() < 0.001);
+
+        // gains are re-solved once the ship is noticeably heavier
+        assert!(fcs.set_propellant_tanks(vec![PropellantTank::new(1_000.0, 1_000.0, Dimension3::default(0.0))]).is_ok());
+        match fcs.feedback_loops(){
+            Some(FeedbackLoops::Lqr(controller)) => assert!((controller.design_mass() - fcs.mass_properties().mass()).abs() < 0.001),
+            _ => panic!("feedback loops should be LQR")
+        }
+
+        // or harder to turn, at the same mass
+        assert!(fcs.set_dry_mass_properties(MassProperties::new(
+            2_000.0, 
+            Dimension3::default(0.0), 
+            [[2_000.0, 0.0, 0.0], [0.0, 1_000.0, 0.0], [0.0, 0.0, 1_000.0]]
+        ).unwrap()).is_ok());
+        match fcs.feedback_loops(){
+            Some(FeedbackLoops::Lqr(controller)) => {
+                assert!((controller.design_mass() - 3_000.0).abs() < 0.001);
+                assert!((controller.design_inertia_tensor()[0][0] - fcs.mass_properties().inertia_tensor()[0][0]).abs() < 0.001);
+            },
+            _ => panic!("feedback loops should be LQR")
+        }
+    }
+
+    #[test]
+    fn test_process_mpc_feedback_loops(){
+        let mut fcs = flight_control_system(false, false);
+        let weights = |weight: f64| ControlAxis::new(Dimension3::default(weight), Dimension3::default(weight));
+        assert!(fcs.set_feedback_loops(Some(FeedbackLoops::Mpc(MpcController::new(10, 1.0, weights(1.0), weights(1.0), weights(0.01)).unwrap()))).is_ok());
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let _ = fcs.process(&zero, &zero, &zero, 1.0);
+        let output = fcs.process(&zero, &zero, &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), 1.0);
+        let feedback = output.feedback_acceleration().linear().x();
+        assert!(feedback < 0.0);
+        assert!(feedback >= -fcs.available_acceleration().linear().x().negative());
+    }
+
+    #[test]
+    fn test_gain_schedule_follows_propellant_mass(){
+        let mut fcs = flight_control_system(true, true);
+        let gains = |kp: f64| ControlAxis::new(Dimension3::default(PidGains::new(kp, 0.0, 0.0)), Dimension3::default(PidGains::new(kp, 0.0, 0.0)));
+        let key = |mass: f64| ControlAxis::new(Dimension3::default(mass), Dimension3::default(mass));
+        assert!(fcs.set_gain_schedule(Some(GainSchedule::new(
+            ScheduleVariable::Mass, 
+            vec![GainSchedulePoint::new(key(2_000.0), gains(0.1)), GainSchedulePoint::new(key(4_000.0), gains(0.3))]
+        ).unwrap())).is_ok());
+        assert!((fcs.scheduled_gains().as_ref().unwrap().linear().x().kp() - 0.1).abs() < 0.001);
+
+        assert!(fcs.set_propellant_tanks(vec![PropellantTank::new(1_000.0, 1_000.0, Dimension3::default(0.0))]).is_ok());
+        assert!((fcs.scheduled_gains().as_ref().unwrap().linear().x().kp() - 0.2).abs() < 0.001);
+
+        // controllers set later pick up the current gains
+        assert!(fcs.set_feedback_loops(Some(FeedbackLoops::Single(ControlAxis::new(
+            Dimension3::new(PidController::new(1.0, 0.0, 0.0), PidController::new(1.0, 0.0, 0.0), PidController::new(1.0, 0.0, 0.0)), 
+            Dimension3::new(PidController::new(1.0, 0.0, 0.0), PidController::new(1.0, 0.0, 0.0), PidController::new(1.0, 0.0, 0.0))
+        )))).is_ok());
+        match fcs.feedback_loops_mut(){
+            Some(FeedbackLoops::Single(controllers)) => assert!((controllers.rotational_mut().z_mut().kp() - 0.2).abs() < 0.001),
+            _ => panic!("feedback loops should be single")
+        }
+
+        // LQR can't take scheduled gains
+        let weights = |weight: f64| ControlAxis::new(Dimension3::default(weight), Dimension3::default(weight));
+        let controller = LqrController::new(fcs.mass_properties(), weights(1.0), weights(1.0), weights(1e-6), 1.0).unwrap();
+        assert!(fcs.set_feedback_loops(Some(FeedbackLoops::Lqr(controller))).is_err());
+        assert!(matches!(fcs.feedback_loops(), Some(FeedbackLoops::Single(_))));
+    }
+
+    #[test]
+    fn test_gain_schedule_keeps_integrator_while_burning(){
+        let mut fcs = flight_control_system(false, false);
+        let mut thruster = Thruster::new(20_000.0, ThrusterSize::Small);
+        thruster.set_propellant_consumption(Some(PropellantConsumption::FuelFlow{minimum: 10.0, maximum: 100.0}));
+        assert!(fcs.change_thruster(0, thruster).is_ok());
+        assert!(fcs.set_propellant_tanks(vec![PropellantTank::new(2_000.0, 2_000.0, Dimension3::default(0.0))]).is_ok());
+        assert!(fcs.set_propellant_feed(0, vec![0]).is_ok());
+        let gains = |kp: f64| ControlAxis::new(Dimension3::default(PidGains::new(kp, kp * 0.1, 0.0)), Dimension3::default(PidGains::new(kp, kp * 0.1, 0.0)));
+        let key = |mass: f64| ControlAxis::new(Dimension3::default(mass), Dimension3::default(mass));
+        assert!(fcs.set_gain_schedule(Some(GainSchedule::new(
+            ScheduleVariable::Mass, 
+            vec![GainSchedulePoint::new(key(2_000.0), gains(0.1)), GainSchedulePoint::new(key(4_000.0), gains(0.3))]
+        ).unwrap())).is_ok());
+        let starting_kp = fcs.scheduled_gains().as_ref().unwrap().linear().y().kp();
+        // the default pid6dof is swapped for PidControllers with the 0.3 kp the full tank schedules
+        match fcs.feedback_loops_mut(){
+            Some(FeedbackLoops::Single(controllers)) => assert!((controllers.linear_mut().y_mut().kp() - 0.3).abs() < 0.001),
+            _ => panic!("feedback loops should be single")
+        }
+
+        // burning along x while the ship keeps drifting in -y, so the y integrator builds up
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+        let mut previous_integral = 0.0;
+        for frame in 0..6{
+            let output = fcs.process(
+                &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+                &zero, 
+                &ControlAxis::new(Dimension3::new(0.0, -(frame as f64), 0.0), Dimension3::default(0.0)), 
+                1.0
+            );
+            assert!(output.propellant_usage().mass_flow() > 0.0);
+            let integral = match fcs.feedback_loops_mut(){
+                Some(FeedbackLoops::Single(controllers)) => controllers.linear_mut().y_mut().integral_term(),
+                _ => panic!("feedback loops should be single")
+            };
+            // nothing to correct on the first frame
+            if frame > 0{assert!(integral > previous_integral)}
+            previous_integral = integral;
+        }
+        // gains followed the falling mass the whole time
+        assert!(fcs.scheduled_gains().as_ref().unwrap().linear().y().kp() < starting_kp);
+    }
+
+    #[test]
+    fn test_process_jerk_limiting_ramps_pilot_input(){
+        let mut fcs = flight_control_system(true, true);
+        fcs.set_jerk_limiter(Some(JerkLimiter::new(
+            ControlAxis::new(Dimension3::default(2.0), Dimension3::default(1.0)), 
+            JerkLimitMode::PerAxis
+        )));
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+        let input = ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0));
+
+        // the ship goes exactly where it was commanded, so there is nothing for feedback to correct
+        let mut position = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+        let mut velocity = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+        let mut desired_x = Vec::new();
+        for input in [&input, &input, &input, &zero]{
+            let output = fcs.process(input, &velocity, &position, 1.0);
+            let acceleration = output.desired_acceleration();
+            position = map_control_axes(
+                &map_control_axes(&position, &velocity, |position, velocity| position + velocity), 
+                acceleration, 
+                |position, acceleration| position + (0.5 * acceleration)
+            );
+            velocity = map_control_axes(&velocity, acceleration, |velocity, acceleration| velocity + acceleration);
+            desired_x.push(acceleration.linear().x());
+        }
+        assert!((desired_x[0] - 2.0).abs() < 0.001);
+        assert!((desired_x[1] - 4.0).abs() < 0.001);
+        // ramped up to the g-force safety limit
+        assert!((desired_x[2] - 5.0).abs() < 0.001);
+        // ramps down from the limit, not from where the limiter would have been without g-force safety
+        assert!((desired_x[3] - 3.0).abs() < 0.001);
+
+        // feedback isn't ramped, so a drift is corrected right away
+        let mut fcs = flight_control_system(false, false);
+        fcs.set_jerk_limiter(Some(JerkLimiter::new(
+            ControlAxis::new(Dimension3::default(0.001), Dimension3::default(0.001)), 
+            JerkLimitMode::PerAxis
+        )));
+        let _ = fcs.process(&zero, &zero, &zero, 1.0);
+        let output = fcs.process(
+            &zero, 
+            &zero, 
+            &ControlAxis::new(Dimension3::new(-1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+            1.0
+        );
+        assert!(output.feedback_acceleration().linear().x() > 0.01);
+        assert!((output.desired_acceleration().linear().x() - output.feedback_acceleration().linear().x()).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_process_reports_limiting_g_limit_profile(){
+        let mut fcs = flight_control_system(true, true);
+        fcs.set_saturation_strategy(SaturationStrategy::UniformScaling);
+        fcs.set_g_limit_profiles(vec![
+            GLimitProfile::new(String::from("pilot"), Dimension3::default(0.0), Dimension3::default(AxisContribution::new(5.0, 5.0))),
+            GLimitProfile::new(String::from("cargo"), Dimension3::default(0.0), Dimension3::default(AxisContribution::new(2.0, 2.0))),
+        ]);
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(0.2, 0.05, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+        // gsafety scales the command to 5 in x and 1.25 in y, then cargo scales the whole command down to 2 in x
+        assert!((output.desired_acceleration().linear().x() - 2.0).abs() < 0.001);
+        assert!((output.desired_acceleration().linear().y() - 0.5).abs() < 0.001);
+        assert_eq!(output.limiting_g_limit_profile(), Some(1));
+        assert_eq!(fcs.g_limit_profiles()[1].name(), "cargo");
+    }
+
+    #[test]
+    fn test_process_g_tolerance_model_tracks_pilot(){
+        let mut fcs = flight_control_system(true, true);
+        // loose linear limits, so only the g tolerance model restricts acceleration
+        *fcs.gsafety_max_acceleration_mut() = ControlAxis::new(
+            Dimension3::default(AxisContribution::new(100.0, 100.0)), 
+            Dimension3::default(AxisContribution::new(5.0, 5.0))
+        );
+        fcs.set_g_tolerance_model(Some(GToleranceModel::new(Dimension3::new(1.0, 0.0, 0.0), 0.2, 0.4, 0.5)));
+        let zero = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+        // full input is 10m/s^2 in +x, just over 1g toward the head
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+        assert!((output.desired_acceleration().linear().x() - (0.4 * STANDARD_GRAVITY)).abs() < 0.001);
+        for _ in 0..10{
+            let _ = fcs.process(
+                &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+                &zero, 
+                &zero, 
+                1.0
+            );
+        }
+        // limits narrow as g-dose builds, which keeps the pilot from greying out
+        assert_eq!(fcs.pilot_state(), PilotState::Nominal);
+        let output = fcs.process(
+            &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+            &zero, 
+            &zero, 
+            1.0
+        );
+        assert!(output.desired_acceleration().linear().x() < 0.3 * STANDARD_GRAVITY);
+        assert!(output.desired_acceleration().linear().x() > 0.2 * STANDARD_GRAVITY);
+
+        // without g-force safety, nothing stops the pilot from greying out
+        *fcs.gsafety_mut() = Toggle::new(false);
+        for _ in 0..10{
+            let _ = fcs.process(
+                &ControlAxis::new(Dimension3::new(1.0, 0.0, 0.0), Dimension3::default(0.0)), 
+                &zero, 
+                &zero, 
+                1.0
+            );
+        }
+        assert!(fcs.pilot_state() != PilotState::Nominal);
+    }
+}
@@ -0,0 +1,337 @@
+//! # Momentum exchange devices
+//! reaction wheels and control moment gyros rotate the ship by trading angular momentum with it, instead of
+//! expelling propellant. they can only store so much momentum before saturating, after which thrusters must
+//! be used to dump it.
+use game_utils::{
+    control_axis::AxisContribution,
+    dimension3::Dimension3,
+};
+use num::Float;
+use crate::{cross3, dot3, normalize3};
+
+
+
+
+
+/// spins a flywheel up or down along a fixed axis. torque on the ship is the opposite of the wheel's change in momentum
+pub struct ReactionWheel<T>{
+    spin_axis: [T; 3],
+    max_torque: T,      // newton-meters
+    max_momentum: T,    // newton-meter-seconds
+    momentum: T,        // currently stored, along spin axis
+}
+impl<T> ReactionWheel<T>
+    where T: Float
+{
+    /// spin_axis is normalized
+    pub fn new(spin_axis: Dimension3<T>, max_torque: T, max_momentum: T) -> Self{
+        Self{
+            spin_axis: normalize3([spin_axis.x(), spin_axis.y(), spin_axis.z()]),
+            max_torque: max_torque.abs(),
+            max_momentum: max_momentum.abs(),
+            momentum: num::zero(),
+        }
+    }
+
+    pub fn max_torque(&self) -> T{self.max_torque}
+
+    pub fn max_momentum(&self) -> T{self.max_momentum}
+
+    pub fn momentum(&self) -> T{self.momentum}
+
+    pub fn set_momentum(&mut self, momentum: T){
+        self.momentum = num::clamp(momentum, -self.max_momentum, self.max_momentum);
+    }
+}
+
+
+
+/// single gimbal control moment gyro. a constant speed rotor whose momentum vector is tilted about a gimbal axis.
+/// tilting it produces a large torque perpendicular to both the gimbal axis and the rotor's momentum
+pub struct ControlMomentGyro<T>{
+    gimbal_axis: [T; 3],
+    // rotor momentum direction at zero gimbal angle, perpendicular to gimbal axis
+    reference_momentum_direction: [T; 3],
+    rotor_momentum: T,      // newton-meter-seconds
+    max_gimbal_rate: T,     // radians per second
+    max_gimbal_angle: T,    // radians either side of reference. stays clear of the singularity at ±90°
+    gimbal_angle: T,
+}
+impl<T> ControlMomentGyro<T>
+    where T: Float
+{
+    /// reference_momentum_direction is made perpendicular to gimbal_axis, and both are normalized
+    pub fn new(
+        gimbal_axis: Dimension3<T>,
+        reference_momentum_direction: Dimension3<T>,
+        rotor_momentum: T,
+        max_gimbal_rate: T,
+        max_gimbal_angle: T,
+    ) -> Self{
+        let gimbal_axis = normalize3([gimbal_axis.x(), gimbal_axis.y(), gimbal_axis.z()]);
+        let reference = [reference_momentum_direction.x(), reference_momentum_direction.y(), reference_momentum_direction.z()];
+        let along_gimbal = dot3(&reference, &gimbal_axis);
+        let reference = normalize3([
+            reference[0] - (gimbal_axis[0] * along_gimbal),
+            reference[1] - (gimbal_axis[1] * along_gimbal),
+            reference[2] - (gimbal_axis[2] * along_gimbal),
+        ]);
+        Self{
+            gimbal_axis,
+            reference_momentum_direction: reference,
+            rotor_momentum: rotor_momentum.abs(),
+            max_gimbal_rate: max_gimbal_rate.abs(),
+            max_gimbal_angle: max_gimbal_angle.abs(),
+            gimbal_angle: num::zero(),
+        }
+    }
+
+    pub fn rotor_momentum(&self) -> T{self.rotor_momentum}
+
+    pub fn max_gimbal_rate(&self) -> T{self.max_gimbal_rate}
+
+    pub fn max_gimbal_angle(&self) -> T{self.max_gimbal_angle}
+
+    pub fn gimbal_angle(&self) -> T{self.gimbal_angle}
+
+    pub fn set_gimbal_angle(&mut self, gimbal_angle: T){
+        self.gimbal_angle = num::clamp(gimbal_angle, -self.max_gimbal_angle, self.max_gimbal_angle);
+    }
+
+    /// rotor momentum direction at the current gimbal angle. h = cos θ·h0 + sin θ·(g × h0)
+    fn momentum_direction(&self) -> [T; 3]{
+        let perpendicular = cross3(&self.gimbal_axis, &self.reference_momentum_direction);
+        let (sin, cos) = self.gimbal_angle.sin_cos();
+        [
+            (self.reference_momentum_direction[0] * cos) + (perpendicular[0] * sin),
+            (self.reference_momentum_direction[1] * cos) + (perpendicular[1] * sin),
+            (self.reference_momentum_direction[2] * cos) + (perpendicular[2] * sin),
+        ]
+    }
+}
+
+
+
+pub enum MomentumDevice<T>{
+    ReactionWheel(ReactionWheel<T>),
+    ControlMomentGyro(ControlMomentGyro<T>),
+}
+impl<T> MomentumDevice<T>
+    where T: Float
+{
+    /// unit axis the device currently produces torque on the ship about.
+    /// fixed for a reaction wheel, rotates with gimbal angle for a control moment gyro
+    pub fn torque_axis(&self) -> Dimension3<T>{
+        let axis = self.torque_axis_array();
+        Dimension3::new(axis[0], axis[1], axis[2])
+    }
+
+    pub(crate) fn torque_axis_array(&self) -> [T; 3]{
+        match self{
+            MomentumDevice::ReactionWheel(wheel) => wheel.spin_axis,
+            // τ = -dh/dt = -θ̇·(g × h). positive torque along -(g × h) is positive gimbal rate
+            MomentumDevice::ControlMomentGyro(gyro) => {
+                let axis = cross3(&gyro.gimbal_axis, &gyro.momentum_direction());
+                [-axis[0], -axis[1], -axis[2]]
+            }
+        }
+    }
+
+    /// largest torque magnitude the device can produce along +/- its torque axis as (positive, negative).
+    /// a side is zero once the device is saturated in that direction
+    pub fn available_torque(&self) -> (T, T){
+        match self{
+            MomentumDevice::ReactionWheel(wheel) => (
+                if wheel.momentum > -wheel.max_momentum{wheel.max_torque}else{num::zero()},
+                if wheel.momentum < wheel.max_momentum{wheel.max_torque}else{num::zero()}
+            ),
+            MomentumDevice::ControlMomentGyro(gyro) => {
+                let max_torque = gyro.max_gimbal_rate * gyro.rotor_momentum;
+                (
+                    if gyro.gimbal_angle < gyro.max_gimbal_angle{max_torque}else{num::zero()},
+                    if gyro.gimbal_angle > -gyro.max_gimbal_angle{max_torque}else{num::zero()}
+                )
+            }
+        }
+    }
+
+    /// same as available_torque, but also limited to what can be produced for delta_time without passing saturation
+    pub fn torque_limits(&self, delta_time: T) -> (T, T){
+        let (positive, negative) = self.available_torque();
+        if delta_time <= num::zero(){return (positive, negative)}
+        match self{
+            MomentumDevice::ReactionWheel(wheel) => (
+                positive.min((wheel.max_momentum + wheel.momentum) / delta_time),
+                negative.min((wheel.max_momentum - wheel.momentum) / delta_time)
+            ),
+            MomentumDevice::ControlMomentGyro(gyro) => (
+                positive.min(((gyro.max_gimbal_angle - gyro.gimbal_angle) / delta_time) * gyro.rotor_momentum),
+                negative.min(((gyro.max_gimbal_angle + gyro.gimbal_angle) / delta_time) * gyro.rotor_momentum)
+            ),
+        }
+    }
+
+    /// 0 = no stored momentum, 1 = saturated
+    pub fn saturation(&self) -> T{
+        match self{
+            MomentumDevice::ReactionWheel(wheel) => {
+                if wheel.max_momentum <= num::zero(){return num::one()}
+                wheel.momentum.abs() / wheel.max_momentum
+            },
+            MomentumDevice::ControlMomentGyro(gyro) => {
+                if gyro.max_gimbal_angle <= num::zero(){return num::one()}
+                gyro.gimbal_angle.abs() / gyro.max_gimbal_angle
+            }
+        }
+    }
+
+    /// sign of the torque along torque_axis that moves the device away from saturation
+    pub(crate) fn desaturating_direction(&self) -> T{
+        let stored = match self{
+            MomentumDevice::ReactionWheel(wheel) => wheel.momentum,
+            MomentumDevice::ControlMomentGyro(gyro) => -gyro.gimbal_angle,
+        };
+        if stored > num::zero(){T::one()}else if stored < num::zero(){-T::one()}else{num::zero()}
+    }
+
+    /// produces the given torque(along torque_axis) on the ship for delta_time, updating stored momentum.
+    /// torque is clamped to torque_limits
+    pub fn apply_torque(&mut self, torque: T, delta_time: T){
+        let (positive, negative) = self.torque_limits(delta_time);
+        let torque = num::clamp(torque, -negative, positive);
+        match self{
+            MomentumDevice::ReactionWheel(wheel) => {
+                let momentum = wheel.momentum - (torque * delta_time);
+                wheel.set_momentum(momentum);
+            },
+            MomentumDevice::ControlMomentGyro(gyro) => {
+                if gyro.rotor_momentum <= num::zero(){return}
+                let angle = gyro.gimbal_angle + ((torque / gyro.rotor_momentum) * delta_time);
+                gyro.set_gimbal_angle(angle);
+            }
+        }
+    }
+}
+
+
+
+/// largest torque the devices can produce about each ship axis, to be added to the thrusters' available torque.
+/// a device whose torque axis is off of a ship axis contributes its projection onto it
+pub fn calculate_available_torque<T>(momentum_devices: &[MomentumDevice<T>]) -> Dimension3<AxisContribution<T>>
+    where T: Float
+{
+    let mut available_torque = [(T::zero(), T::zero()); 3];
+    for device in momentum_devices{
+        let (positive, negative) = device.available_torque();
+        for (available, component) in available_torque.iter_mut().zip(device.torque_axis_array().iter()){
+            if *component >= num::zero(){
+                available.0 = available.0 + (positive * *component);
+                available.1 = available.1 + (negative * *component);
+            }else{
+                available.0 = available.0 + (negative * -*component);
+                available.1 = available.1 + (positive * -*component);
+            }
+        }
+    }
+
+    Dimension3::new(
+        AxisContribution::new(available_torque[0].0, available_torque[0].1),
+        AxisContribution::new(available_torque[1].0, available_torque[1].1),
+        AxisContribution::new(available_torque[2].0, available_torque[2].1)
+    )
+}
+
+const MAX_ALLOCATION_ITERATIONS: usize = 100;
+
+/// torque along each device's torque axis that best achieves the desired torque, in the same order as the devices.
+/// solves min ||D·τ - desired||² within each device's torque limits for delta_time, by cyclic coordinate descent.
+/// devices above momentum_dump_threshold(saturation, 0 to 1) also get a desaturating torque, growing to their full
+/// torque as they reach saturation. that torque is not part of the desired torque, so whatever produces the rest of the
+/// ship's torque(thrusters) has to counter it, which is what dumps the stored momentum.
+pub fn allocate_torque<T>(
+    desired_torque: &Dimension3<T>,
+    momentum_devices: &[MomentumDevice<T>],
+    momentum_dump_threshold: T,
+    delta_time: T,
+) -> Vec<T>
+    where T: Float
+{
+    let mut residual = [desired_torque.x(), desired_torque.y(), desired_torque.z()];
+    let axes: Vec<[T; 3]> = momentum_devices.iter().map(|device| device.torque_axis_array()).collect();
+    let limits: Vec<(T, T)> = momentum_devices.iter().map(|device| device.torque_limits(delta_time)).collect();
+    let dump_torques: Vec<T> = momentum_devices.iter().zip(limits.iter())
+        .map(|(device, (positive, negative))| {
+            let saturation = device.saturation();
+            if saturation <= momentum_dump_threshold || momentum_dump_threshold >= num::one(){return num::zero()}
+            let fraction = num::clamp((saturation - momentum_dump_threshold) / (T::one() - momentum_dump_threshold), num::zero(), num::one());
+            let direction = device.desaturating_direction();
+            let limit = if direction > num::zero(){*positive}else{*negative};
+            direction * limit * fraction
+        })
+        .collect();
+    let mut torques: Vec<T> = vec![num::zero(); momentum_devices.len()];
+    let tolerance: T = num::cast(1e-6).unwrap();
+
+    for _ in 0..MAX_ALLOCATION_ITERATIONS{
+        let mut max_change: T = num::zero();
+        for (((axis, (positive, negative)), dump_torque), torque) in axes.iter().zip(limits.iter()).zip(dump_torques.iter()).zip(torques.iter_mut()){
+            // leave room within the limits for the dump torque
+            let new_torque = num::clamp(*torque + dot3(axis, &residual), -*negative - *dump_torque, *positive - *dump_torque);
+            let change = new_torque - *torque;
+            for (residual, component) in residual.iter_mut().zip(axis.iter()){
+                *residual = *residual - (change * *component);
+            }
+            *torque = new_torque;
+            max_change = max_change.max(change.abs());
+        }
+        if max_change < tolerance{break}
+    }
+
+    torques.iter().zip(dump_torques.iter()).map(|(torque, dump_torque)| *torque + *dump_torque).collect()
+}
+
+
+
+#[test]
+fn test_reaction_wheel_saturates(){
+    let mut device = MomentumDevice::ReactionWheel(ReactionWheel::new(Dimension3::new(0.0, 0.0, 2.0), 10.0, 50.0));
+    assert!((device.torque_axis().z() - 1.0).abs() < 0.001);
+
+    for _ in 0..4{
+        device.apply_torque(10.0, 1.0);
+    }
+    assert!((device.saturation() - 0.8).abs() < 0.001);
+    // only 10 N·m·s of storage left
+    assert!((device.torque_limits(2.0).0 - 5.0).abs() < 0.001);
+
+    device.apply_torque(10.0, 2.0);
+    assert!((device.saturation() - 1.0).abs() < 0.001);
+    assert!((device.available_torque().0 - 0.0).abs() < 0.001);
+    assert!((device.available_torque().1 - 10.0).abs() < 0.001);
+    assert!((device.desaturating_direction() - (-1.0)).abs() < 0.001);
+}
+
+#[test]
+fn test_control_moment_gyro_torque_axis_rotates_with_gimbal(){
+    // rotor momentum along x, gimbaled about z. tilting it produces torque about y
+    let mut device = MomentumDevice::ControlMomentGyro(ControlMomentGyro::new(
+        Dimension3::new(0.0, 0.0, 1.0),
+        Dimension3::new(1.0, 0.0, 0.0),
+        100.0,
+        1.0,
+        1.0
+    ));
+    let axis = device.torque_axis();
+    assert!((axis.y() - (-1.0)).abs() < 0.001);
+    assert!((device.available_torque().0 - 100.0).abs() < 0.001);
+
+    device.apply_torque(50.0, 1.0);
+    let ControlMomentGyro{gimbal_angle, ..} = match &device{
+        MomentumDevice::ControlMomentGyro(gyro) => gyro,
+        _ => unreachable!()
+    };
+    assert!((gimbal_angle - 0.5).abs() < 0.001);
+    assert!((device.saturation() - 0.5).abs() < 0.001);
+    // torque axis has rotated along with the rotor
+    assert!(device.torque_axis().x().abs() > 0.1);
+}
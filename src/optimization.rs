@@ -0,0 +1,356 @@
+//! # small dense solvers
+//! pure rust, generic over Float, sized for the handful of variables a flight control system deals with.
+//! not intended to compete with a general purpose optimization library.
+use num::Float;
+
+
+
+
+
+/// minimizes cᵀ·x subject to A·x = b and x >= 0, using the two phase simplex method with Bland's rule.
+/// returns None if the problem is infeasible or unbounded.
+pub(crate) fn solve_linear_program<T>(a: &[Vec<T>], b: &[T], c: &[T]) -> Option<Vec<T>>
+    where T: Float
+{
+    let rows = a.len();
+    let columns = c.len();
+    let tolerance: T = num::cast(1e-9).unwrap();
+
+    // tableau is [A | I(artificials) | b], with the reduced cost row appended last.
+    // rows are negated where needed so that b >= 0 and the artificials form a feasible starting basis
+    let mut tableau: Vec<Vec<T>> = Vec::with_capacity(rows + 1);
+    for (row, rhs) in a.iter().zip(b.iter()){
+        let sign = if *rhs < num::zero(){-T::one()}else{T::one()};
+        let mut tableau_row = vec![num::zero(); columns + rows + 1];
+        for (value, coefficient) in tableau_row.iter_mut().zip(row.iter()){
+            *value = *coefficient * sign;
+        }
+        tableau_row[columns + tableau.len()] = num::one();
+        tableau_row[columns + rows] = *rhs * sign;
+        tableau.push(tableau_row);
+    }
+    let mut basis: Vec<usize> = (columns..columns + rows).collect();
+
+    // phase 1: minimize the sum of artificials
+    let mut phase_one_costs = vec![num::zero(); columns + rows];
+    for cost in phase_one_costs.iter_mut().skip(columns){
+        *cost = num::one();
+    }
+    tableau.push(reduced_costs(&tableau, &basis, &phase_one_costs));
+    if !run_simplex(&mut tableau, &mut basis, columns + rows, tolerance){
+        return None
+    }
+    let scale = b.iter().fold(T::one(), |max, value| max.max(value.abs()));
+    if -tableau[rows][columns + rows] > tolerance * scale{
+        return None
+    }
+
+    // drive any artificials left in the basis(at zero) out, so they can't come back during phase 2
+    for row in 0..rows{
+        if basis[row] < columns{continue}
+        if let Some(column) = (0..columns).find(|column| tableau[row][*column].abs() > tolerance){
+            pivot(&mut tableau, &mut basis, row, column);
+        }
+    }
+
+    // phase 2: minimize the real objective, artificials are not allowed to enter the basis
+    let mut phase_two_costs = c.to_vec();
+    phase_two_costs.resize(columns + rows, num::zero());
+    tableau[rows] = reduced_costs(&tableau[..rows], &basis, &phase_two_costs);
+    if !run_simplex(&mut tableau, &mut basis, columns, tolerance){
+        return None
+    }
+
+    let mut solution = vec![num::zero(); columns];
+    for (row, variable) in basis.iter().enumerate(){
+        if *variable < columns{
+            solution[*variable] = tableau[row][columns + rows];
+        }
+    }
+
+    Some(solution)
+}
+
+fn reduced_costs<T: Float>(constraint_rows: &[Vec<T>], basis: &[usize], costs: &[T]) -> Vec<T>{
+    let width = costs.len() + 1;
+    let mut reduced = costs.to_vec();
+    reduced.push(num::zero());
+    for (row, variable) in constraint_rows.iter().zip(basis.iter()){
+        let basis_cost = costs[*variable];
+        for (reduced, value) in reduced.iter_mut().zip(row.iter()).take(width){
+            *reduced = *reduced - (basis_cost * *value);
+        }
+    }
+    reduced
+}
+
+/// iterates until no column below enterable_columns has a negative reduced cost.
+/// returns false if the problem is unbounded
+fn run_simplex<T: Float>(tableau: &mut [Vec<T>], basis: &mut [usize], enterable_columns: usize, tolerance: T) -> bool{
+    let rows = basis.len();
+    let rhs = tableau[0].len() - 1;
+    let max_iterations = 50 * (rows + rhs);
+
+    for _ in 0..max_iterations{
+        // Bland's rule, lowest index with negative reduced cost enters, to avoid cycling
+        let entering = match (0..enterable_columns).find(|column| tableau[rows][*column] < -tolerance){
+            Some(column) => column,
+            None => return true
+        };
+
+        let mut leaving: Option<usize> = None;
+        for row in 0..rows{
+            if tableau[row][entering] <= tolerance{continue}
+            let ratio = tableau[row][rhs] / tableau[row][entering];
+            leaving = match leaving{
+                None => Some(row),
+                Some(best) => {
+                    let best_ratio = tableau[best][rhs] / tableau[best][entering];
+                    if ratio < best_ratio - tolerance || ((ratio - best_ratio).abs() <= tolerance && basis[row] < basis[best]){
+                        Some(row)
+                    }else{
+                        Some(best)
+                    }
+                }
+            };
+        }
+        match leaving{
+            Some(row) => pivot(tableau, basis, row, entering),
+            None => return false
+        }
+    }
+
+    true
+}
+
+fn pivot<T: Float>(tableau: &mut [Vec<T>], basis: &mut [usize], pivot_row: usize, pivot_column: usize){
+    let pivot_value = tableau[pivot_row][pivot_column];
+    for value in tableau[pivot_row].iter_mut(){
+        *value = *value / pivot_value;
+    }
+    let normalized = tableau[pivot_row].clone();
+    for (row, values) in tableau.iter_mut().enumerate(){
+        if row == pivot_row{continue}
+        let factor = values[pivot_column];
+        if factor == num::zero(){continue}
+        for (value, pivot_value) in values.iter_mut().zip(normalized.iter()){
+            *value = *value - (factor * *pivot_value);
+        }
+    }
+    basis[pivot_row] = pivot_column;
+}
+
+
+
+
+pub(crate) fn identity_matrix<T: Float>(size: usize) -> Vec<Vec<T>>{
+    (0..size).map(|row| (0..size).map(|column| if row == column{T::one()}else{T::zero()}).collect()).collect()
+}
+
+pub(crate) fn transpose<T: Float>(a: &[Vec<T>]) -> Vec<Vec<T>>{
+    if a.is_empty(){return Vec::new()}
+    (0..a[0].len()).map(|column| a.iter().map(|row| row[column]).collect()).collect()
+}
+
+pub(crate) fn multiply<T: Float>(a: &[Vec<T>], b: &[Vec<T>]) -> Vec<Vec<T>>{
+    let columns = b.first().map_or(0, |row| row.len());
+    a.iter().map(|row| {
+        (0..columns).map(|column| {
+            row.iter().zip(b.iter()).fold(T::zero(), |sum, (a, b_row)| sum + (*a * b_row[column]))
+        }).collect()
+    }).collect()
+}
+
+pub(crate) fn multiply_vector<T: Float>(a: &[Vec<T>], x: &[T]) -> Vec<T>{
+    a.iter().map(|row| row.iter().zip(x.iter()).fold(T::zero(), |sum, (a, x)| sum + (*a * *x))).collect()
+}
+
+pub(crate) fn add<T: Float>(a: &[Vec<T>], b: &[Vec<T>]) -> Vec<Vec<T>>{
+    a.iter().zip(b.iter()).map(|(a, b)| a.iter().zip(b.iter()).map(|(a, b)| *a + *b).collect()).collect()
+}
+
+/// Gauss-Jordan elimination with partial pivoting. returns None if the matrix is singular
+pub(crate) fn invert<T: Float>(a: &[Vec<T>]) -> Option<Vec<Vec<T>>>{
+    let size = a.len();
+    let tolerance: T = num::cast(1e-12).unwrap();
+    let mut left: Vec<Vec<T>> = a.to_vec();
+    let mut right = identity_matrix(size);
+    for column in 0..size{
+        let pivot_row = (column..size).max_by(|i, j| {
+            left[*i][column].abs().partial_cmp(&left[*j][column].abs()).unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+        if left[pivot_row][column].abs() <= tolerance{return None}
+        left.swap(column, pivot_row);
+        right.swap(column, pivot_row);
+
+        let pivot = left[column][column];
+        for k in 0..size{
+            left[column][k] = left[column][k] / pivot;
+            right[column][k] = right[column][k] / pivot;
+        }
+        for row in 0..size{
+            if row == column{continue}
+            let factor = left[row][column];
+            if factor == T::zero(){continue}
+            for k in 0..size{
+                left[row][k] = left[row][k] - (factor * left[column][k]);
+                right[row][k] = right[row][k] - (factor * right[column][k]);
+            }
+        }
+    }
+    Some(right)
+}
+
+
+/// minimizes ½·xᵀ·P·x + qᵀ·x subject to l <= A·x <= u, using ADMM(the OSQP iteration) with adaptive step size.
+/// P must be positive semidefinite. rows of A with l == u are equality constraints. P and A are fixed at setup, so
+/// the factorization is reused across solves where only q and the bounds change, like successive frames of MPC
+pub(crate) struct QuadraticProgram<T>{
+    p: Vec<Vec<T>>,
+    a: Vec<Vec<T>>,
+    a_transpose: Vec<Vec<T>>,
+    a_transpose_a: Vec<Vec<T>>,
+    // step size, carried between solves since the best value rarely changes much
+    rho: T,
+    // (P + σI + ρAᵀA)⁻¹
+    inverse: Vec<Vec<T>>,
+}
+impl<T> QuadraticProgram<T>
+    where T: Float
+{
+    /// returns None if the linear system is singular
+    pub(crate) fn new(p: Vec<Vec<T>>, a: Vec<Vec<T>>) -> Option<Self>{
+        let a_transpose = transpose(&a);
+        let a_transpose_a = multiply(&a_transpose, &a);
+        let rho = num::cast(0.1).unwrap();
+        let inverse = Self::kkt_inverse(&p, &a_transpose_a, rho)?;
+        Some(Self{p, a, a_transpose, a_transpose_a, rho, inverse})
+    }
+
+    fn kkt_inverse(p: &[Vec<T>], a_transpose_a: &[Vec<T>], rho: T) -> Option<Vec<Vec<T>>>{
+        let sigma: T = num::cast(1e-6).unwrap();
+        let mut kkt: Vec<Vec<T>> = p.to_vec();
+        for (row, kkt_row) in kkt.iter_mut().enumerate(){
+            kkt_row[row] = kkt_row[row] + sigma;
+            for (value, a_transpose_a) in kkt_row.iter_mut().zip(a_transpose_a[row].iter()){
+                *value = *value + (rho * *a_transpose_a);
+            }
+        }
+        invert(&kkt)
+    }
+
+    /// warm_start is the starting x, usually the last solution shifted along. stops at max_iterations with the latest
+    /// iterate, which is close enough for control when warm started every frame
+    pub(crate) fn solve(&mut self, q: &[T], l: &[T], u: &[T], warm_start: Option<&[T]>, max_iterations: usize) -> Vec<T>{
+        let variables = q.len();
+        let constraints = l.len();
+        let sigma: T = num::cast(1e-6).unwrap();
+        let alpha: T = num::cast(1.6).unwrap();
+        let tolerance: T = num::cast(1e-6).unwrap();
+
+        let clamp = |z: &[T]| -> Vec<T> {(0..constraints).map(|row| z[row].max(l[row]).min(u[row])).collect()};
+        let max_abs = |v: &[T]| v.iter().fold(T::zero(), |max, value| max.max(value.abs()));
+        let mut x: Vec<T> = warm_start.map_or(vec![T::zero(); variables], |warm_start| warm_start.to_vec());
+        let mut a_x = multiply_vector(&self.a, &x);
+        let mut z = clamp(&a_x);
+        let mut y = vec![T::zero(); constraints];
+        for iteration in 0..max_iterations{
+            let rho = self.rho;
+            let rho_z_minus_y: Vec<T> = z.iter().zip(y.iter()).map(|(z, y)| (rho * *z) - *y).collect();
+            let a_transpose_term = multiply_vector(&self.a_transpose, &rho_z_minus_y);
+            let rhs: Vec<T> = (0..variables).map(|i| (sigma * x[i]) - q[i] + a_transpose_term[i]).collect();
+            let x_tilde = multiply_vector(&self.inverse, &rhs);
+            let z_tilde = multiply_vector(&self.a, &x_tilde);
+
+            x = (0..variables).map(|i| (alpha * x_tilde[i]) + ((T::one() - alpha) * x[i])).collect();
+            // A·x follows from the same relaxation, without another product
+            a_x = (0..constraints).map(|i| (alpha * z_tilde[i]) + ((T::one() - alpha) * a_x[i])).collect();
+            let z_relaxed: Vec<T> = (0..constraints).map(|i| (alpha * z_tilde[i]) + ((T::one() - alpha) * z[i])).collect();
+            let z_next = clamp(&(0..constraints).map(|i| z_relaxed[i] + (y[i] / rho)).collect::<Vec<T>>());
+            y = (0..constraints).map(|i| y[i] + (rho * (z_relaxed[i] - z_next[i]))).collect();
+            z = z_next;
+
+            // residuals cost as much as an iteration, so they are only checked every few
+            if iteration % 10 != 9{continue}
+            let p_x = multiply_vector(&self.p, &x);
+            let a_transpose_y = multiply_vector(&self.a_transpose, &y);
+            let primal_residual = max_abs(&(0..constraints).map(|i| a_x[i] - z[i]).collect::<Vec<T>>());
+            let dual_residual = max_abs(&(0..variables).map(|i| p_x[i] + q[i] + a_transpose_y[i]).collect::<Vec<T>>());
+            let primal_scale = max_abs(&a_x).max(max_abs(&z));
+            let dual_scale = max_abs(&p_x).max(max_abs(&a_transpose_y)).max(max_abs(q));
+            if primal_residual <= tolerance * (T::one() + primal_scale) && dual_residual <= tolerance * (T::one() + dual_scale){
+                break;
+            }
+
+            // balance primal and dual residuals, refactoring only when rho changes a lot
+            if iteration % 50 == 49{
+                let tiny: T = num::cast(1e-12).unwrap();
+                let ratio = ((primal_residual / (primal_scale + tiny)) / ((dual_residual / (dual_scale + tiny)) + tiny)).sqrt();
+                let new_rho = (rho * ratio).max(num::cast(1e-6).unwrap()).min(num::cast(1e6).unwrap());
+                let five: T = num::cast(5.0).unwrap();
+                if new_rho > rho * five || new_rho < rho / five{
+                    if let Some(inverse) = Self::kkt_inverse(&self.p, &self.a_transpose_a, new_rho){
+                        self.rho = new_rho;
+                        self.inverse = inverse;
+                    }
+                }
+            }
+        }
+        x
+    }
+}
+
+
+
+
+#[test]
+fn test_solve_linear_program(){
+    // min -x - 2y, x + y + s1 = 4, x + 3y + s2 = 6 -> x = 3, y = 1
+    let solution: Vec<f64> = solve_linear_program(
+        &[vec![1.0, 1.0, 1.0, 0.0], vec![1.0, 3.0, 0.0, 1.0]],
+        &[4.0, 6.0],
+        &[-1.0, -2.0, 0.0, 0.0]
+    ).unwrap();
+    assert!((solution[0] - 3.0).abs() < 0.001);
+    assert!((solution[1] - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn test_solve_linear_program_infeasible(){
+    // x - y = -1 and x + y = -1 can't both hold with x, y >= 0
+    let solution: Option<Vec<f64>> = solve_linear_program(
+        &[vec![1.0, -1.0], vec![1.0, 1.0]],
+        &[-1.0, -1.0],
+        &[1.0, 1.0]
+    );
+    assert!(solution.is_none());
+}
+
+#[test]
+fn test_invert(){
+    let a = vec![vec![0.0, 2.0, 0.0], vec![1.0, 0.0, 0.0], vec![0.0, 0.0, 4.0]];
+    let product = multiply(&a, &invert(&a).unwrap());
+    for (row, expected) in product.iter().zip(identity_matrix::<f64>(3).iter()){
+        for (value, expected) in row.iter().zip(expected.iter()){
+            assert!((value - expected).abs() < 1e-9);
+        }
+    }
+    assert!(invert(&[vec![1.0, 2.0], vec![2.0, 4.0]]).is_none());
+}
+
+#[test]
+fn test_quadratic_program(){
+    // min (x - 2)² + (y - 2)², x + y <= 2, 0 <= x <= 0.5 -> x = 0.5, y = 1.5
+    let mut program = QuadraticProgram::new(
+        vec![vec![2.0, 0.0], vec![0.0, 2.0]],
+        vec![vec![1.0, 1.0], vec![1.0, 0.0]]
+    ).unwrap();
+    let solution: Vec<f64> = program.solve(&[-4.0, -4.0], &[f64::NEG_INFINITY, 0.0], &[2.0, 0.5], None, 4_000);
+    assert!((solution[0] - 0.5).abs() < 0.001);
+    assert!((solution[1] - 1.5).abs() < 0.001);
+
+    // same program, new linear cost. min (x - 1)² + (y + 1)² -> x = 0.5, y = -1
+    let solution: Vec<f64> = program.solve(&[-2.0, 2.0], &[f64::NEG_INFINITY, 0.0], &[2.0, 0.5], Some(&solution), 4_000);
+    assert!((solution[0] - 0.5).abs() < 0.001);
+    assert!((solution[1] - (-1.0)).abs() < 0.001);
+}
@@ -4,8 +4,16 @@ use game_utils::{
     control_axis::{ControlAxis, AxisContribution}, 
     dimension3::{Dimension3, Vector3}
 };
-use crate::FcsError;
+use crate::{
+    FcsError,
+    cross3,
+    dot3,
+    normalize3,
+    momentum_devices::{self, MomentumDevice},
+    saturation::SaturationStrategy,
+};
 use num::Float;
+use std::collections::VecDeque;
 
 
 // we need to ensure that the negative component of available acceleration does not use values with a "-" sign. 
@@ -22,6 +30,7 @@ use num::Float;
 
 
 #[derive(Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ThrusterSize{
     Small,
     Medium,
@@ -30,25 +39,203 @@ pub enum ThrusterSize{
 
 
 
+/// how a thruster's delivered throttle follows its commanded throttle
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ThrottleResponse<T>{
+    Instant,
+    /// delivered throttle approaches the command exponentially. time constants in seconds
+    FirstOrderLag{spool_up: T, spool_down: T},
+    /// delivered throttle changes by at most this much throttle per second
+    RateLimited{spool_up: T, spool_down: T},
+}
+
+
+
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThrusterResponse<T>{
+    throttle_response: ThrottleResponse<T>,
+    // seconds between a command and the thruster starting to respond to it
+    dead_time: T,
+    // shortest time, in seconds, a thruster fires once ignited. sets the minimum impulse bit
+    minimum_on_time: T,
+    // Some(period in seconds) for on/off thrusters, which approximate a throttle by pulsing at full thrust
+    // for throttle * period of each period
+    pulse_width_modulation: Option<T>,
+}
+impl<T> ThrusterResponse<T>
+    where T: Float
+{
+    pub fn new(
+        throttle_response: ThrottleResponse<T>, 
+        dead_time: T, 
+        minimum_on_time: T, 
+        pulse_width_modulation: Option<T>
+    ) -> Self{
+        Self{
+            throttle_response, 
+            dead_time, 
+            minimum_on_time, 
+            pulse_width_modulation
+        }
+    }
+
+    /// thrust follows the command immediately
+    pub fn instant() -> Self{
+        Self::new(ThrottleResponse::Instant, num::zero(), num::zero(), None)
+    }
+
+    pub fn throttle_response(&self) -> ThrottleResponse<T>{self.throttle_response}
+
+    pub fn dead_time(&self) -> T{self.dead_time}
+
+    pub fn minimum_on_time(&self) -> T{self.minimum_on_time}
+
+    pub fn pulse_width_modulation(&self) -> Option<T>{self.pulse_width_modulation}
+}
+
+
+
+/// standard gravity, used to convert specific impulse(seconds) to effective exhaust velocity
+pub const STANDARD_GRAVITY: f64 = 9.80665;
+
+/// how much propellant a thruster burns
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PropellantConsumption<T>{
+    /// specific impulse in seconds. mass flow = thrust / (isp * g0)
+    SpecificImpulse(T),
+    /// mass flow(kg/s) while firing, interpolated linearly from minimum(just above zero throttle) to maximum(full throttle)
+    FuelFlow{minimum: T, maximum: T},
+}
+
+
+
 #[derive(Clone, Copy)]
 pub struct Thruster<T>{
     // this number should always be a positive value so it is (dis?)ambiguated from the axis it is contributing to
     max_thrust: T,
     size: ThrusterSize,
+    response: ThrusterResponse<T>,
+    // None for thrusters that don't use propellant
+    propellant_consumption: Option<PropellantConsumption<T>>,
 }
 impl<T> Thruster<T>
-    where T: Copy
+    where T: Float
 {
     pub fn new(max_thrust: T, size: ThrusterSize) -> Self{
         Self{
             max_thrust, 
-            size
+            size,
+            response: ThrusterResponse::instant(),
+            propellant_consumption: None,
         }
     }
 
     pub fn max_thrust(&self) -> T{self.max_thrust}
     
     pub fn size(&self) -> ThrusterSize{self.size}
+
+    pub fn response(&self) -> &ThrusterResponse<T>{&self.response}
+
+    pub fn set_response(&mut self, response: ThrusterResponse<T>){
+        self.response = response;
+    }
+
+    pub fn propellant_consumption(&self) -> Option<PropellantConsumption<T>>{self.propellant_consumption}
+
+    pub fn set_propellant_consumption(&mut self, propellant_consumption: Option<PropellantConsumption<T>>){
+        self.propellant_consumption = propellant_consumption;
+    }
+
+    /// propellant mass flow(kg/s) while producing the given thrust at the given throttle.
+    /// None if this thruster has no propellant consumption model
+    pub fn mass_flow(&self, throttle: T, thrust: T) -> Option<T>{
+        if throttle <= num::zero() || thrust <= num::zero(){
+            return self.propellant_consumption.map(|_| num::zero())
+        }
+        self.propellant_consumption.map(|consumption| match consumption{
+            PropellantConsumption::SpecificImpulse(specific_impulse) => {
+                let standard_gravity: T = num::cast(STANDARD_GRAVITY).unwrap();
+                thrust / (specific_impulse * standard_gravity)
+            },
+            PropellantConsumption::FuelFlow{minimum, maximum} => {
+                minimum + ((maximum - minimum) * num::clamp(throttle, num::zero(), num::one()))
+            }
+        })
+    }
+}
+
+
+
+/// condition of the thruster attached to a mount point
+#[derive(Clone, Copy, PartialEq)]
+pub enum ThrusterHealth<T>{
+    Nominal,
+    /// thruster can only produce this fraction(0 to 1) of its max thrust
+    Degraded(T),
+    /// thruster is producing max thrust, and can't be throttled or shut off
+    StuckOn,
+    /// thruster produces no thrust
+    FailedOff,
+}
+
+
+
+/// actuator limits of a gimbaled mount point. ranges are the max deflection(radians) either side of the
+/// mount point's thrust direction
+#[derive(Clone, Copy)]
+pub struct Gimbal<T>{
+    pitch_range: T,
+    yaw_range: T,
+    // radians per second
+    slew_rate: T,
+}
+impl<T> Gimbal<T>
+    where T: Float
+{
+    pub fn new(pitch_range: T, yaw_range: T, slew_rate: T) -> Self{
+        Self{
+            pitch_range: pitch_range.abs(), 
+            yaw_range: yaw_range.abs(), 
+            slew_rate: slew_rate.abs()
+        }
+    }
+
+    pub fn pitch_range(&self) -> T{self.pitch_range}
+
+    pub fn yaw_range(&self) -> T{self.yaw_range}
+
+    pub fn slew_rate(&self) -> T{self.slew_rate}
+
+    /// clamps angles to the gimbal's range
+    pub fn clamp(&self, angles: GimbalAngles<T>) -> GimbalAngles<T>{
+        GimbalAngles::new(
+            num::clamp(angles.pitch(), -self.pitch_range, self.pitch_range),
+            num::clamp(angles.yaw(), -self.yaw_range, self.yaw_range)
+        )
+    }
+}
+
+
+
+/// deflection(radians) of a gimbaled thruster from its mount point's thrust direction
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GimbalAngles<T>{
+    pitch: T,
+    yaw: T,
+}
+impl<T> GimbalAngles<T>
+    where T: Float
+{
+    pub fn new(pitch: T, yaw: T) -> Self{
+        Self{pitch, yaw}
+    }
+
+    pub fn pitch(&self) -> T{self.pitch}
+
+    pub fn yaw(&self) -> T{self.yaw}
 }
 
 
@@ -56,22 +243,35 @@ impl<T> Thruster<T>
 pub struct ThrusterMountPoint<T>{
     // is Option so that having no thruster attached can be represented
     attached_thruster: Option<Thruster<T>>,
+    thruster_health: ThrusterHealth<T>,
+    // indices of the propellant tanks plumbed to this mount point
+    propellant_feed: Vec<usize>,
+    // true when the thruster burns propellant, but none of its feed tanks have any left. see update_propellant_starvation
+    propellant_starved: bool,
     thrust_direction: Vector3<T>,
+    // Some for mount points that can steer their thrust
+    gimbal: Option<Gimbal<T>>,
     max_thruster_size: ThrusterSize,
     mount_location: Dimension3<T>,   // relative to ship center of mass? // used to determine torque?
 }
 impl<T> ThrusterMountPoint<T>
     where T: Float
 {
+    /// thrust direction is normalized, so every calculation sees max thrust along it. a zero direction produces no thrust
     pub fn new(
         attached_thruster: Option<Thruster<T>>,
         thrust_direction: Vector3<T>,
         max_thruster_size: ThrusterSize,
         mount_location: Dimension3<T>,
     ) -> Self{
+        let direction = normalize3([thrust_direction.x(), thrust_direction.y(), thrust_direction.z()]);
         Self{
             attached_thruster, 
-            thrust_direction, 
+            thruster_health: ThrusterHealth::Nominal,
+            propellant_feed: Vec::new(),
+            propellant_starved: false,
+            thrust_direction: Vector3::new(direction[0], direction[1], direction[2], num::one()), 
+            gimbal: None,
             max_thruster_size, 
             mount_location,
         }
@@ -79,18 +279,105 @@ impl<T> ThrusterMountPoint<T>
 
     pub fn attached_thruster(&self) -> &Option<Thruster<T>>{&self.attached_thruster}
     
-    pub fn change_thruster(&mut self, thruster: Thruster<T>) -> Result<(), FcsError>{
+    pub fn change_thruster(&mut self, thruster: Thruster<T>) -> Result<(), FcsError<'static>>{
         if thruster.size() > self.max_thruster_size{
             return Err(FcsError::new("Tried to attach a thruster that is too large for mount point."))
         }
         self.attached_thruster = Some(thruster);
+        // a replacement thruster is undamaged
+        self.thruster_health = ThrusterHealth::Nominal;
 
         Ok(())
     }
+
+    pub fn propellant_feed(&self) -> &[usize]{&self.propellant_feed}
+
+    /// indices into the ship's propellant tanks that this mount point draws from
+    pub fn set_propellant_feed(&mut self, propellant_feed: Vec<usize>){
+        self.propellant_feed = propellant_feed;
+    }
+
+    /// a starved mount point produces no thrust, commanded or not
+    pub fn propellant_starved(&self) -> bool{self.propellant_starved}
+
+    pub fn thruster_health(&self) -> ThrusterHealth<T>{self.thruster_health}
+
+    pub fn set_thruster_health(&mut self, thruster_health: ThrusterHealth<T>){
+        self.thruster_health = thruster_health;
+    }
+
+    /// max thrust the flight control system can command from this mount point, given thruster health.
+    /// a stuck on thruster can't be commanded, so it contributes nothing
+    pub fn controllable_thrust(&self) -> T{
+        if self.propellant_starved{return num::zero()}
+        match self.attached_thruster{
+            Some(thruster) => match self.thruster_health{
+                ThrusterHealth::Nominal => thruster.max_thrust(),
+                ThrusterHealth::Degraded(factor) => thruster.max_thrust() * num::clamp(factor, num::zero(), num::one()),
+                ThrusterHealth::StuckOn | ThrusterHealth::FailedOff => num::zero(),
+            },
+            None => num::zero()
+        }
+    }
+
+    /// thrust produced regardless of what is commanded. only non zero for a stuck on thruster
+    pub fn uncommanded_thrust(&self) -> T{
+        if self.propellant_starved{return num::zero()}
+        match (self.attached_thruster, self.thruster_health){
+            (Some(thruster), ThrusterHealth::StuckOn) => thruster.max_thrust(),
+            _ => num::zero()
+        }
+    }
     
     pub fn thrust_direction(&self) -> /*&*/Vector3<T>{/*&*/self.thrust_direction}
+
+    pub fn gimbal(&self) -> &Option<Gimbal<T>>{&self.gimbal}
+
+    /// makes this a gimbaled mount point, or a fixed one if None
+    pub fn set_gimbal(&mut self, gimbal: Option<Gimbal<T>>){
+        self.gimbal = gimbal;
+    }
+
+    /// unit thrust direction with the gimbal deflected by the given angles. pitch rotates thrust toward the
+    /// first of gimbal_axes, yaw toward the second
+    pub fn gimbaled_thrust_direction(&self, angles: &GimbalAngles<T>) -> Dimension3<T>{
+        let (direction, pitch_axis, yaw_axis) = self.gimbal_axes();
+        let axial = angles.pitch().cos() * angles.yaw().cos();
+        let pitch = angles.pitch().sin();
+        let yaw = angles.pitch().cos() * angles.yaw().sin();
+        Dimension3::new(
+            (direction[0] * axial) + (pitch_axis[0] * pitch) + (yaw_axis[0] * yaw),
+            (direction[1] * axial) + (pitch_axis[1] * pitch) + (yaw_axis[1] * yaw),
+            (direction[2] * axial) + (pitch_axis[2] * pitch) + (yaw_axis[2] * yaw)
+        )
+    }
+
+    /// orthonormal (thrust direction, pitch deflection axis, yaw deflection axis)
+    pub(crate) fn gimbal_axes(&self) -> ([T; 3], [T; 3], [T; 3]){
+        let direction = [self.thrust_direction.x(), self.thrust_direction.y(), self.thrust_direction.z()];
+        let threshold: T = num::cast(0.9).unwrap();
+        let reference = if direction[2].abs() > threshold{
+            [num::zero(), num::one(), num::zero()]
+        }else{
+            [num::zero(), num::zero(), num::one()]
+        };
+        let pitch_axis = normalize3(cross3(&direction, &reference));
+        let yaw_axis = cross3(&direction, &pitch_axis);
+        (direction, pitch_axis, yaw_axis)
+    }
     
-    //pub fn max_thruster_size(&self) -> ThrusterSize{self.max_thruster_size}
+    pub fn max_thruster_size(&self) -> ThrusterSize{self.max_thruster_size}
+    
+    pub fn mount_location(&self) -> &Dimension3<T>{&self.mount_location}
+
+    /// vector from the given center of mass to this mount point
+    pub fn lever_arm(&self, center: &Dimension3<T>) -> Dimension3<T>{
+        Dimension3::new(
+            self.mount_location.x() - center.x(),
+            self.mount_location.y() - center.y(),
+            self.mount_location.z() - center.z()
+        )
+    }
     
     //distance from dry center of mass to specified mount point
     pub fn distance_from_center(&self, center: Dimension3<T>) -> T{
@@ -109,11 +396,41 @@ impl<T> ThrusterMountPoint<T>
 
 
 /// Calculates a sum available thrust for each of 6 different axial directions, from a given set of thrusters.
+/// rotational axes hold the sum available torque(newton-meters) about the ship's center of mass.
+/// accounts for thruster health, so should also be called whenever a thruster's health changes.
+/// stuck on thrusters add to the side of each axis they push, and take the same away from the other side, since the
+/// rest of the suite has to cancel them before it can push that way.
 /// intended to be called on instantiation, and/or when thrusters are added/replaced, not on every frame update.
 pub fn calculate_available_thrust<T>(
     thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
 ) -> ControlAxis<Dimension3<AxisContribution<T>>>
     where T: Float
+{
+    let mut available_thrust = sum_available_thrust(thruster_mount_points, center_of_mass, |mount_point| mount_point.controllable_thrust());
+    let uncommanded = uncommanded_wrench(thruster_mount_points, center_of_mass);
+    let shift = |axis_contribution: &mut AxisContribution<T>, bias: T| {
+        axis_contribution.set_positive((axis_contribution.positive() + bias).max(num::zero()));
+        axis_contribution.set_negative((axis_contribution.negative() - bias).max(num::zero()));
+    };
+    shift(available_thrust.linear_mut().x_mut(), uncommanded[0]);
+    shift(available_thrust.linear_mut().y_mut(), uncommanded[1]);
+    shift(available_thrust.linear_mut().z_mut(), uncommanded[2]);
+    shift(available_thrust.rotational_mut().x_mut(), uncommanded[3]);
+    shift(available_thrust.rotational_mut().y_mut(), uncommanded[4]);
+    shift(available_thrust.rotational_mut().z_mut(), uncommanded[5]);
+
+    available_thrust
+}
+
+fn sum_available_thrust<T, F>(
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+    max_thrust: F,
+) -> ControlAxis<Dimension3<AxisContribution<T>>>
+    where 
+        T: Float,
+        F: Fn(&ThrusterMountPoint<T>) -> T
 {
     let mut available_thrust = ControlAxis::new(
         Dimension3::default(AxisContribution::new(num::zero(), num::zero())),
@@ -121,17 +438,21 @@ pub fn calculate_available_thrust<T>(
     );
 
     for thruster_mount_point in thruster_mount_points{
-        match thruster_mount_point.attached_thruster(){
-            Some(thruster) => {
-                sum_available_thrust_per_axis(
-                    &thruster,
-                    thruster_mount_point.thrust_direction(),
-                    //thruster_mount_point.distance_from_center(ship_center_of_mass),
-                    //angle between vector from center of mass to mount location, and vector of thrust_direction
-                    &mut available_thrust
-                )
-            },
-            None => {/*do nothing*/}
+        if thruster_mount_point.attached_thruster().is_none(){continue}
+        match thruster_mount_point.gimbal(){
+            Some(gimbal) => sum_gimbaled_thrust_per_axis(
+                max_thrust(thruster_mount_point),
+                thruster_mount_point,
+                gimbal,
+                &thruster_mount_point.lever_arm(center_of_mass),
+                &mut available_thrust
+            ),
+            None => sum_available_thrust_per_axis(
+                max_thrust(thruster_mount_point),
+                thruster_mount_point.thrust_direction(),
+                &thruster_mount_point.lever_arm(center_of_mass),
+                &mut available_thrust
+            )
         }
     }
 
@@ -143,136 +464,1980 @@ pub fn calculate_available_thrust<T>(
 /// thrust direction is a unit vector made up of 3 axes and a magnitude of 1.
 /// each axis will be <= 1, all 3 summing to 1
 /// so product of axis contribution and thruster max thrust produces axis 
-/// specific max thrust possible
+/// specific max thrust possible.
+/// torque is the cross product of the lever arm(center of mass to mount location) and the thrust force
 fn sum_available_thrust_per_axis<T>(
-    thruster: &Thruster<T>,
+    max_thrust: T,
     thrust_direction: Vector3<T>,
-    //distance_from_center: T,
-    //angle_of_force_generator: T,
+    lever_arm: &Dimension3<T>,
     available_thrust: &mut ControlAxis<Dimension3<AxisContribution<T>>>,
 )
     where T: Float
 {
-    match thrust_direction.x().is_sign_positive(){
-        true => {
-            let linear_sum = available_thrust.linear().x().positive() + (thrust_direction.x().abs() * thruster.max_thrust());
-            available_thrust.linear_mut().x_mut().set_positive(linear_sum);
+    let force = Dimension3::new(
+        thrust_direction.x() * max_thrust,
+        thrust_direction.y() * max_thrust,
+        thrust_direction.z() * max_thrust
+    );
+    let torque = torque(lever_arm, &force);
 
-            //let rotational_sum = force(newtons) * distance_from_center * sin(angle_of_force_generator);
-            //available_thrust.rotational_mut().x_mut().set_positive(rotational_sum);
-        }
-        false => {
-            let linear_sum = available_thrust.linear().x().negative() + (thrust_direction.x().abs() * thruster.max_thrust());
-            available_thrust.linear_mut().x_mut().set_negative(linear_sum);
+    add_to_axis_contribution(available_thrust.linear_mut().x_mut(), force.x());
+    add_to_axis_contribution(available_thrust.linear_mut().y_mut(), force.y());
+    add_to_axis_contribution(available_thrust.linear_mut().z_mut(), force.z());
+
+    add_to_axis_contribution(available_thrust.rotational_mut().x_mut(), torque.x());
+    add_to_axis_contribution(available_thrust.rotational_mut().y_mut(), torque.y());
+    add_to_axis_contribution(available_thrust.rotational_mut().z_mut(), torque.z());
+}
+
+
+
+const GIMBAL_ENVELOPE_STEPS: usize = 20;
+
+/// a gimbaled thruster can push either side of an axis depending on gimbal angle, so each side gets the 
+/// most it can reach anywhere in the gimbal range. searched over a grid of angles that includes the range limits.
+/// each axis is maximized independently, so this is an envelope, not a combination that can be achieved all at once.
+fn sum_gimbaled_thrust_per_axis<T>(
+    max_thrust: T,
+    thruster_mount_point: &ThrusterMountPoint<T>,
+    gimbal: &Gimbal<T>,
+    lever_arm: &Dimension3<T>,
+    available_thrust: &mut ControlAxis<Dimension3<AxisContribution<T>>>,
+)
+    where T: Float
+{
+    let lever_arm = [lever_arm.x(), lever_arm.y(), lever_arm.z()];
+    let unit = |axis: usize| -> [T; 3]{
+        let mut unit = [T::zero(); 3];
+        unit[axis] = T::one();
+        unit
+    };
+    // force along axis e is f·e. torque about axis e is (r × f)·e = f·(e × r)
+    let projections: [[T; 3]; 6] = [
+        unit(0), unit(1), unit(2),
+        cross3(&unit(0), &lever_arm), cross3(&unit(1), &lever_arm), cross3(&unit(2), &lever_arm),
+    ];
+
+    let mut positive = [T::zero(); 6];
+    let mut negative = [T::zero(); 6];
+    let steps: T = num::cast(GIMBAL_ENVELOPE_STEPS).unwrap();
+    let two: T = num::cast(2.0).unwrap();
+    for pitch_step in 0..=GIMBAL_ENVELOPE_STEPS{
+        for yaw_step in 0..=GIMBAL_ENVELOPE_STEPS{
+            let pitch_fraction: T = num::cast(pitch_step).unwrap();
+            let yaw_fraction: T = num::cast(yaw_step).unwrap();
+            let direction = thruster_mount_point.gimbaled_thrust_direction(&GimbalAngles::new(
+                gimbal.pitch_range() * ((two * pitch_fraction / steps) - T::one()),
+                gimbal.yaw_range() * ((two * yaw_fraction / steps) - T::one())
+            ));
+            let direction = [direction.x(), direction.y(), direction.z()];
+            for (axis, projection) in projections.iter().enumerate(){
+                let value = dot3(&direction, projection) * max_thrust;
+                positive[axis] = positive[axis].max(value);
+                negative[axis] = negative[axis].max(-value);
+            }
         }
     }
-    match thrust_direction.y().is_sign_positive(){
-        true => {
-            let linear_sum = available_thrust.linear().y().positive() + (thrust_direction.y().abs() * thruster.max_thrust());
-            available_thrust.linear_mut().y_mut().set_positive(linear_sum);
+
+    let add = |axis_contribution: &mut AxisContribution<T>, axis: usize|{
+        axis_contribution.set_positive(axis_contribution.positive() + positive[axis]);
+        axis_contribution.set_negative(axis_contribution.negative() + negative[axis]);
+    };
+    add(available_thrust.linear_mut().x_mut(), 0);
+    add(available_thrust.linear_mut().y_mut(), 1);
+    add(available_thrust.linear_mut().z_mut(), 2);
+    add(available_thrust.rotational_mut().x_mut(), 3);
+    add(available_thrust.rotational_mut().y_mut(), 4);
+    add(available_thrust.rotational_mut().z_mut(), 5);
+}
+
+/// one side of one of the 6 control axes
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ThrustDirection{
+    LinXPos,
+    LinXNeg,
+    LinYPos,
+    LinYNeg,
+    LinZPos,
+    LinZNeg,
+    RotXPos,
+    RotXNeg,
+    RotYPos,
+    RotYNeg,
+    RotZPos,
+    RotZNeg,
+}
+impl ThrustDirection{
+    /// in the same order as control_axis_to_array, positive before negative
+    pub const ALL: [ThrustDirection; 12] = [
+        ThrustDirection::LinXPos, ThrustDirection::LinXNeg,
+        ThrustDirection::LinYPos, ThrustDirection::LinYNeg,
+        ThrustDirection::LinZPos, ThrustDirection::LinZNeg,
+        ThrustDirection::RotXPos, ThrustDirection::RotXNeg,
+        ThrustDirection::RotYPos, ThrustDirection::RotYNeg,
+        ThrustDirection::RotZPos, ThrustDirection::RotZNeg,
+    ];
+}
+
+/// flattens into the same order as ThrustDirection::ALL
+pub(crate) fn axis_contributions_to_array<T: Float>(axis_contributions: &ControlAxis<Dimension3<AxisContribution<T>>>) -> [T; 12]{
+    let linear = axis_contributions.linear();
+    let rotational = axis_contributions.rotational();
+    [
+        linear.x().positive(), linear.x().negative(),
+        linear.y().positive(), linear.y().negative(),
+        linear.z().positive(), linear.z().negative(),
+        rotational.x().positive(), rotational.x().negative(),
+        rotational.y().positive(), rotational.y().negative(),
+        rotational.z().positive(), rotational.z().negative(),
+    ]
+}
+
+
+
+/// how much of a thruster suite's nominal control authority survives thruster damage
+pub struct AuthorityReport<T>{
+    // available thrust with thruster health accounted for / available thrust if every thruster was nominal.
+    // 1 = unaffected, 0 = no authority left. sides with no nominal authority report 1
+    remaining_authority: ControlAxis<Dimension3<AxisContribution<T>>>,
+    // sides of an axis that had authority when nominal, but have none now
+    lost_authority: Vec<ThrustDirection>,
+}
+impl<T> AuthorityReport<T>{
+    pub fn remaining_authority(&self) -> &ControlAxis<Dimension3<AxisContribution<T>>>{&self.remaining_authority}
+    pub fn lost_authority(&self) -> &[ThrustDirection]{&self.lost_authority}
+}
+
+/// compares available thrust against what the same thruster suite would provide with every thruster nominal
+pub fn calculate_authority_report<T>(
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+) -> AuthorityReport<T>
+    where T: Float
+{
+    let nominal = axis_contributions_to_array(&sum_available_thrust(
+        thruster_mount_points, 
+        center_of_mass, 
+        |mount_point| match mount_point.attached_thruster(){
+            Some(thruster) => thruster.max_thrust(),
+            None => num::zero()
         }
-        false => {
-            let linear_sum = available_thrust.linear().y().negative() + (thrust_direction.y().abs() * thruster.max_thrust());
-            available_thrust.linear_mut().y_mut().set_negative(linear_sum);
+    ));
+    let available = axis_contributions_to_array(&calculate_available_thrust(thruster_mount_points, center_of_mass));
+
+    let mut remaining = [T::one(); 12];
+    let mut lost_authority = Vec::new();
+    for (index, direction) in ThrustDirection::ALL.iter().enumerate(){
+        if nominal[index] <= T::epsilon(){continue}
+        remaining[index] = available[index] / nominal[index];
+        if available[index] <= T::epsilon(){
+            lost_authority.push(*direction);
         }
     }
-    match thrust_direction.z().is_sign_positive(){
-        true => {
-            let linear_sum = available_thrust.linear().z().positive() + (thrust_direction.z().abs() * thruster.max_thrust());
-            available_thrust.linear_mut().z_mut().set_positive(linear_sum);
-        }
-        false => {
-            let linear_sum = available_thrust.linear().z().negative() + (thrust_direction.z().abs() * thruster.max_thrust());
-            available_thrust.linear_mut().z_mut().set_negative(linear_sum);
-        }
+
+    AuthorityReport{
+        remaining_authority: ControlAxis::new(
+            Dimension3::new(
+                AxisContribution::new(remaining[0], remaining[1]),
+                AxisContribution::new(remaining[2], remaining[3]),
+                AxisContribution::new(remaining[4], remaining[5])
+            ),
+            Dimension3::new(
+                AxisContribution::new(remaining[6], remaining[7]),
+                AxisContribution::new(remaining[8], remaining[9]),
+                AxisContribution::new(remaining[10], remaining[11])
+            )
+        ),
+        lost_authority,
     }
 }
 
 
 
-//fn _torque<T: Float>(force: T, distance: T) -> T{
-//    //force(Newtons) * distance to center of mass or turning point(meters) * sin(angle_of_force_generator) 
-//    force * distance // * 1(if force generator perpendicular)
-//}
+/// whether each side of an axis can be driven without disturbing the other axes
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AxisControllability{
+    Controllable,
+    PositiveOnly,
+    NegativeOnly,
+    Uncontrollable,
+}
 
+/// what a thruster layout can and can't do, for a ship editor or a damage readout
+pub struct ControllabilityReport<T>{
+    // largest acceleration on each side of each axis, with every other axis held at zero.
+    // lower than available acceleration when thrusters have to fight each other to cancel unwanted force or torque
+    authority: ControlAxis<Dimension3<AxisContribution<T>>>,
+    controllability: ControlAxis<Dimension3<AxisControllability>>,
+    // sides of an axis with no decoupled authority
+    uncontrollable_directions: Vec<ThrustDirection>,
+    // mount points whose line of thrust misses the center of mass, so translating with them also rotates the ship
+    cross_coupling: Vec<CrossCoupling<T>>,
+    // mount points with no thruster attached
+    empty_mount_points: Vec<usize>,
+}
+impl<T> ControllabilityReport<T>{
+    pub fn authority(&self) -> &ControlAxis<Dimension3<AxisContribution<T>>>{&self.authority}
+    pub fn controllability(&self) -> &ControlAxis<Dimension3<AxisControllability>>{&self.controllability}
+    pub fn uncontrollable_directions(&self) -> &[ThrustDirection]{&self.uncontrollable_directions}
+    pub fn cross_coupling(&self) -> &[CrossCoupling<T>]{&self.cross_coupling}
+    pub fn empty_mount_points(&self) -> &[usize]{&self.empty_mount_points}
+    /// every side of every axis can be driven independently
+    pub fn is_fully_controllable(&self) -> bool{self.uncontrollable_directions.is_empty()}
+}
 
+pub struct CrossCoupling<T>{
+    mount_point: usize,
+    // torque produced per newton of thrust(r × thrust direction), in meters
+    torque_per_thrust: Dimension3<T>,
+}
+impl<T: Copy> CrossCoupling<T>{
+    pub fn mount_point(&self) -> usize{self.mount_point}
+    pub fn torque_per_thrust(&self) -> &Dimension3<T>{&self.torque_per_thrust}
+}
 
-/// use available thrust per axis and mass/moment of inertia to calculate available acceleration per axis.
-// torque(or moment) measured in newton-meters = Force(Newtons) * distance((meters)to center of mass/turning point)
-pub fn calculate_available_acceleration<T>(
-    available_thrust: &ControlAxis<Dimension3<AxisContribution<T>>>,
-    mass: T
-) -> ControlAxis<Dimension3<AxisContribution<T>>>
-    where T: Float//Mul<Output = T> + Div<Output = T> + Copy
+/// analyzes how well a thruster layout controls each of the 6 axes, given current thruster health
+pub fn calculate_controllability_report<T>(
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    mass_properties: &MassProperties<T>,
+) -> ControllabilityReport<T>
+    where T: Float
 {
-    ControlAxis::new(
-        Dimension3::new(
-            AxisContribution::new(
-                available_thrust.linear().x().positive() / mass,
-                available_thrust.linear().x().negative() / mass
-            ),
-            AxisContribution::new(
-                available_thrust.linear().y().positive() / mass,
-                available_thrust.linear().y().negative() / mass
+    let center_of_mass = mass_properties.center_of_mass();
+    let mut authority = [T::zero(); 12];
+    for (index, authority) in authority.iter_mut().enumerate(){
+        let mut acceleration = [T::zero(); 6];
+        acceleration[index / 2] = if index % 2 == 0{T::one()}else{-T::one()};
+        let unit_wrench = crate::control_axis_to_array(&calculate_desired_wrench(
+            &crate::array_to_control_axis(&acceleration), 
+            mass_properties
+        ));
+        // stuck on thrusters that can't be cancelled leave no decoupled authority at all
+        *authority = max_wrench_scale(&unit_wrench, &[T::zero(); 6], thruster_mount_points, center_of_mass, &[])
+            .unwrap_or(num::zero());
+    }
+
+    let mut uncontrollable_directions = Vec::new();
+    let mut controllability = [AxisControllability::Controllable; 6];
+    for (axis, controllability) in controllability.iter_mut().enumerate(){
+        let positive = authority[axis * 2] > num::zero();
+        let negative = authority[(axis * 2) + 1] > num::zero();
+        *controllability = match (positive, negative){
+            (true, true) => AxisControllability::Controllable,
+            (true, false) => AxisControllability::PositiveOnly,
+            (false, true) => AxisControllability::NegativeOnly,
+            (false, false) => AxisControllability::Uncontrollable,
+        };
+        if !positive{uncontrollable_directions.push(ThrustDirection::ALL[axis * 2])}
+        if !negative{uncontrollable_directions.push(ThrustDirection::ALL[(axis * 2) + 1])}
+    }
+
+    let tolerance: T = num::cast(1e-6).unwrap();
+    let mut cross_coupling = Vec::new();
+    let mut empty_mount_points = Vec::new();
+    for (index, mount_point) in thruster_mount_points.iter().enumerate(){
+        if mount_point.attached_thruster().is_none(){
+            empty_mount_points.push(index);
+            continue
+        }
+        let direction = mount_point.thrust_direction();
+        let lever_arm = mount_point.lever_arm(center_of_mass);
+        let torque_per_thrust = torque(&lever_arm, &Dimension3::new(direction.x(), direction.y(), direction.z()));
+        let magnitude = (
+            torque_per_thrust.x().powi(2) + 
+            torque_per_thrust.y().powi(2) + 
+            torque_per_thrust.z().powi(2)
+        ).sqrt();
+        if magnitude > tolerance{
+            cross_coupling.push(CrossCoupling{mount_point: index, torque_per_thrust});
+        }
+    }
+
+    ControllabilityReport{
+        authority: ControlAxis::new(
+            Dimension3::new(
+                AxisContribution::new(authority[0], authority[1]),
+                AxisContribution::new(authority[2], authority[3]),
+                AxisContribution::new(authority[4], authority[5])
             ),
-            AxisContribution::new(
-                available_thrust.linear().z().positive() / mass,
-                available_thrust.linear().z().negative() / mass
+            Dimension3::new(
+                AxisContribution::new(authority[6], authority[7]),
+                AxisContribution::new(authority[8], authority[9]),
+                AxisContribution::new(authority[10], authority[11])
             )
         ),
-        Dimension3::new(
-            AxisContribution::new(
-                calculate_angular_acceleration(available_thrust.rotational().x().positive()),//available_thrust.rotational().x().positive() / mass
-                calculate_angular_acceleration(available_thrust.rotational().x().negative())//available_thrust.rotational().x().negative() / mass
-            ),
-            AxisContribution::new(
-                available_thrust.rotational().y().positive() / mass,
-                available_thrust.rotational().y().negative() / mass
-            ),
-            AxisContribution::new(
-                available_thrust.rotational().z().positive() / mass,
-                available_thrust.rotational().z().negative() / mass
-            )
-        )
-    )
+        controllability: crate::array_to_control_axis(&controllability),
+        uncontrollable_directions,
+        cross_coupling,
+        empty_mount_points,
+    }
+}
+
+/// largest s for which fixed_wrench + s * unit_wrench can be produced exactly, solved as the linear program
+/// max s subject to B·u - s·unit_wrench = fixed - uncommanded, 0 <= u <= 1.
+/// additional_columns are extra actuators(e.g. momentum devices) that also get a throttle in [0, 1].
+/// None if fixed_wrench can't be produced at all, such as with a stuck on thruster that can't be cancelled
+fn max_wrench_scale<T>(
+    unit_wrench: &[T; 6],
+    fixed_wrench: &[T; 6],
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+    additional_columns: &[[T; 6]],
+) -> Option<T>
+    where T: Float
+{
+    let virtual_columns = virtual_thrusters(thruster_mount_points, center_of_mass);
+    let gimbal_constraints = gimbal_constraints(&virtual_columns);
+    let columns: Vec<[T; 6]> = virtual_columns.iter().map(|column| column.wrench).chain(additional_columns.iter().copied()).collect();
+    let uncommanded = uncommanded_wrench(thruster_mount_points, center_of_mass);
+    // variables are [throttles, s, throttle slacks, gimbal slacks]
+    let scale_variable = columns.len();
+    let variables = (columns.len() * 2) + 1 + gimbal_constraints.len();
+
+    let mut a: Vec<Vec<T>> = Vec::new();
+    let mut b: Vec<T> = Vec::new();
+    for axis in 0..6{
+        let scale = columns.iter().fold(unit_wrench[axis].abs().max(fixed_wrench[axis].abs()), |max, column| max.max(column[axis].abs()));
+        if scale <= T::epsilon(){continue}
+        let mut row = vec![num::zero(); variables];
+        for (value, column) in row.iter_mut().zip(columns.iter()){
+            *value = column[axis] / scale;
+        }
+        row[scale_variable] = -unit_wrench[axis] / scale;
+        a.push(row);
+        b.push((fixed_wrench[axis] - uncommanded[axis]) / scale);
+    }
+    for position in 0..columns.len(){
+        let mut row = vec![num::zero(); variables];
+        row[position] = num::one();
+        row[columns.len() + 1 + position] = num::one();
+        a.push(row);
+        b.push(num::one());
+    }
+    for (position, (axial, laterals)) in gimbal_constraints.iter().enumerate(){
+        let mut row = vec![num::zero(); variables];
+        row[laterals[0]] = num::one();
+        row[laterals[1]] = num::one();
+        row[*axial] = -T::one();
+        row[(columns.len() * 2) + 1 + position] = num::one();
+        a.push(row);
+        b.push(num::zero());
+    }
+    let mut c = vec![num::zero(); variables];
+    c[scale_variable] = -T::one();
+
+    crate::optimization::solve_linear_program(&a, &b, &c).map(|solution| {
+        let tolerance: T = num::cast(1e-9).unwrap();
+        if solution[scale_variable] > tolerance{solution[scale_variable]}else{num::zero()}
+    })
+}
+
+
+
+/// which half of the wrench an attainable set covers
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WrenchSubspace{
+    Force,
+    Torque,
+}
+
+/// every force(or torque) the thruster suite can produce, regardless of what it does on the other subspace.
+/// a convex polytope(zonotope) with one generator per thruster.
+/// gimbaled thrusters are taken at their nominal thrust direction.
+/// with fewer than 3 independent generators the set is flat(a polygon, line segment or point), and has no faces
+pub struct AttainableWrenchSet<T>{
+    subspace: WrenchSubspace,
+    vertices: Vec<Dimension3<T>>,
+    // indices into vertices, counter clockwise when viewed from outside
+    faces: Vec<Vec<usize>>,
+    // outward unit normal of each face, and its distance from origin along that normal. n·x <= distance inside
+    face_planes: Vec<([T; 3], T)>,
+    // kept for flat sets, which have no face planes to test against
+    generators: Vec<[T; 3]>,
+    // wrench from stuck on thrusters, which the set is built around
+    offset: [T; 3],
+}
+impl<T> AttainableWrenchSet<T>
+    where T: Float
+{
+    pub fn subspace(&self) -> WrenchSubspace{self.subspace}
+
+    pub fn vertices(&self) -> &[Dimension3<T>]{&self.vertices}
+
+    pub fn faces(&self) -> &[Vec<usize>]{&self.faces}
+
+    pub fn face_normal(&self, face: usize) -> Option<Dimension3<T>>{
+        self.face_planes.get(face).map(|(normal, _)| Dimension3::new(normal[0], normal[1], normal[2]))
+    }
+
+    /// for a flat set, only true for wrenches lying in the set itself
+    pub fn contains(&self, wrench: &Dimension3<T>) -> bool{
+        let wrench = [wrench.x(), wrench.y(), wrench.z()];
+        if self.face_planes.is_empty(){
+            let tolerance: T = num::cast(1e-9).unwrap();
+            return self.flat_fraction(&wrench).is_some_and(|fraction| fraction >= T::one() - tolerance)
+        }
+        let tolerance = self.tolerance();
+        self.face_planes.iter().all(|(normal, distance)| dot3(normal, &wrench) <= *distance + tolerance)
+    }
+
+    /// largest s for which s * wrench is inside the set. >= 1 when wrench is attainable.
+    /// T::infinity() for a zero wrench, 0 if zero wrench itself isn't attainable(stuck on thrusters)
+    pub fn attainable_fraction(&self, wrench: &Dimension3<T>) -> T{
+        if !self.contains(&Dimension3::new(num::zero(), num::zero(), num::zero())){return num::zero()}
+        let wrench = [wrench.x(), wrench.y(), wrench.z()];
+        if self.face_planes.is_empty(){
+            return self.flat_fraction(&wrench).unwrap_or(num::zero())
+        }
+        let mut fraction = T::infinity();
+        for (normal, distance) in self.face_planes.iter(){
+            let along = dot3(normal, &wrench);
+            if along > T::epsilon(){
+                fraction = fraction.min(distance.max(num::zero()) / along);
+            }
+        }
+        fraction
+    }
+
+    fn tolerance(&self) -> T{
+        let scale = self.face_planes.iter().fold(T::one(), |max, (_, distance)| max.max(distance.abs()));
+        scale * num::cast(1e-9).unwrap()
+    }
+
+    /// largest s for which s * wrench is in a flat set, solved as the linear program
+    /// max s subject to G·u - s·wrench = -offset, 0 <= u <= 1. a flat set has no interior for face planes to bound,
+    /// so this is how it is tested instead. a zero wrench gives T::infinity() if the origin is in the set.
+    /// None if no multiple of wrench(including zero) is in the set
+    fn flat_fraction(&self, wrench: &[T; 3]) -> Option<T>{
+        let zero = wrench.iter().all(|component| *component == num::zero());
+        let columns = self.generators.len();
+        // variables are [throttles, s, throttle slacks]
+        let scale_variable = columns;
+        let variables = (columns * 2) + 1;
+
+        let mut a: Vec<Vec<T>> = Vec::new();
+        let mut b: Vec<T> = Vec::new();
+        for axis in 0..3{
+            let scale = self.generators.iter()
+                .fold(wrench[axis].abs().max(self.offset[axis].abs()), |max, generator| max.max(generator[axis].abs()));
+            if scale <= T::epsilon(){continue}
+            let mut row = vec![num::zero(); variables];
+            for (value, generator) in row.iter_mut().zip(self.generators.iter()){
+                *value = generator[axis] / scale;
+            }
+            row[scale_variable] = -wrench[axis] / scale;
+            a.push(row);
+            b.push(-self.offset[axis] / scale);
+        }
+        for position in 0..columns{
+            let mut row = vec![num::zero(); variables];
+            row[position] = num::one();
+            row[columns + 1 + position] = num::one();
+            a.push(row);
+            b.push(num::one());
+        }
+        // with a zero wrench s is unbounded, so only feasibility is checked
+        let mut c = vec![num::zero(); variables];
+        if !zero{c[scale_variable] = -T::one()}
+
+        crate::optimization::solve_linear_program(&a, &b, &c).map(|solution| {
+            if zero{return T::infinity()}
+            let tolerance: T = num::cast(1e-9).unwrap();
+            if solution[scale_variable] > tolerance{solution[scale_variable]}else{num::zero()}
+        })
+    }
+}
+
+pub fn calculate_attainable_wrench_set<T>(
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+    subspace: WrenchSubspace,
+) -> AttainableWrenchSet<T>
+    where T: Float
+{
+    let range = match subspace{
+        WrenchSubspace::Force => 0..3,
+        WrenchSubspace::Torque => 3..6,
+    };
+    let project = |wrench: &[T; 6]| [wrench[range.start], wrench[range.start + 1], wrench[range.start + 2]];
+    let offset = project(&uncommanded_wrench(thruster_mount_points, center_of_mass));
+    let generators: Vec<[T; 3]> = thruster_mount_points.iter()
+        .filter(|mount_point| mount_point.controllable_thrust() > num::zero())
+        .map(|mount_point| project(&max_thruster_wrench(mount_point, center_of_mass)))
+        .filter(|generator| dot3(generator, generator) > T::epsilon())
+        .collect();
+    let scale = generators.iter().fold(T::one(), |max, generator| max.max(dot3(generator, generator).sqrt()));
+    let tolerance: T = scale * num::cast(1e-9).unwrap();
+
+    let mut vertices: Vec<[T; 3]> = Vec::new();
+    let mut add_vertex = |vertex: [T; 3]| -> usize{
+        match vertices.iter().position(|existing| {
+            let difference = [existing[0] - vertex[0], existing[1] - vertex[1], existing[2] - vertex[2]];
+            dot3(&difference, &difference).sqrt() <= tolerance
+        }){
+            Some(index) => index,
+            None => {
+                vertices.push(vertex);
+                vertices.len() - 1
+            }
+        }
+    };
+    let mut faces: Vec<Vec<usize>> = Vec::new();
+    let mut face_planes: Vec<([T; 3], T)> = Vec::new();
+
+    // generators that all lie in one plane(or along one line) only span a flat set
+    let mut spanning_normal: Option<[T; 3]> = None;
+    for (first_index, first) in generators.iter().enumerate(){
+        for second in generators.iter().skip(first_index + 1){
+            let normal = cross3(first, second);
+            if dot3(&normal, &normal).sqrt() > tolerance{
+                spanning_normal = spanning_normal.or(Some(normalize3(normal)));
+            }
+        }
+    }
+    let flat = match spanning_normal{
+        Some(normal) => generators.iter().all(|generator| dot3(&normal, generator).abs() <= tolerance),
+        None => true
+    };
+
+    // every facet of a 3d zonotope is normal to the cross product of some pair of generators
+    let parallel_threshold: T = T::one() - num::cast(1e-9).unwrap();
+    for (first_index, first) in generators.iter().enumerate(){
+        for second in generators.iter().skip(first_index + 1){
+            let normal = cross3(first, second);
+            if dot3(&normal, &normal).sqrt() <= tolerance * scale{continue}
+            let normal = normalize3(normal);
+            for normal in [normal, [-normal[0], -normal[1], -normal[2]]]{
+                if face_planes.iter().any(|(existing, _)| dot3(existing, &normal) > parallel_threshold){continue}
+
+                // generators pointing out of the face are at full throttle, ones in the face's plane span it
+                let mut center = offset;
+                let mut in_plane: Vec<[T; 3]> = Vec::new();
+                let half: T = num::cast(0.5).unwrap();
+                for generator in generators.iter(){
+                    let along = dot3(&normal, generator);
+                    if along.abs() <= tolerance{
+                        in_plane.push(*generator);
+                        for (center, component) in center.iter_mut().zip(generator.iter()){
+                            *center = *center + (*component * half);
+                        }
+                    }else if along > num::zero(){
+                        for (center, component) in center.iter_mut().zip(generator.iter()){
+                            *center = *center + *component;
+                        }
+                    }
+                }
+
+                // the face is a zonogon, walk its edges in order of angle around the normal
+                let e1 = normalize3(in_plane[0]);
+                let e2 = cross3(&normal, &e1);
+                let pi: T = num::cast(std::f64::consts::PI).unwrap();
+                let angle_tolerance: T = num::cast(1e-9).unwrap();
+                let mut edges: Vec<(T, [T; 3])> = in_plane.iter()
+                    .map(|generator| {
+                        let angle = dot3(generator, &e2).atan2(dot3(generator, &e1));
+                        // a segment is symmetric about the face center, so flipping it keeps every angle in [0, π)
+                        if angle < -angle_tolerance || angle >= pi - angle_tolerance{
+                            let flipped = [-generator[0], -generator[1], -generator[2]];
+                            (dot3(&flipped, &e2).atan2(dot3(&flipped, &e1)).max(num::zero()), flipped)
+                        }else{
+                            (angle.max(num::zero()), *generator)
+                        }
+                    })
+                    .collect();
+                edges.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+                // parallel generators make up a single edge
+                let mut merged: Vec<(T, [T; 3])> = Vec::new();
+                for (angle, edge) in edges{
+                    match merged.last_mut(){
+                        Some((last_angle, last_edge)) if (angle - *last_angle).abs() <= angle_tolerance => {
+                            for (last, component) in last_edge.iter_mut().zip(edge.iter()){
+                                *last = *last + *component;
+                            }
+                        },
+                        _ => merged.push((angle, edge))
+                    }
+                }
+                let edges = merged;
+                let mut vertex = center;
+                for (_, edge) in edges.iter(){
+                    for (vertex, component) in vertex.iter_mut().zip(edge.iter()){
+                        *vertex = *vertex - (*component * half);
+                    }
+                }
+                let mut face = Vec::new();
+                for sign in [T::one(), -T::one()]{
+                    for (_, edge) in edges.iter(){
+                        face.push(add_vertex(vertex));
+                        for (vertex, component) in vertex.iter_mut().zip(edge.iter()){
+                            *vertex = *vertex + (*component * sign);
+                        }
+                    }
+                }
+                face.dedup();
+
+                face_planes.push((normal, dot3(&normal, &center)));
+                faces.push(face);
+            }
+        }
+    }
+
+    // fewer than 3 independent generators, the set is a point or line segment with no faces
+    if faces.is_empty(){
+        add_vertex(offset);
+        if let Some(direction) = generators.first(){
+            let mut low = offset;
+            let mut high = offset;
+            for generator in generators.iter(){
+                let end = if dot3(direction, generator) > num::zero(){&mut high}else{&mut low};
+                for (end, component) in end.iter_mut().zip(generator.iter()){
+                    *end = *end + *component;
+                }
+            }
+            add_vertex(low);
+            add_vertex(high);
+        }
+    }
+    // a flat polygon only gets its top and bottom, which would pass every in plane wrench. its vertices are kept
+    if flat{
+        faces.clear();
+        face_planes.clear();
+    }
+
+    AttainableWrenchSet{
+        subspace,
+        vertices: vertices.iter().map(|vertex| Dimension3::new(vertex[0], vertex[1], vertex[2])).collect(),
+        faces,
+        face_planes,
+        generators,
+        offset,
+    }
 }
 
-// need an actual formula for angular acceleration
-fn calculate_angular_acceleration<T>(some_input: T) -> T{some_input}
+/// largest s for which s * desired_wrench can be produced exactly by the thruster suite, accounting for both force and
+/// torque together. >= 1 when desired_wrench is achievable. 0 when no positive multiple of it is, T::infinity() for a zero wrench
+pub fn calculate_attainable_fraction<T>(
+    desired_wrench: &ControlAxis<Dimension3<T>>,
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+) -> T
+    where T: Float
+{
+    let desired = crate::control_axis_to_array(desired_wrench);
+    if desired.iter().all(|component| *component == num::zero()){return T::infinity()}
+    max_wrench_scale(&desired, &[T::zero(); 6], thruster_mount_points, center_of_mass, &[]).unwrap_or(num::zero())
+}
 
 
 
+/// limits desired acceleration to what the thrusters and momentum devices can actually produce together, using the
+/// given strategy. unlike per axis limits, axes compete here, since translating and rotating draw on the same thrusters.
+/// PerAxis leaves the command unchanged, allocation then finds the closest wrench it can.
+/// the command is also left unchanged if nothing can be produced exactly(stuck on thrusters that can't be cancelled)
+pub fn saturate_to_attainable<T>(
+    desired_acceleration: &ControlAxis<Dimension3<T>>,
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    momentum_devices: &[MomentumDevice<T>],
+    mass_properties: &MassProperties<T>,
+    strategy: SaturationStrategy,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    let desired = crate::control_axis_to_array(desired_acceleration);
+    // a momentum device can torque either way, so it is a column for each direction
+    let mut device_columns: Vec<[T; 6]> = Vec::new();
+    for device in momentum_devices{
+        let (positive, negative) = device.available_torque();
+        let axis = device.torque_axis_array();
+        for limit in [positive, -negative]{
+            device_columns.push([num::zero(), num::zero(), num::zero(), axis[0] * limit, axis[1] * limit, axis[2] * limit]);
+        }
+    }
+    let wrench_of = |acceleration: &[T; 6]| crate::control_axis_to_array(&calculate_desired_wrench(
+        &crate::array_to_control_axis(acceleration), 
+        mass_properties
+    ));
+    // fraction(up to 1) of part that fits alongside fixed. None leaves the whole command as is
+    let fraction = |part: &[T; 6], fixed: &[T; 6]| -> Option<T>{
+        if part.iter().all(|component| *component == num::zero()){return Some(num::one())}
+        max_wrench_scale(
+            &wrench_of(part), 
+            &wrench_of(fixed), 
+            thruster_mount_points, 
+            mass_properties.center_of_mass(), 
+            &device_columns
+        ).map(|scale| scale.min(num::one()))
+    };
+    let scaled = |part: &[T; 6], scale: T| {
+        let mut scaled = *part;
+        for component in scaled.iter_mut(){
+            *component = *component * scale;
+        }
+        scaled
+    };
+
+    let saturated = match strategy{
+        SaturationStrategy::PerAxis => None,
+        SaturationStrategy::UniformScaling => fraction(&desired, &[T::zero(); 6]).map(|scale| scaled(&desired, scale)),
+        SaturationStrategy::RotationPriority => {
+            let mut rotation = desired;
+            let mut translation = desired;
+            for component in rotation.iter_mut().take(3){*component = num::zero()}
+            for component in translation.iter_mut().skip(3){*component = num::zero()}
+            fraction(&rotation, &[T::zero(); 6]).and_then(|rotation_scale| {
+                let rotation = scaled(&rotation, rotation_scale);
+                fraction(&translation, &rotation).map(|translation_scale| {
+                    let mut combined = scaled(&translation, translation_scale);
+                    for (combined, rotation) in combined.iter_mut().zip(rotation.iter()){
+                        *combined = *combined + *rotation;
+                    }
+                    combined
+                })
+            })
+        },
+        SaturationStrategy::DaisyChain => {
+            let mut fixed = [T::zero(); 6];
+            let mut feasible = true;
+            for axis in [3, 4, 5, 0, 1, 2]{
+                let mut part = [T::zero(); 6];
+                part[axis] = desired[axis];
+                match fraction(&part, &fixed){
+                    Some(scale) => fixed[axis] = desired[axis] * scale,
+                    None => {
+                        feasible = false;
+                        break
+                    }
+                }
+            }
+            if feasible{Some(fixed)}else{None}
+        },
+    };
+
+    match saturated{
+        Some(saturated) => crate::array_to_control_axis(&saturated),
+        None => crate::array_to_control_axis(&desired)
+    }
+}
+
+
+
+/// adds the magnitude of value to the side of the axis its sign points to
+fn add_to_axis_contribution<T>(axis_contribution: &mut AxisContribution<T>, value: T)
+    where T: Float
+{
+    match value.is_sign_positive(){
+        true => {
+            let sum = axis_contribution.positive() + value.abs();
+            axis_contribution.set_positive(sum);
+        }
+        false => {
+            let sum = axis_contribution.negative() + value.abs();
+            axis_contribution.set_negative(sum);
+        }
+    }
+}
+
+
+
+/// torque(or moment) measured in newton-meters. τ = r × F
+pub fn torque<T: Float>(lever_arm: &Dimension3<T>, force: &Dimension3<T>) -> Dimension3<T>{
+    Dimension3::new(
+        (lever_arm.y() * force.z()) - (lever_arm.z() * force.y()),
+        (lever_arm.z() * force.x()) - (lever_arm.x() * force.z()),
+        (lever_arm.x() * force.y()) - (lever_arm.y() * force.x())
+    )
+}
+
+
+
+/// mass, center of mass, and inertia tensor of a ship. 
+/// inertia tensor is the full 3x3 matrix about the center of mass, in ship local axes, including products of inertia.
+pub struct MassProperties<T>{
+    mass: T,
+    center_of_mass: Dimension3<T>,
+    inertia_tensor: [[T; 3]; 3],
+    // cached, because every angular acceleration calculation needs it
+    inverse_inertia_tensor: [[T; 3]; 3],
+}
+impl<T> MassProperties<T>
+    where T: Float
+{
+    pub fn new(
+        mass: T, 
+        center_of_mass: Dimension3<T>, 
+        inertia_tensor: [[T; 3]; 3]
+    ) -> Result<Self, FcsError<'static>>{
+        if mass <= num::zero(){
+            return Err(FcsError::new("Mass must be greater than zero."))
+        }
+        let inverse_inertia_tensor = match invert_3x3(&inertia_tensor){
+            Some(inverse) => inverse,
+            None => return Err(FcsError::new("Inertia tensor must be invertible."))
+        };
+
+        Ok(Self{
+            mass, 
+            center_of_mass, 
+            inertia_tensor, 
+            inverse_inertia_tensor
+        })
+    }
+
+    pub fn mass(&self) -> T{self.mass}
+
+    pub fn center_of_mass(&self) -> &Dimension3<T>{&self.center_of_mass}
+
+    pub fn inertia_tensor(&self) -> &[[T; 3]; 3]{&self.inertia_tensor}
+
+    /// α = I⁻¹·τ
+    pub fn angular_acceleration(&self, torque: &Dimension3<T>) -> Dimension3<T>{
+        multiply_3x3(&self.inverse_inertia_tensor, torque)
+    }
+
+    /// τ = I·α
+    pub fn torque(&self, angular_acceleration: &Dimension3<T>) -> Dimension3<T>{
+        multiply_3x3(&self.inertia_tensor, angular_acceleration)
+    }
+}
+
+
+
+fn multiply_3x3<T: Float>(matrix: &[[T; 3]; 3], vector: &Dimension3<T>) -> Dimension3<T>{
+    Dimension3::new(
+        (matrix[0][0] * vector.x()) + (matrix[0][1] * vector.y()) + (matrix[0][2] * vector.z()),
+        (matrix[1][0] * vector.x()) + (matrix[1][1] * vector.y()) + (matrix[1][2] * vector.z()),
+        (matrix[2][0] * vector.x()) + (matrix[2][1] * vector.y()) + (matrix[2][2] * vector.z())
+    )
+}
+
+fn invert_3x3<T: Float>(m: &[[T; 3]; 3]) -> Option<[[T; 3]; 3]>{
+    let cofactor = |row_a: usize, row_b: usize, col_a: usize, col_b: usize| -> T{
+        (m[row_a][col_a] * m[row_b][col_b]) - (m[row_a][col_b] * m[row_b][col_a])
+    };
+    // adjugate is the transpose of the cofactor matrix
+    let adjugate = [
+        [cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+        [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+        [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)],
+    ];
+    let determinant = (m[0][0] * adjugate[0][0]) + (m[0][1] * adjugate[1][0]) + (m[0][2] * adjugate[2][0]);
+    // relative to the tensor's scale, so small ships(or f32) aren't mistaken for singular
+    let scale = m.iter().flatten().fold(T::zero(), |scale, value| scale.max(value.abs()));
+    if scale <= num::zero() || determinant.abs() <= T::epsilon() * scale.powi(3){
+        return None
+    }
+
+    let mut inverse = adjugate;
+    for row in inverse.iter_mut(){
+        for value in row.iter_mut(){
+            *value = *value / determinant;
+        }
+    }
+
+    Some(inverse)
+}
+
+
+
+/// use available thrust per axis and mass/moment of inertia to calculate available acceleration per axis.
+/// rotational axes report the largest angular acceleration purely about that axis that the available torque
+/// can produce. with off-diagonal products of inertia, a rotation about one axis needs torque on the others too(τ = I·α),
+/// so the most restrictive of those torque components sets the limit.
+pub fn calculate_available_acceleration<T>(
+    available_thrust: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    mass_properties: &MassProperties<T>
+) -> ControlAxis<Dimension3<AxisContribution<T>>>
+    where T: Float
+{
+    let mass = mass_properties.mass();
+    ControlAxis::new(
+        Dimension3::new(
+            AxisContribution::new(
+                available_thrust.linear().x().positive() / mass,
+                available_thrust.linear().x().negative() / mass
+            ),
+            AxisContribution::new(
+                available_thrust.linear().y().positive() / mass,
+                available_thrust.linear().y().negative() / mass
+            ),
+            AxisContribution::new(
+                available_thrust.linear().z().positive() / mass,
+                available_thrust.linear().z().negative() / mass
+            )
+        ),
+        Dimension3::new(
+            AxisContribution::new(
+                calculate_angular_acceleration(available_thrust.rotational(), mass_properties, 0, true),
+                calculate_angular_acceleration(available_thrust.rotational(), mass_properties, 0, false)
+            ),
+            AxisContribution::new(
+                calculate_angular_acceleration(available_thrust.rotational(), mass_properties, 1, true),
+                calculate_angular_acceleration(available_thrust.rotational(), mass_properties, 1, false)
+            ),
+            AxisContribution::new(
+                calculate_angular_acceleration(available_thrust.rotational(), mass_properties, 2, true),
+                calculate_angular_acceleration(available_thrust.rotational(), mass_properties, 2, false)
+            )
+        )
+    )
+}
+
+/// largest angular acceleration purely about the given axis(0 = x, 1 = y, 2 = z), in the given direction.
+/// α_max = min over each torque axis j of available_torque_j / |I_j,axis|
+fn calculate_angular_acceleration<T>(
+    available_torque: &Dimension3<AxisContribution<T>>,
+    mass_properties: &MassProperties<T>,
+    axis: usize,
+    positive: bool,
+) -> T
+    where T: Float
+{
+    let available_torque = [available_torque.x(), available_torque.y(), available_torque.z()];
+    let mut angular_acceleration = T::infinity();
+    for (row, torque) in mass_properties.inertia_tensor().iter().zip(available_torque.iter()){
+        // torque needed on this axis per unit of angular acceleration
+        let required = if positive{row[axis]}else{-row[axis]};
+        if required.abs() <= T::epsilon(){continue}
+        let limit = if required > num::zero(){torque.positive()}else{torque.negative()};
+        angular_acceleration = angular_acceleration.min(limit / required.abs());
+    }
+
+    if angular_acceleration.is_finite(){angular_acceleration}else{num::zero()}
+}
+
+
+
+
+
+
+
+/// converts desired accelerations to the force(m·a) and torque(I·α) the thruster suite must produce
+pub fn calculate_desired_wrench<T>(
+    desired_acceleration: &ControlAxis<Dimension3<T>>,
+    mass_properties: &MassProperties<T>,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    //f = m * a
+    let mass = mass_properties.mass();
+
+    ControlAxis::new(
+        Dimension3::new(
+            desired_acceleration.linear().x() * mass, 
+            desired_acceleration.linear().y() * mass, 
+            desired_acceleration.linear().z() * mass
+        ),
+        //τ = I * α
+        mass_properties.torque(desired_acceleration.rotational())
+    )
+}
+
+
+
+/// per thruster throttle values that best achieve a desired wrench(force and torque)
+pub struct ThrusterOutput<T>{
+    // one value in [0, 1] for each mount point, in the same order as the mount points passed in.
+    // mount points with no attached thruster are always 0
+    throttles: Vec<T>,
+    // one value for each mount point, Some for gimbaled mount points
+    gimbal_angles: Vec<Option<GimbalAngles<T>>>,
+    achieved_wrench: ControlAxis<Dimension3<T>>,
+    // desired wrench - achieved wrench. non zero when the desired wrench is outside of what the thrusters can produce
+    residual: ControlAxis<Dimension3<T>>,
+}
+impl<T> ThrusterOutput<T>{
+    pub fn throttles(&self) -> &[T]{&self.throttles}
+    pub fn gimbal_angles(&self) -> &[Option<GimbalAngles<T>>]{&self.gimbal_angles}
+    pub fn achieved_wrench(&self) -> &ControlAxis<Dimension3<T>>{&self.achieved_wrench}
+    pub fn residual(&self) -> &ControlAxis<Dimension3<T>>{&self.residual}
+}
+
+
+
+/// how calculate_thruster_output distributes the desired wrench across the thruster suite
+pub enum AllocationMethod<T>{
+    /// closest achievable wrench, see allocate_thrust
+    LeastSquares,
+    /// exact wrench using the least propellant mass flow, from each thruster's propellant consumption model.
+    /// thrusters with no consumption model are costed by their thrust instead
+    MinimumFuel,
+    /// exact wrench minimizing the sum of weight * throttle, one weight per mount point
+    WeightedThrottle(Vec<T>),
+}
+impl<T> AllocationMethod<T>{
+    /// WeightedThrottle needs exactly one weight per mount point
+    pub fn validate(&self, mount_point_count: usize) -> Result<(), FcsError<'static>>{
+        match self{
+            AllocationMethod::WeightedThrottle(weights) if weights.len() != mount_point_count => Err(
+                FcsError::with_field("Must have one weight per mount point.", "WeightedThrottle".to_string())
+            ),
+            _ => Ok(())
+        }
+    }
+}
+
+
+
+pub fn calculate_thruster_output<T>(
+    desired_acceleration: &ControlAxis<Dimension3<T>>,
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    mass_properties: &MassProperties<T>,
+    allocation_method: &AllocationMethod<T>,
+) -> Result<ThrusterOutput<T>, FcsError<'static>>
+    where T: Float
+{
+    allocate_wrench(&calculate_desired_wrench(desired_acceleration, mass_properties), thruster_mount_points, mass_properties, allocation_method)
+}
+
+fn allocate_wrench<T>(
+    desired_wrench: &ControlAxis<Dimension3<T>>,
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    mass_properties: &MassProperties<T>,
+    allocation_method: &AllocationMethod<T>,
+) -> Result<ThrusterOutput<T>, FcsError<'static>>
+    where T: Float
+{
+    allocation_method.validate(thruster_mount_points.len())?;
+    match allocation_method{
+        AllocationMethod::LeastSquares => Ok(allocate_thrust(
+            desired_wrench, 
+            thruster_mount_points, 
+            mass_properties.center_of_mass()
+        )),
+        AllocationMethod::MinimumFuel => allocate_thrust_minimum_cost(
+            desired_wrench, 
+            thruster_mount_points, 
+            mass_properties.center_of_mass(),
+            &thruster_mount_points.iter()
+                .map(|mount_point| match mount_point.attached_thruster(){
+                    Some(thruster) => {
+                        let thrust = mount_point.controllable_thrust();
+                        thruster.mass_flow(num::one(), thrust).unwrap_or(thrust)
+                    },
+                    None => num::zero()
+                })
+                .collect::<Vec<T>>()
+        ),
+        AllocationMethod::WeightedThrottle(weights) => allocate_thrust_minimum_cost(
+            desired_wrench, 
+            thruster_mount_points, 
+            mass_properties.center_of_mass(),
+            weights
+        ),
+    }
+}
+
+
+
+/// thruster output alongside the torque each momentum exchange device should produce
+pub struct BlendedOutput<T>{
+    thruster_output: ThrusterOutput<T>,
+    // one value for each device, along its torque axis. see momentum_devices::allocate_torque
+    momentum_device_torques: Vec<T>,
+}
+impl<T> BlendedOutput<T>{
+    pub fn thruster_output(&self) -> &ThrusterOutput<T>{&self.thruster_output}
+    pub fn momentum_device_torques(&self) -> &[T]{&self.momentum_device_torques}
+    pub fn into_parts(self) -> (ThrusterOutput<T>, Vec<T>){(self.thruster_output, self.momentum_device_torques)}
+}
+
+/// like calculate_thruster_output, but rotation is handed to the momentum exchange devices first, since they burn no
+/// propellant. the thrusters make up whatever torque the devices can't, and counter any momentum dump torque.
+/// thruster_output's residual is what neither could produce
+pub fn calculate_blended_output<T>(
+    desired_acceleration: &ControlAxis<Dimension3<T>>,
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    momentum_devices: &[MomentumDevice<T>],
+    mass_properties: &MassProperties<T>,
+    allocation_method: &AllocationMethod<T>,
+    momentum_dump_threshold: T,
+    delta_time: T,
+) -> Result<BlendedOutput<T>, FcsError<'static>>
+    where T: Float
+{
+    let desired_wrench = calculate_desired_wrench(desired_acceleration, mass_properties);
+    let momentum_device_torques = momentum_devices::allocate_torque(
+        desired_wrench.rotational(), 
+        momentum_devices, 
+        momentum_dump_threshold, 
+        delta_time
+    );
+    let mut device_torque = [T::zero(); 3];
+    for (device, torque) in momentum_devices.iter().zip(momentum_device_torques.iter()){
+        for (total, component) in device_torque.iter_mut().zip(device.torque_axis_array().iter()){
+            *total = *total + (*torque * *component);
+        }
+    }
+    let thruster_wrench = ControlAxis::new(
+        Dimension3::new(desired_wrench.linear().x(), desired_wrench.linear().y(), desired_wrench.linear().z()),
+        Dimension3::new(
+            desired_wrench.rotational().x() - device_torque[0],
+            desired_wrench.rotational().y() - device_torque[1],
+            desired_wrench.rotational().z() - device_torque[2]
+        )
+    );
+
+    Ok(BlendedOutput{
+        thruster_output: allocate_wrench(&thruster_wrench, thruster_mount_points, mass_properties, allocation_method)?,
+        momentum_device_torques,
+    })
+}
+
+
+
+const MAX_ALLOCATION_ITERATIONS: usize = 500;
+
+/// distributes a desired wrench across the thruster suite.
+/// solves min ||B·u - w||² subject to 0 <= u <= 1, where each column of B is the wrench a thruster 
+/// produces at full throttle, using cyclic coordinate descent. thrusters can only push, so a
+/// thruster is never given a negative throttle.
+/// gimbaled thrusters are split into an axial column and lateral columns for each gimbal direction, which are
+/// recombined into a throttle and gimbal angles. each throttle is kept inside the bounds that hold the deflection
+/// within the gimbal range and the combined thrust at or below full, so what is allocated is what gets commanded.
+pub fn allocate_thrust<T>(
+    desired_wrench: &ControlAxis<Dimension3<T>>,
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+) -> ThrusterOutput<T>
+    where T: Float
+{
+    let columns = virtual_thrusters(thruster_mount_points, center_of_mass);
+    let desired = crate::control_axis_to_array(desired_wrench);
+    // stuck on thrusters push whether we like it or not, so the rest of the suite must work around them
+    let mut residual = subtract6(&desired, &uncommanded_wrench(thruster_mount_points, center_of_mass));
+    let mut throttles: Vec<T> = vec![num::zero(); columns.len()];
+    let tolerance: T = num::cast(1e-6).unwrap();
+
+    for _ in 0..MAX_ALLOCATION_ITERATIONS{
+        let mut max_change: T = num::zero();
+        for index in 0..columns.len(){
+            let column = &columns[index].wrench;
+            let norm_squared = dot6(column, column);
+            if norm_squared <= T::epsilon(){continue}
+
+            // starting from zero, every update stays inside the bounds, so the bounds never cross
+            let (lower, upper) = throttle_bounds(&columns, &throttles, index);
+            let new_throttle = num::clamp(throttles[index] + (dot6(column, &residual) / norm_squared), lower, upper.max(lower));
+            let change = new_throttle - throttles[index];
+            for (residual, component) in residual.iter_mut().zip(column.iter()){
+                *residual = *residual - (change * *component);
+            }
+            throttles[index] = new_throttle;
+            max_change = max_change.max(change.abs());
+        }
+        if max_change < tolerance{break}
+    }
+
+    thruster_output_from_virtual_throttles(&columns, &throttles, thruster_mount_points, center_of_mass, &desired)
+}
+
+/// distributes a desired wrench across the thruster suite, minimizing Σ cost·u subject to B·u = w and 0 <= u <= 1.
+/// solved as a linear program, so redundant thrusters are only used when they are the cheaper option.
+/// falls back to allocate_thrust's least squares best effort when the desired wrench can't be achieved exactly.
+/// costs must hold one value per mount point.
+/// a gimbaled thruster's lateral columns are constrained to no more than its axial column, which keeps the
+/// solution inside the gimbal range, and deflection costs the extra throttle it takes(see deflection_cost).
+pub fn allocate_thrust_minimum_cost<T>(
+    desired_wrench: &ControlAxis<Dimension3<T>>,
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+    costs: &[T],
+) -> Result<ThrusterOutput<T>, FcsError<'static>>
+    where T: Float
+{
+    if costs.len() != thruster_mount_points.len(){
+        return Err(FcsError::with_field("Must have one cost per mount point.", "costs".to_string()))
+    }
+    let desired = crate::control_axis_to_array(desired_wrench);
+    let commandable = subtract6(&desired, &uncommanded_wrench(thruster_mount_points, center_of_mass));
+    let columns = virtual_thrusters(thruster_mount_points, center_of_mass);
+    let gimbal_constraints = gimbal_constraints(&columns);
+    // variables are [virtual throttles, throttle slacks, gimbal slacks]
+    let variables = (columns.len() * 2) + gimbal_constraints.len();
+
+    // each wrench row is scaled by its largest coefficient so newtons and newton-meters are comparable
+    let mut a: Vec<Vec<T>> = Vec::new();
+    let mut b: Vec<T> = Vec::new();
+    for (axis, desired) in commandable.iter().enumerate(){
+        let scale = columns.iter().fold(T::zero(), |max, column| max.max(column.wrench[axis].abs()));
+        if scale <= T::epsilon(){
+            if desired.abs() > T::epsilon(){
+                return Ok(allocate_thrust(desired_wrench, thruster_mount_points, center_of_mass))
+            }
+            continue
+        }
+        let mut row = vec![num::zero(); variables];
+        for (value, column) in row.iter_mut().zip(columns.iter()){
+            *value = column.wrench[axis] / scale;
+        }
+        a.push(row);
+        b.push(*desired / scale);
+    }
+    // u + slack = 1 keeps every throttle at or below full. a gimbaled thruster's axial row also counts the throttle
+    // its deflection takes
+    for position in 0..columns.len(){
+        let mut row = vec![num::zero(); variables];
+        row[position] = num::one();
+        row[columns.len() + position] = num::one();
+        if columns[position].kind == VirtualThrusterKind::Axial{
+            for (lateral, column) in columns.iter().enumerate(){
+                if column.mount_point == columns[position].mount_point && column.kind != VirtualThrusterKind::Axial{
+                    row[lateral] = deflection_cost(column.range);
+                }
+            }
+        }
+        a.push(row);
+        b.push(num::one());
+    }
+    // lateral + opposite lateral + slack = axial
+    for (position, (axial, laterals)) in gimbal_constraints.iter().enumerate(){
+        let mut row = vec![num::zero(); variables];
+        row[laterals[0]] = num::one();
+        row[laterals[1]] = num::one();
+        row[*axial] = -T::one();
+        row[(columns.len() * 2) + position] = num::one();
+        a.push(row);
+        b.push(num::zero());
+    }
+    let mut c = vec![num::zero(); variables];
+    for (cost, column) in c.iter_mut().zip(columns.iter()){
+        *cost = match column.kind{
+            VirtualThrusterKind::Axial => costs[column.mount_point],
+            _ => costs[column.mount_point] * deflection_cost(column.range)
+        };
+    }
+
+    Ok(match crate::optimization::solve_linear_program(&a, &b, &c){
+        Some(solution) => thruster_output_from_virtual_throttles(
+            &columns, 
+            &solution[..columns.len()], 
+            thruster_mount_points, 
+            center_of_mass, 
+            &desired
+        ),
+        None => allocate_thrust(desired_wrench, thruster_mount_points, center_of_mass)
+    })
+}
+
+
+
+#[derive(Clone, Copy, PartialEq)]
+enum VirtualThrusterKind{
+    Axial,
+    PitchPositive,
+    PitchNegative,
+    YawPositive,
+    YawNegative,
+}
+
+/// a single column of the allocation problem. a fixed thruster is one axial column. a gimbaled thruster also 
+/// gets a lateral column for deflection either way about each gimbal axis, sized so a lateral throttle equal to the
+/// axial throttle deflects thrust to the edge of the gimbal range
+struct VirtualThruster<T>{
+    mount_point: usize,
+    kind: VirtualThrusterKind,
+    // gimbal range in this column's direction. zero for axial columns
+    range: T,
+    wrench: [T; 6],
+}
+
+// tan goes to infinity at 90 degrees, so wider gimbals are allocated as if they stopped just short of it
+const MAX_ALLOCATED_GIMBAL_RANGE: f64 = std::f64::consts::FRAC_PI_2 * 0.95;
+
+/// lateral force per unit of lateral throttle, as a fraction of max thrust
+fn lateral_ratio<T: Float>(range: T) -> T{
+    range.min(num::cast(MAX_ALLOCATED_GIMBAL_RANGE).unwrap()).tan()
+}
+
+/// throttle taken by a unit of lateral throttle. deflecting by the full range takes 1/cos(range) times the axial
+/// throttle, and this is the straight line between no deflection and full deflection, so it never underestimates
+fn deflection_cost<T: Float>(range: T) -> T{
+    (T::one() / range.min(num::cast(MAX_ALLOCATED_GIMBAL_RANGE).unwrap()).cos()) - T::one()
+}
+
+/// (lower, upper) limits on a single virtual throttle, given the rest. keeps lateral + opposite lateral at or below
+/// axial, and axial + deflection cost at or below full throttle
+fn throttle_bounds<T>(columns: &[VirtualThruster<T>], throttles: &[T], index: usize) -> (T, T)
+    where T: Float
+{
+    let column = &columns[index];
+    let opposite = |kind: VirtualThrusterKind| match kind{
+        VirtualThrusterKind::PitchPositive => VirtualThrusterKind::PitchNegative,
+        VirtualThrusterKind::PitchNegative => VirtualThrusterKind::PitchPositive,
+        VirtualThrusterKind::YawPositive => VirtualThrusterKind::YawNegative,
+        VirtualThrusterKind::YawNegative => VirtualThrusterKind::YawPositive,
+        VirtualThrusterKind::Axial => VirtualThrusterKind::Axial,
+    };
+
+    let mut deflection: T = num::zero();
+    let mut pitch: T = num::zero();
+    let mut yaw: T = num::zero();
+    let mut axial: T = num::zero();
+    let mut opposite_throttle: T = num::zero();
+    for (other, (sibling, throttle)) in columns.iter().zip(throttles.iter()).enumerate(){
+        if other == index || sibling.mount_point != column.mount_point{continue}
+        match sibling.kind{
+            VirtualThrusterKind::Axial => axial = *throttle,
+            VirtualThrusterKind::PitchPositive | VirtualThrusterKind::PitchNegative => pitch = pitch + *throttle,
+            VirtualThrusterKind::YawPositive | VirtualThrusterKind::YawNegative => yaw = yaw + *throttle,
+        }
+        if sibling.kind != VirtualThrusterKind::Axial{
+            deflection = deflection + (deflection_cost(sibling.range) * *throttle);
+        }
+        if column.kind != VirtualThrusterKind::Axial && sibling.kind == opposite(column.kind){
+            opposite_throttle = *throttle;
+        }
+    }
+
+    match column.kind{
+        VirtualThrusterKind::Axial => (pitch.max(yaw), (T::one() - deflection).min(num::one())),
+        _ => {
+            let cost = deflection_cost(column.range);
+            let full = if cost > T::epsilon(){(T::one() - axial - deflection) / cost}else{T::one()};
+            (num::zero(), (axial - opposite_throttle).min(full).min(num::one()))
+        }
+    }
+}
+
+/// (axial column, lateral columns) for each gimbal direction, so lateral columns can be kept at or below the axial
+fn gimbal_constraints<T>(columns: &[VirtualThruster<T>]) -> Vec<(usize, [usize; 2])>{
+    let mut gimbal_constraints = Vec::new();
+    for (index, column) in columns.iter().enumerate(){
+        for (positive, negative) in [
+            (VirtualThrusterKind::PitchPositive, VirtualThrusterKind::PitchNegative), 
+            (VirtualThrusterKind::YawPositive, VirtualThrusterKind::YawNegative)
+        ]{
+            if column.kind != positive{continue}
+            let axial = columns.iter().position(|other| other.mount_point == column.mount_point && other.kind == VirtualThrusterKind::Axial);
+            let opposite = columns.iter().position(|other| other.mount_point == column.mount_point && other.kind == negative);
+            if let (Some(axial), Some(opposite)) = (axial, opposite){
+                gimbal_constraints.push((axial, [index, opposite]));
+            }
+        }
+    }
+    gimbal_constraints
+}
+
+fn virtual_thrusters<T>(thruster_mount_points: &[ThrusterMountPoint<T>], center_of_mass: &Dimension3<T>) -> Vec<VirtualThruster<T>>
+    where T: Float
+{
+    let mut columns = Vec::new();
+    for (index, mount_point) in thruster_mount_points.iter().enumerate(){
+        let thrust = mount_point.controllable_thrust();
+        if thrust <= num::zero(){continue}
+        columns.push(VirtualThruster{
+            mount_point: index, 
+            kind: VirtualThrusterKind::Axial, 
+            range: num::zero(),
+            wrench: max_thruster_wrench(mount_point, center_of_mass)
+        });
+
+        if let Some(gimbal) = mount_point.gimbal(){
+            let (_, pitch_axis, yaw_axis) = mount_point.gimbal_axes();
+            for (kind, axis, range, sign) in [
+                (VirtualThrusterKind::PitchPositive, pitch_axis, gimbal.pitch_range(), T::one()),
+                (VirtualThrusterKind::PitchNegative, pitch_axis, gimbal.pitch_range(), -T::one()),
+                (VirtualThrusterKind::YawPositive, yaw_axis, gimbal.yaw_range(), T::one()),
+                (VirtualThrusterKind::YawNegative, yaw_axis, gimbal.yaw_range(), -T::one()),
+            ]{
+                if range <= num::zero(){continue}
+                let direction = Dimension3::new(axis[0] * sign, axis[1] * sign, axis[2] * sign);
+                columns.push(VirtualThruster{
+                    mount_point: index,
+                    kind,
+                    range,
+                    wrench: thruster_wrench_in_direction(mount_point, center_of_mass, &direction, thrust * lateral_ratio(range)),
+                });
+            }
+        }
+    }
+    columns
+}
+
+/// recombines virtual throttles into a throttle(and gimbal angles) per mount point
+fn thruster_output_from_virtual_throttles<T>(
+    columns: &[VirtualThruster<T>],
+    virtual_throttles: &[T],
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+    desired: &[T; 6]
+) -> ThrusterOutput<T>
+    where T: Float
+{
+    // force along (thrust direction, pitch axis, yaw axis), in newtons
+    let mut components = vec![[T::zero(); 3]; thruster_mount_points.len()];
+    for (column, throttle) in columns.iter().zip(virtual_throttles.iter()){
+        let mount_point = &thruster_mount_points[column.mount_point];
+        let throttle = num::clamp(*throttle, num::zero(), num::one());
+        let thrust = mount_point.controllable_thrust();
+        let component = &mut components[column.mount_point];
+        let lateral = thrust * lateral_ratio(column.range) * throttle;
+        match column.kind{
+            VirtualThrusterKind::Axial => component[0] = component[0] + (thrust * throttle),
+            VirtualThrusterKind::PitchPositive => component[1] = component[1] + lateral,
+            VirtualThrusterKind::PitchNegative => component[1] = component[1] - lateral,
+            VirtualThrusterKind::YawPositive => component[2] = component[2] + lateral,
+            VirtualThrusterKind::YawNegative => component[2] = component[2] - lateral,
+        }
+    }
+
+    let mut throttles = Vec::with_capacity(thruster_mount_points.len());
+    let mut gimbal_angles = Vec::with_capacity(thruster_mount_points.len());
+    for (mount_point, component) in thruster_mount_points.iter().zip(components.iter()){
+        let thrust = mount_point.controllable_thrust();
+        if thrust <= num::zero(){
+            throttles.push(num::zero());
+            gimbal_angles.push(mount_point.gimbal().map(|_| GimbalAngles::new(num::zero(), num::zero())));
+            continue
+        }
+        match mount_point.gimbal(){
+            Some(gimbal) => {
+                let magnitude = dot3(component, component).sqrt();
+                if magnitude <= T::epsilon(){
+                    throttles.push(num::zero());
+                    gimbal_angles.push(Some(GimbalAngles::new(num::zero(), num::zero())));
+                    continue
+                }
+                // direction = (cos p cos y, sin p, cos p sin y) in gimbal axes
+                let angles = gimbal.clamp(GimbalAngles::new(
+                    component[1].atan2(component[0].hypot(component[2])),
+                    component[2].atan2(component[0])
+                ));
+                throttles.push((magnitude / thrust).min(num::one()));
+                gimbal_angles.push(Some(angles));
+            },
+            None => {
+                throttles.push(num::clamp(component[0] / thrust, num::zero(), num::one()));
+                gimbal_angles.push(None);
+            }
+        }
+    }
+
+    thruster_output_from_throttles(throttles, gimbal_angles, thruster_mount_points, center_of_mass, desired)
+}
+
+/// stuck on thrusters are reported at full throttle, since that is what they are producing
+fn thruster_output_from_throttles<T>(
+    mut throttles: Vec<T>, 
+    gimbal_angles: Vec<Option<GimbalAngles<T>>>,
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    center_of_mass: &Dimension3<T>,
+    desired: &[T; 6]
+) -> ThrusterOutput<T>
+    where T: Float
+{
+    let mut achieved = uncommanded_wrench(thruster_mount_points, center_of_mass);
+    for ((mount_point, throttle), angles) in thruster_mount_points.iter().zip(throttles.iter_mut()).zip(gimbal_angles.iter()){
+        if mount_point.uncommanded_thrust() > num::zero(){
+            *throttle = num::one();
+            continue
+        }
+        let wrench = commanded_thruster_wrench(mount_point, center_of_mass, mount_point.controllable_thrust() * *throttle, angles);
+        for (achieved, component) in achieved.iter_mut().zip(wrench.iter()){
+            *achieved = *achieved + *component;
+        }
+    }
+    let residual = subtract6(desired, &achieved);
+
+    ThrusterOutput{
+        throttles,
+        gimbal_angles,
+        achieved_wrench: crate::array_to_control_axis(&achieved),
+        residual: crate::array_to_control_axis(&residual),
+    }
+}
+
+/// wrench [fx, fy, fz, τx, τy, τz] produced by a mount point's thruster at full commanded throttle, given its health
+pub(crate) fn max_thruster_wrench<T>(thruster_mount_point: &ThrusterMountPoint<T>, center_of_mass: &Dimension3<T>) -> [T; 6]
+    where T: Float
+{
+    thruster_wrench(thruster_mount_point, center_of_mass, thruster_mount_point.controllable_thrust())
+}
+
+/// sum wrench of every stuck on thruster in the suite
+fn uncommanded_wrench<T>(thruster_mount_points: &[ThrusterMountPoint<T>], center_of_mass: &Dimension3<T>) -> [T; 6]
+    where T: Float
+{
+    let mut sum = [num::zero(); 6];
+    for mount_point in thruster_mount_points{
+        let wrench = thruster_wrench(mount_point, center_of_mass, mount_point.uncommanded_thrust());
+        for (sum, component) in sum.iter_mut().zip(wrench.iter()){
+            *sum = *sum + *component;
+        }
+    }
+    sum
+}
+
+fn thruster_wrench<T>(thruster_mount_point: &ThrusterMountPoint<T>, center_of_mass: &Dimension3<T>, thrust: T) -> [T; 6]
+    where T: Float
+{
+    let direction = thruster_mount_point.thrust_direction();
+    thruster_wrench_in_direction(
+        thruster_mount_point, 
+        center_of_mass, 
+        &Dimension3::new(direction.x(), direction.y(), direction.z()), 
+        thrust
+    )
+}
+
+/// wrench of a thruster producing the given thrust, deflected by the given gimbal angles if any
+fn commanded_thruster_wrench<T>(
+    thruster_mount_point: &ThrusterMountPoint<T>, 
+    center_of_mass: &Dimension3<T>, 
+    thrust: T,
+    gimbal_angles: &Option<GimbalAngles<T>>
+) -> [T; 6]
+    where T: Float
+{
+    match gimbal_angles{
+        Some(angles) => thruster_wrench_in_direction(
+            thruster_mount_point, 
+            center_of_mass, 
+            &thruster_mount_point.gimbaled_thrust_direction(angles), 
+            thrust
+        ),
+        None => thruster_wrench(thruster_mount_point, center_of_mass, thrust)
+    }
+}
+
+fn thruster_wrench_in_direction<T>(
+    thruster_mount_point: &ThrusterMountPoint<T>, 
+    center_of_mass: &Dimension3<T>, 
+    direction: &Dimension3<T>,
+    thrust: T
+) -> [T; 6]
+    where T: Float
+{
+    let force = Dimension3::new(
+        direction.x() * thrust,
+        direction.y() * thrust,
+        direction.z() * thrust
+    );
+    let torque = torque(&thruster_mount_point.lever_arm(center_of_mass), &force);
+    [force.x(), force.y(), force.z(), torque.x(), torque.y(), torque.z()]
+}
+
+fn subtract6<T: Float>(a: &[T; 6], b: &[T; 6]) -> [T; 6]{
+    let mut difference = *a;
+    for (difference, b) in difference.iter_mut().zip(b.iter()){
+        *difference = *difference - *b;
+    }
+    difference
+}
+
+pub(crate) fn dot6<T: Float>(a: &[T; 6], b: &[T; 6]) -> T{
+    a.iter().zip(b.iter()).fold(num::zero(), |sum, (a, b)| sum + (*a * *b))
+}
+
+/// thrust actually being produced by a thruster suite at a point in time
+pub struct DeliveredThrust<T>{
+    // one value in [0, 1] for each mount point
+    throttles: Vec<T>,
+    // one value for each mount point, Some for gimbaled mount points
+    gimbal_angles: Vec<Option<GimbalAngles<T>>>,
+    wrench: ControlAxis<Dimension3<T>>,
+}
+impl<T> DeliveredThrust<T>{
+    pub fn throttles(&self) -> &[T]{&self.throttles}
+    pub fn gimbal_angles(&self) -> &[Option<GimbalAngles<T>>]{&self.gimbal_angles}
+    pub fn wrench(&self) -> &ControlAxis<Dimension3<T>>{&self.wrench}
+}
+
+
+
+#[derive(Clone)]
+struct ThrusterState<T>{
+    delivered: T,
+    // commands waiting out the dead time, as (age in seconds, command)
+    pending_commands: VecDeque<(T, T)>,
+    command: T,
+    // how long the thruster has been firing, and what it was last commanded while firing
+    on_time: T,
+    last_on_command: T,
+    pulse_phase: Option<T>,
+    pulse_length: T,
+    gimbal_angles: GimbalAngles<T>,
+}
+impl<T: Float> ThrusterState<T>{
+    fn new() -> Self{
+        Self{
+            delivered: num::zero(),
+            pending_commands: VecDeque::new(),
+            command: num::zero(),
+            on_time: num::zero(),
+            last_on_command: num::zero(),
+            pulse_phase: None,
+            pulse_length: num::zero(),
+            gimbal_angles: GimbalAngles::new(num::zero(), num::zero()),
+        }
+    }
+}
+
+
+
+/// simulates each thruster's response to commanded throttles over time.
+/// throttles from calculate_thruster_output are what was asked for, this is what the thrusters produce.
+pub struct ThrusterDynamics<T>{
+    states: Vec<ThrusterState<T>>,
+}
+impl<T> Default for ThrusterDynamics<T>
+    where T: Float
+{
+    fn default() -> Self{Self::new()}
+}
+impl<T> ThrusterDynamics<T>
+    where T: Float
+{
+    pub fn new() -> Self{
+        Self{states: Vec::new()}
+    }
+
+    /// advances every thruster by delta_time toward its commanded throttle, and every gimbal toward its
+    /// commanded angles at no more than its slew rate.
+    /// commanded_throttles and commanded_gimbal_angles hold one value per mount point, as in ThrusterOutput.
+    /// a gimbal with no commanded angles holds its current angles
+    pub fn update(
+        &mut self,
+        thruster_mount_points: &[ThrusterMountPoint<T>],
+        commanded_throttles: &[T],
+        commanded_gimbal_angles: &[Option<GimbalAngles<T>>],
+        center_of_mass: &Dimension3<T>,
+        delta_time: T,
+    ) -> DeliveredThrust<T>{
+        self.states.resize(thruster_mount_points.len(), ThrusterState::new());
+
+        let mut throttles = Vec::with_capacity(thruster_mount_points.len());
+        let mut gimbal_angles = Vec::with_capacity(thruster_mount_points.len());
+        let mut wrench = [num::zero(); 6];
+        for (index, (mount_point, state)) in thruster_mount_points.iter().zip(self.states.iter_mut()).enumerate(){
+            let command = commanded_throttles.get(index).copied().unwrap_or(num::zero());
+            let delivered = match mount_point.attached_thruster(){
+                Some(_) if mount_point.propellant_starved() => num::zero(),
+                Some(thruster) => match mount_point.thruster_health(){
+                    ThrusterHealth::StuckOn => num::one(),
+                    ThrusterHealth::FailedOff => num::zero(),
+                    _ => update_thruster_state(state, thruster.response(), command, delta_time)
+                },
+                None => num::zero()
+            };
+            state.delivered = delivered;
+
+            let angles = mount_point.gimbal().map(|gimbal| {
+                if let Some(Some(command)) = commanded_gimbal_angles.get(index){
+                    let command = gimbal.clamp(*command);
+                    let max_step = gimbal.slew_rate() * delta_time;
+                    state.gimbal_angles = GimbalAngles::new(
+                        state.gimbal_angles.pitch() + num::clamp(command.pitch() - state.gimbal_angles.pitch(), -max_step, max_step),
+                        state.gimbal_angles.yaw() + num::clamp(command.yaw() - state.gimbal_angles.yaw(), -max_step, max_step)
+                    );
+                }
+                state.gimbal_angles
+            });
+
+            let thrust = (mount_point.controllable_thrust() * delivered) + mount_point.uncommanded_thrust();
+            for (sum, component) in wrench.iter_mut().zip(commanded_thruster_wrench(mount_point, center_of_mass, thrust, &angles).iter()){
+                *sum = *sum + *component;
+            }
+            throttles.push(delivered);
+            gimbal_angles.push(angles);
+        }
+
+        DeliveredThrust{
+            throttles,
+            gimbal_angles,
+            wrench: crate::array_to_control_axis(&wrench),
+        }
+    }
+
+    /// clears all thruster state, as if every thruster had been off for a long time
+    pub fn reset(&mut self){
+        self.states.clear();
+    }
+}
+
+fn update_thruster_state<T>(
+    state: &mut ThrusterState<T>, 
+    response: &ThrusterResponse<T>, 
+    command: T, 
+    delta_time: T
+) -> T
+    where T: Float
+{
+    let command = num::clamp(command, num::zero(), num::one());
+
+    // dead time
+    if response.dead_time() > num::zero(){
+        for (age, _) in state.pending_commands.iter_mut(){
+            *age = *age + delta_time;
+        }
+        state.pending_commands.push_back((num::zero(), command));
+        while state.pending_commands.len() > 1 && state.pending_commands[1].0 >= response.dead_time(){
+            state.pending_commands.pop_front();
+        }
+        if let Some((age, pending)) = state.pending_commands.front(){
+            if *age >= response.dead_time(){state.command = *pending}
+        }
+    }
+    else{
+        state.command = command;
+    }
+
+    let target = match response.pulse_width_modulation(){
+        Some(period) => {
+            // latch a pulse length at the start of each period. pulses shorter than the minimum on time
+            // are rounded to either nothing or the minimum impulse bit
+            let phase = match state.pulse_phase{
+                Some(phase) if phase < period => phase,
+                _ => {
+                    let mut pulse_length = state.command * period;
+                    if pulse_length < response.minimum_on_time(){
+                        let two: T = num::cast(2.0).unwrap();
+                        pulse_length = if pulse_length >= response.minimum_on_time() / two{response.minimum_on_time()}else{num::zero()};
+                    }
+                    state.pulse_length = pulse_length;
+                    num::zero()
+                }
+            };
+            state.pulse_phase = Some(phase + delta_time);
+            if phase < state.pulse_length{num::one()}else{num::zero()}
+        },
+        None => {
+            // once ignited, keep firing until the minimum on time has passed
+            if state.command > num::zero(){
+                state.last_on_command = state.command;
+                state.on_time = state.on_time + delta_time;
+                state.command
+            }
+            else if state.on_time > num::zero() && state.on_time < response.minimum_on_time(){
+                state.on_time = state.on_time + delta_time;
+                state.last_on_command
+            }
+            else{
+                state.on_time = num::zero();
+                num::zero()
+            }
+        }
+    };
+
+    let delivered = state.delivered;
+    let delivered = match response.throttle_response(){
+        ThrottleResponse::Instant => target,
+        ThrottleResponse::FirstOrderLag{spool_up, spool_down} => {
+            let time_constant = if target > delivered{spool_up}else{spool_down};
+            if time_constant <= num::zero(){
+                target
+            }else{
+                delivered + ((target - delivered) * (T::one() - (-delta_time / time_constant).exp()))
+            }
+        },
+        ThrottleResponse::RateLimited{spool_up, spool_down} => {
+            if target > delivered{
+                target.min(delivered + (spool_up * delta_time))
+            }else{
+                target.max(delivered - (spool_down * delta_time))
+            }
+        },
+    };
+
+    num::clamp(delivered, num::zero(), num::one())
+}
+
+
+
+pub struct PropellantTank<T>{
+    capacity: T,    // kg
+    level: T,       // kg
+    location: Dimension3<T>,    // same frame as thruster mount locations
+}
+impl<T> PropellantTank<T>
+    where T: Float
+{
+    /// level is clamped to [0, capacity]
+    pub fn new(capacity: T, level: T, location: Dimension3<T>) -> Self{
+        Self{
+            capacity, 
+            level: num::clamp(level, num::zero(), capacity), 
+            location
+        }
+    }
+
+    pub fn capacity(&self) -> T{self.capacity}
+
+    pub fn level(&self) -> T{self.level}
+
+    pub fn set_level(&mut self, level: T){
+        self.level = num::clamp(level, num::zero(), self.capacity);
+    }
+
+    pub fn location(&self) -> &Dimension3<T>{&self.location}
+
+    pub fn is_empty(&self) -> bool{self.level <= num::zero()}
+}
+
+
+
+/// propellant burned over a single update
+pub struct PropellantUsage<T>{
+    // total kg/s
+    mass_flow: T,
+    // kg/s for each mount point
+    mass_flow_per_mount_point: Vec<T>,
+    // mount points that are flagged as starved, or wanted propellant but whose tanks are all empty
+    starved: Vec<usize>,
+}
+impl<T: Copy> PropellantUsage<T>{
+    pub fn mass_flow(&self) -> T{self.mass_flow}
+    pub fn mass_flow_per_mount_point(&self) -> &[T]{&self.mass_flow_per_mount_point}
+    pub fn starved(&self) -> &[usize]{&self.starved}
+}
+
+/// burns propellant for the given throttles(one per mount point, e.g. DeliveredThrust::throttles) over delta_time.
+/// each mount point draws evenly from whichever of its feed tanks still have propellant.
+pub fn consume_propellant<T>(
+    thruster_mount_points: &[ThrusterMountPoint<T>],
+    throttles: &[T],
+    propellant_tanks: &mut [PropellantTank<T>],
+    delta_time: T,
+) -> PropellantUsage<T>
+    where T: Float
+{
+    let mut mass_flow: T = num::zero();
+    let mut mass_flow_per_mount_point = Vec::with_capacity(thruster_mount_points.len());
+    let mut starved = Vec::new();
+
+    for (index, mount_point) in thruster_mount_points.iter().enumerate(){
+        if mount_point.propellant_starved(){
+            starved.push(index);
+            mass_flow_per_mount_point.push(num::zero());
+            continue
+        }
+        let throttle = match mount_point.thruster_health(){
+            ThrusterHealth::StuckOn => num::one(),
+            _ => throttles.get(index).copied().unwrap_or(num::zero())
+        };
+        let thrust = (mount_point.controllable_thrust() * throttle) + mount_point.uncommanded_thrust();
+        let demand = match mount_point.attached_thruster(){
+            Some(thruster) => thruster.mass_flow(throttle, thrust).unwrap_or(num::zero()),
+            None => num::zero()
+        };
+        if demand <= num::zero(){
+            mass_flow_per_mount_point.push(num::zero());
+            continue
+        }
+
+        let feeding: Vec<usize> = mount_point.propellant_feed().iter()
+            .copied()
+            .filter(|tank| propellant_tanks.get(*tank).is_some_and(|tank| !tank.is_empty()))
+            .collect();
+        if feeding.is_empty(){
+            starved.push(index);
+            mass_flow_per_mount_point.push(num::zero());
+            continue
+        }
+
+        let share = (demand * delta_time) / num::cast(feeding.len()).unwrap();
+        let mut burned: T = num::zero();
+        for tank in feeding{
+            let tank = &mut propellant_tanks[tank];
+            let drawn = share.min(tank.level());
+            tank.set_level(tank.level() - drawn);
+            burned = burned + drawn;
+        }
+        let flow = if delta_time > num::zero(){burned / delta_time}else{num::zero()};
+        mass_flow = mass_flow + flow;
+        mass_flow_per_mount_point.push(flow);
+    }
 
+    PropellantUsage{
+        mass_flow,
+        mass_flow_per_mount_point,
+        starved,
+    }
+}
 
+/// every index in propellant_feed must name one of the ship's tank_count tanks.
+/// field is the path of the feed, e.g. "thruster_mount_points[0].propellant_feed"
+pub fn validate_propellant_feed(propellant_feed: &[usize], tank_count: usize, field: &str) -> Result<(), FcsError<'static>>{
+    match propellant_feed.iter().position(|tank| *tank >= tank_count){
+        Some(position) => Err(FcsError::with_field("Must be the index of a propellant tank.", format!("{}[{}]", field, position))),
+        None => Ok(())
+    }
+}
 
+/// flags mount points whose thruster burns propellant, but has no feed tank with any left. a starved mount point
+/// produces no thrust, so drops out of available thrust and allocation, until its tanks are refilled.
+/// returns true if any mount point changed, meaning available thrust needs recalculating
+pub fn update_propellant_starvation<T>(
+    thruster_mount_points: &mut [ThrusterMountPoint<T>],
+    propellant_tanks: &[PropellantTank<T>],
+) -> bool
+    where T: Float
+{
+    let mut changed = false;
+    for mount_point in thruster_mount_points.iter_mut(){
+        let burns_propellant = mount_point.attached_thruster()
+            .is_some_and(|thruster| thruster.propellant_consumption().is_some());
+        let starved = burns_propellant && !mount_point.propellant_feed().iter()
+            .any(|tank| propellant_tanks.get(*tank).is_some_and(|tank| !tank.is_empty()));
+        changed = changed || (starved != mount_point.propellant_starved);
+        mount_point.propellant_starved = starved;
+    }
+    changed
+}
 
-pub fn calculate_thruster_output<T>(
-    desired_acceleration: &ControlAxis<Dimension3<T>>,
-    _thruster_mount_points: &[ThrusterMountPoint<T>],
-    mass: T,
-) -> ControlAxis<Dimension3<T>>
-    where T: Float//Mul<Output = T> + Copy
+/// mass properties of the dry ship plus the propellant currently in its tanks.
+/// tanks are treated as point masses, and moved to the combined center of mass with the parallel axis theorem
+pub fn calculate_wet_mass_properties<T>(
+    dry_mass_properties: &MassProperties<T>,
+    propellant_tanks: &[PropellantTank<T>],
+) -> Result<MassProperties<T>, FcsError<'static>>
+    where T: Float
 {
-    //f = m * a
+    let dry_center = dry_mass_properties.center_of_mass();
+    let mut mass = dry_mass_properties.mass();
+    let mut moment = [
+        dry_center.x() * mass, 
+        dry_center.y() * mass, 
+        dry_center.z() * mass
+    ];
+    for tank in propellant_tanks{
+        mass = mass + tank.level();
+        moment[0] = moment[0] + (tank.location().x() * tank.level());
+        moment[1] = moment[1] + (tank.location().y() * tank.level());
+        moment[2] = moment[2] + (tank.location().z() * tank.level());
+    }
+    let center = [moment[0] / mass, moment[1] / mass, moment[2] / mass];
 
-    ControlAxis::new(
-        Dimension3::new(
-            desired_acceleration.linear().x() * mass, 
-            desired_acceleration.linear().y() * mass, 
-            desired_acceleration.linear().z() * mass
-        ),
-        Dimension3::new(
-            desired_acceleration.rotational().x() * mass, 
-            desired_acceleration.rotational().y() * mass, 
-            desired_acceleration.rotational().z() * mass
-        )
-    )
+    let mut inertia_tensor = *dry_mass_properties.inertia_tensor();
+    add_point_mass_inertia(
+        &mut inertia_tensor, 
+        dry_mass_properties.mass(), 
+        [dry_center.x() - center[0], dry_center.y() - center[1], dry_center.z() - center[2]]
+    );
+    for tank in propellant_tanks{
+        add_point_mass_inertia(
+            &mut inertia_tensor, 
+            tank.level(), 
+            [tank.location().x() - center[0], tank.location().y() - center[1], tank.location().z() - center[2]]
+        );
+    }
 
-    //create collection containing any thruster that can contribute to desired thrust for that axis
+    MassProperties::new(mass, Dimension3::new(center[0], center[1], center[2]), inertia_tensor)
+}
 
-    //request desired thrust from those thrusters divided by number of thrusters that can contribute
+/// parallel axis theorem. m * (|d|²·E - d·dᵀ)
+fn add_point_mass_inertia<T: Float>(inertia_tensor: &mut [[T; 3]; 3], mass: T, offset: [T; 3]){
+    let distance_squared = offset.iter().fold(T::zero(), |sum, value| sum + (*value * *value));
+    for (row, values) in inertia_tensor.iter_mut().enumerate(){
+        for (column, value) in values.iter_mut().enumerate(){
+            let identity = if row == column{distance_squared}else{num::zero()};
+            *value = *value + (mass * (identity - (offset[row] * offset[column])));
+        }
+    }
 }
 
+
+
 // simplified version of pcs that doesn't represent the physical model of the thruster setup.
 // it converts desired accelerations to generalized desired thrust
 pub fn calculate_simplified_thruster_output<T>(
@@ -382,7 +2547,9 @@ fn test_calculate_available_thrust(){
     ];
     
     let available_thrust = calculate_available_thrust(
-        &thruster_suite);
+        &thruster_suite,
+        &Dimension3::default(0.0)
+    );
 
     let expected = ControlAxis::new(
         Dimension3::new(
@@ -425,6 +2592,63 @@ fn test_calculate_available_thrust(){
     assert!((available_thrust.rotational().z().negative() - expected.rotational().z().negative()).abs() < 0.001);
 }
 
+#[test]
+fn test_calculate_available_thrust_with_offset_mount_points(){
+    // pair of +x thrusters mounted above and below center of mass, pair of -x thrusters mounted 
+    // left and right of center of mass. each pair cancels its torque when fired together
+    let thruster_suite: [ThrusterMountPoint<f64>; 4] = [
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::new(0.0, 2.0, 0.0),
+        ),
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::new(0.0, -2.0, 0.0),
+        ),
+        ThrusterMountPoint::new(
+            Some(Thruster::new(500.0, ThrusterSize::Small)),
+            Vector3::new(-1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::new(0.0, 0.0, 3.0),
+        ),
+        ThrusterMountPoint::new(
+            Some(Thruster::new(500.0, ThrusterSize::Small)),
+            Vector3::new(-1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::new(0.0, 0.0, -3.0),
+        ),
+    ];
+
+    let available_thrust = calculate_available_thrust(
+        &thruster_suite,
+        &Dimension3::default(0.0)
+    );
+
+    assert!((available_thrust.linear().x().positive() - 2_000.0).abs() < 0.001);
+    assert!((available_thrust.linear().x().negative() - 1_000.0).abs() < 0.001);
+
+    // r = (0, ±2, 0), F = (1000, 0, 0) -> τz = ∓2000
+    assert!((available_thrust.rotational().z().positive() - 2_000.0).abs() < 0.001);
+    assert!((available_thrust.rotational().z().negative() - 2_000.0).abs() < 0.001);
+    // r = (0, 0, ±3), F = (-500, 0, 0) -> τy = ∓1500
+    assert!((available_thrust.rotational().y().positive() - 1_500.0).abs() < 0.001);
+    assert!((available_thrust.rotational().y().negative() - 1_500.0).abs() < 0.001);
+    assert!((available_thrust.rotational().x().positive() - 0.0).abs() < 0.001);
+    assert!((available_thrust.rotational().x().negative() - 0.0).abs() < 0.001);
+
+    // moving the center of mass changes the lever arms. the -x thrusters now also produce -τz
+    let available_thrust = calculate_available_thrust(
+        &thruster_suite,
+        &Dimension3::new(0.0, 1.0, 0.0)
+    );
+    assert!((available_thrust.rotational().z().positive() - 3_000.0).abs() < 0.001);
+    assert!((available_thrust.rotational().z().negative() - 2_000.0).abs() < 0.001);
+}
+
 #[test]
 fn test_calculate_available_acceleration(){
     let available_thrust = ControlAxis::new(
@@ -440,11 +2664,15 @@ fn test_calculate_available_acceleration(){
         )
     );
 
-    let mass: f32 = 2_000.0;
+    let mass_properties = MassProperties::new(
+        2_000.0, 
+        Dimension3::default(0.0), 
+        [[1_000.0, 0.0, 0.0], [0.0, 1_000.0, 0.0], [0.0, 0.0, 1_000.0]]
+    ).unwrap();
 
-    let available_acceleration = calculate_available_acceleration(
+    let available_acceleration: ControlAxis<Dimension3<AxisContribution<f32>>> = calculate_available_acceleration(
         &available_thrust,
-        mass
+        &mass_properties
     );
 
     let expected = ControlAxis::new(
@@ -475,4 +2703,847 @@ fn test_calculate_available_acceleration(){
     assert!((available_acceleration.rotational().x().negative() - expected.rotational().x().negative()).abs() < 0.001);
     assert!((available_acceleration.rotational().y().negative() - expected.rotational().y().negative()).abs() < 0.001);
     assert!((available_acceleration.rotational().z().negative() - expected.rotational().z().negative()).abs() < 0.001);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_mass_properties_rejects_invalid_input(){
+    assert!(MassProperties::new(0.0, Dimension3::default(0.0), [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]).is_err());
+    assert!(MassProperties::new(1.0, Dimension3::default(0.0), [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0]]).is_err());
+    assert!(MassProperties::new(1.0, Dimension3::default(0.0), [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1e-17]]).is_err());
+    // small, but well conditioned
+    assert!(MassProperties::new(1.0, Dimension3::default(0.0), [[1e-6, 0.0, 0.0], [0.0, 1e-6, 0.0], [0.0, 0.0, 1e-6]]).is_ok());
+    assert!(MassProperties::new(1.0_f32, Dimension3::default(0.0), [[0.001, 0.0, 0.0], [0.0, 0.001, 0.0], [0.0, 0.0, 0.001]]).is_ok());
+}
+
+#[test]
+fn test_angular_acceleration_with_products_of_inertia(){
+    let mass_properties = MassProperties::new(
+        1_000.0, 
+        Dimension3::default(0.0), 
+        [[200.0, -50.0, 0.0], [-50.0, 100.0, 0.0], [0.0, 0.0, 300.0]]
+    ).unwrap();
+
+    let torque = Dimension3::new(100.0, 20.0, -60.0);
+    let angular_acceleration = mass_properties.angular_acceleration(&torque);
+    // I·α should give back the original torque
+    let round_trip = mass_properties.torque(&angular_acceleration);
+
+    assert!((round_trip.x() - torque.x()).abs() < 0.001);
+    assert!((round_trip.y() - torque.y()).abs() < 0.001);
+    assert!((round_trip.z() - torque.z()).abs() < 0.001);
+    assert!((angular_acceleration.z() - (-0.2)).abs() < 0.001);
+    // off-diagonal term couples x torque into y rotation
+    assert!(angular_acceleration.y().abs() > 0.001);
+}
+
+#[test]
+fn test_calculate_available_angular_acceleration(){
+    let available_thrust = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(0.0, 0.0)),
+        Dimension3::new(
+            AxisContribution::new(1_000.0, 500.0),
+            AxisContribution::new(1_000.0, 1_000.0),
+            AxisContribution::new(600.0, 600.0)
+        )
+    );
+
+    let diagonal = MassProperties::new(
+        1_000.0, 
+        Dimension3::default(0.0), 
+        [[200.0, 0.0, 0.0], [0.0, 100.0, 0.0], [0.0, 0.0, 300.0]]
+    ).unwrap();
+    let available_acceleration: ControlAxis<Dimension3<AxisContribution<f64>>> = calculate_available_acceleration(&available_thrust, &diagonal);
+
+    assert!((available_acceleration.rotational().x().positive() - 5.0).abs() < 0.001);
+    assert!((available_acceleration.rotational().x().negative() - 2.5).abs() < 0.001);
+    assert!((available_acceleration.rotational().y().positive() - 10.0).abs() < 0.001);
+    assert!((available_acceleration.rotational().z().positive() - 2.0).abs() < 0.001);
+
+    // pure +x rotation needs τ = (200, -400, 0)·α, so y's negative torque(1000 / 400 = 2.5) is the limit
+    let asymmetric = MassProperties::new(
+        1_000.0, 
+        Dimension3::default(0.0), 
+        [[200.0, -400.0, 0.0], [-400.0, 1_000.0, 0.0], [0.0, 0.0, 300.0]]
+    ).unwrap();
+    let available_acceleration: ControlAxis<Dimension3<AxisContribution<f64>>> = calculate_available_acceleration(&available_thrust, &asymmetric);
+
+    assert!((available_acceleration.rotational().x().positive() - 2.5).abs() < 0.001);
+    // pure -x rotation needs τ = (-200, 400, 0)·α, limited by x's negative torque(500 / 200 = 2.5)
+    assert!((available_acceleration.rotational().x().negative() - 2.5).abs() < 0.001);
+    // pure +y rotation needs τ = (-400, 1000, 0)·α. x's negative torque allows 500 / 400 = 1.25, y's own allows 1000 / 1000 = 1
+    assert!((available_acceleration.rotational().y().positive() - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn test_allocate_thrust_uses_only_pushing_thrusters(){
+    let thruster_suite: Vec<ThrusterMountPoint<f64>> = [
+        Vector3::new(1.0, 0.0, 0.0, 1.0),
+        Vector3::new(-1.0, 0.0, 0.0, 1.0),
+        Vector3::new(0.0, 1.0, 0.0, 1.0),
+        Vector3::new(0.0, -1.0, 0.0, 1.0),
+    ].into_iter().map(|thrust_direction| 
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            thrust_direction,
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        )
+    ).collect();
+
+    let output = allocate_thrust(
+        &ControlAxis::new(Dimension3::new(500.0, -250.0, 0.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0)
+    );
+
+    assert!((output.throttles()[0] - 0.5).abs() < 0.001);
+    assert!((output.throttles()[1] - 0.0).abs() < 0.001);
+    assert!((output.throttles()[2] - 0.0).abs() < 0.001);
+    assert!((output.throttles()[3] - 0.25).abs() < 0.001);
+    assert!((output.achieved_wrench().linear().x() - 500.0).abs() < 0.01);
+    assert!((output.achieved_wrench().linear().y() - (-250.0)).abs() < 0.01);
+    assert!(output.residual().linear().x().abs() < 0.01);
+}
+
+#[test]
+fn test_allocate_thrust_balances_torque_from_offset_thrusters(){
+    let thruster_suite: [ThrusterMountPoint<f64>; 3] = [
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::new(0.0, 2.0, 0.0),
+        ),
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::new(0.0, -2.0, 0.0),
+        ),
+        ThrusterMountPoint::new(
+            None,
+            Vector3::new(-1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        ),
+    ];
+
+    // pure translation needs both thrusters equally so their torques cancel
+    let output = allocate_thrust(
+        &ControlAxis::new(Dimension3::new(1_000.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0)
+    );
+    assert!((output.throttles()[0] - 0.5).abs() < 0.001);
+    assert!((output.throttles()[1] - 0.5).abs() < 0.001);
+    assert!((output.throttles()[2] - 0.0).abs() < 0.001);
+    assert!(output.achieved_wrench().rotational().z().abs() < 0.01);
+
+    // pure torque about z can't be produced without also producing +x force, so there is a residual
+    let output = allocate_thrust(
+        &ControlAxis::new(Dimension3::default(0.0), Dimension3::new(0.0, 0.0, 2_000.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0)
+    );
+    assert!(output.throttles()[1] > output.throttles()[0]);
+    assert!(output.residual().linear().x() < 0.0);
+    assert!(output.residual().rotational().z() > 0.0);
+
+    // requests beyond max thrust saturate at full throttle
+    let output = allocate_thrust(
+        &ControlAxis::new(Dimension3::new(5_000.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0)
+    );
+    assert!((output.throttles()[0] - 1.0).abs() < 0.001);
+    assert!((output.throttles()[1] - 1.0).abs() < 0.001);
+    assert!((output.residual().linear().x() - 3_000.0).abs() < 0.01);
+}
+
+#[test]
+fn test_allocate_thrust_minimum_cost_prefers_cheaper_thrusters(){
+    // one efficient main engine and two redundant, expensive thrusters that together produce the same force
+    let thruster_suite: [ThrusterMountPoint<f64>; 3] = [
+        ThrusterMountPoint::new(
+            Some(Thruster::new(2_000.0, ThrusterSize::Large)),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Large,
+            Dimension3::default(0.0),
+        ),
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::new(0.0, 1.0, 0.0),
+        ),
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::new(0.0, -1.0, 0.0),
+        ),
+    ];
+    let desired_wrench = ControlAxis::new(Dimension3::new(1_500.0, 0.0, 0.0), Dimension3::default(0.0));
+
+    let output = allocate_thrust_minimum_cost(
+        &desired_wrench, 
+        &thruster_suite, 
+        &Dimension3::default(0.0), 
+        &[1.0, 5.0, 5.0]
+    ).unwrap();
+    assert!((output.throttles()[0] - 0.75).abs() < 0.001);
+    assert!((output.throttles()[1] - 0.0).abs() < 0.001);
+    assert!((output.throttles()[2] - 0.0).abs() < 0.001);
+    assert!(output.residual().linear().x().abs() < 0.01);
+
+    // main engine alone can't meet the request, so the redundant pair makes up the rest without adding torque
+    let output = allocate_thrust_minimum_cost(
+        &ControlAxis::new(Dimension3::new(3_000.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0), 
+        &[1.0, 5.0, 5.0]
+    ).unwrap();
+    assert!((output.throttles()[0] - 1.0).abs() < 0.001);
+    assert!((output.throttles()[1] - 0.5).abs() < 0.001);
+    assert!((output.throttles()[2] - 0.5).abs() < 0.001);
+    assert!(output.achieved_wrench().rotational().z().abs() < 0.01);
+}
+
+#[test]
+fn test_allocate_thrust_minimum_cost_falls_back_when_infeasible(){
+    let thruster_suite: [ThrusterMountPoint<f64>; 1] = [
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        ),
+    ];
+
+    let output = allocate_thrust_minimum_cost(
+        &ControlAxis::new(Dimension3::new(5_000.0, 100.0, 0.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0), 
+        &[1.0]
+    ).unwrap();
+    assert!((output.throttles()[0] - 1.0).abs() < 0.001);
+    assert!((output.residual().linear().x() - 4_000.0).abs() < 0.01);
+    assert!((output.residual().linear().y() - 100.0).abs() < 0.01);
+
+    assert!(allocate_thrust_minimum_cost(
+        &ControlAxis::new(Dimension3::new(500.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0), 
+        &[]
+    ).is_err());
+}
+
+#[test]
+fn test_thruster_health_affects_available_thrust(){
+    let mut thruster_suite: Vec<ThrusterMountPoint<f64>> = [
+        Vector3::new(1.0, 0.0, 0.0, 1.0),
+        Vector3::new(1.0, 0.0, 0.0, 1.0),
+        Vector3::new(-1.0, 0.0, 0.0, 1.0),
+    ].into_iter().map(|thrust_direction| 
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            thrust_direction,
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        )
+    ).collect();
+
+    thruster_suite[0].set_thruster_health(ThrusterHealth::Degraded(0.25));
+    thruster_suite[2].set_thruster_health(ThrusterHealth::FailedOff);
+    let available_thrust = calculate_available_thrust(&thruster_suite, &Dimension3::default(0.0));
+    assert!((available_thrust.linear().x().positive() - 1_250.0).abs() < 0.001);
+    assert!((available_thrust.linear().x().negative() - 0.0).abs() < 0.001);
+
+    let report = calculate_authority_report(&thruster_suite, &Dimension3::default(0.0));
+    assert!((report.remaining_authority().linear().x().positive() - 0.625).abs() < 0.001);
+    assert!((report.remaining_authority().linear().x().negative() - 0.0).abs() < 0.001);
+    assert!((report.remaining_authority().linear().y().positive() - 1.0).abs() < 0.001);
+    assert_eq!(report.lost_authority(), &[ThrustDirection::LinXNeg]);
+
+    // replacing a thruster restores it to nominal
+    assert!(thruster_suite[2].change_thruster(Thruster::new(1_000.0, ThrusterSize::Small)).is_ok());
+    assert!(calculate_authority_report(&thruster_suite, &Dimension3::default(0.0)).lost_authority().is_empty());
+}
+
+#[test]
+fn test_allocate_thrust_counters_stuck_on_thruster(){
+    let mut thruster_suite: Vec<ThrusterMountPoint<f64>> = [
+        Vector3::new(1.0, 0.0, 0.0, 1.0),
+        Vector3::new(-1.0, 0.0, 0.0, 1.0),
+    ].into_iter().map(|thrust_direction| 
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            thrust_direction,
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        )
+    ).collect();
+    thruster_suite[0].set_thruster_health(ThrusterHealth::StuckOn);
+    let hold_position = ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0));
+
+    // the healthy thruster spends all of its authority cancelling the stuck one
+    let available_thrust = calculate_available_thrust(&thruster_suite, &Dimension3::default(0.0));
+    assert!((available_thrust.linear().x().positive() - 1_000.0).abs() < 0.001);
+    assert!(available_thrust.linear().x().negative().abs() < 0.001);
+
+    let output = allocate_thrust(&hold_position, &thruster_suite, &Dimension3::default(0.0));
+    assert!((output.throttles()[0] - 1.0).abs() < 0.001);
+    assert!((output.throttles()[1] - 1.0).abs() < 0.001);
+    assert!(output.achieved_wrench().linear().x().abs() < 0.01);
+
+    let output = allocate_thrust_minimum_cost(&hold_position, &thruster_suite, &Dimension3::default(0.0), &[1.0, 1.0]).unwrap();
+    assert!((output.throttles()[0] - 1.0).abs() < 0.001);
+    assert!((output.throttles()[1] - 1.0).abs() < 0.001);
+    assert!(output.residual().linear().x().abs() < 0.01);
+}
+
+#[test]
+fn test_mount_point_normalizes_thrust_direction(){
+    let thruster_suite = [
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(2.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        )
+    ];
+    assert!((thruster_suite[0].thrust_direction().x() - 1.0).abs() < 0.001);
+
+    // available thrust and allocation agree on what the thruster can do
+    let available_thrust = calculate_available_thrust(&thruster_suite, &Dimension3::default(0.0));
+    assert!((available_thrust.linear().x().positive() - 1_000.0).abs() < 0.001);
+    let output = allocate_thrust(
+        &ControlAxis::new(Dimension3::new(2_000.0, 0.0, 0.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0)
+    );
+    assert!((output.throttles()[0] - 1.0).abs() < 0.001);
+    assert!((output.achieved_wrench().linear().x() - available_thrust.linear().x().positive()).abs() < 0.01);
+}
+
+#[cfg(test)]
+fn single_thruster_suite(response: ThrusterResponse<f64>) -> [ThrusterMountPoint<f64>; 1]{
+    let mut thruster = Thruster::new(1_000.0, ThrusterSize::Small);
+    thruster.set_response(response);
+    [
+        ThrusterMountPoint::new(
+            Some(thruster),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        )
+    ]
+}
+
+#[test]
+fn test_thruster_dynamics_first_order_lag(){
+    let thruster_suite = single_thruster_suite(ThrusterResponse::new(
+        ThrottleResponse::FirstOrderLag{spool_up: 1.0, spool_down: 0.5}, 0.0, 0.0, None
+    ));
+    let mut dynamics = ThrusterDynamics::new();
+
+    let mut delivered = dynamics.update(&thruster_suite, &[1.0], &[], &Dimension3::default(0.0), 0.01);
+    for _ in 1..100{
+        delivered = dynamics.update(&thruster_suite, &[1.0], &[], &Dimension3::default(0.0), 0.01);
+    }
+    // one time constant reaches 1 - e^-1 of the command
+    assert!((delivered.throttles()[0] - 0.632).abs() < 0.001);
+    assert!((delivered.wrench().linear().x() - 632.0).abs() < 1.0);
+}
+
+#[test]
+fn test_thruster_dynamics_rate_limit_and_dead_time(){
+    let thruster_suite = single_thruster_suite(ThrusterResponse::new(
+        ThrottleResponse::RateLimited{spool_up: 2.0, spool_down: 4.0}, 0.2, 0.0, None
+    ));
+    let mut dynamics = ThrusterDynamics::new();
+
+    let delivered = dynamics.update(&thruster_suite, &[1.0], &[], &Dimension3::default(0.0), 0.1);
+    assert!((delivered.throttles()[0] - 0.0).abs() < 0.001);
+    let delivered = dynamics.update(&thruster_suite, &[1.0], &[], &Dimension3::default(0.0), 0.1);
+    assert!((delivered.throttles()[0] - 0.0).abs() < 0.001);
+    // command issued 0.2s ago starts taking effect, limited to 2 throttle per second
+    let delivered = dynamics.update(&thruster_suite, &[1.0], &[], &Dimension3::default(0.0), 0.1);
+    assert!((delivered.throttles()[0] - 0.2).abs() < 0.001);
+    let delivered = dynamics.update(&thruster_suite, &[0.0], &[], &Dimension3::default(0.0), 0.1);
+    assert!((delivered.throttles()[0] - 0.4).abs() < 0.001);
+}
+
+#[test]
+fn test_thruster_dynamics_minimum_on_time(){
+    let thruster_suite = single_thruster_suite(ThrusterResponse::new(
+        ThrottleResponse::Instant, 0.0, 0.3, None
+    ));
+    let mut dynamics = ThrusterDynamics::new();
+
+    let delivered = dynamics.update(&thruster_suite, &[0.5], &[], &Dimension3::default(0.0), 0.1);
+    assert!((delivered.throttles()[0] - 0.5).abs() < 0.001);
+    // command dropped, but thruster has only been on for 0.1s
+    let delivered = dynamics.update(&thruster_suite, &[0.0], &[], &Dimension3::default(0.0), 0.1);
+    assert!((delivered.throttles()[0] - 0.5).abs() < 0.001);
+    let delivered = dynamics.update(&thruster_suite, &[0.0], &[], &Dimension3::default(0.0), 0.1);
+    assert!((delivered.throttles()[0] - 0.5).abs() < 0.001);
+    let delivered = dynamics.update(&thruster_suite, &[0.0], &[], &Dimension3::default(0.0), 0.1);
+    assert!((delivered.throttles()[0] - 0.0).abs() < 0.001);
+}
+
+#[test]
+fn test_thruster_dynamics_pulse_width_modulation(){
+    let thruster_suite = single_thruster_suite(ThrusterResponse::new(
+        ThrottleResponse::Instant, 0.0, 0.0, Some(1.0)
+    ));
+    let mut dynamics = ThrusterDynamics::new();
+
+    let mut sum = 0.0;
+    for _ in 0..1_000{
+        let delivered = dynamics.update(&thruster_suite, &[0.3], &[], &Dimension3::default(0.0), 0.01);
+        assert!(delivered.throttles()[0] == 0.0 || delivered.throttles()[0] == 1.0);
+        sum += delivered.throttles()[0];
+    }
+    // average thrust over whole periods matches the commanded throttle
+    assert!((sum / 1_000.0 - 0.3).abs() < 0.011);
+}
+
+#[test]
+fn test_consume_propellant(){
+    let mut thruster = Thruster::new(9_806.65, ThrusterSize::Small);
+    thruster.set_propellant_consumption(Some(PropellantConsumption::SpecificImpulse(100.0)));
+    let mut thruster_suite = [
+        ThrusterMountPoint::new(
+            Some(thruster),
+            Vector3::new(1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        ),
+        ThrusterMountPoint::new(
+            Some(thruster),
+            Vector3::new(-1.0, 0.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        ),
+    ];
+    thruster_suite[0].set_propellant_feed(vec![0, 1]);
+    let mut propellant_tanks = [
+        PropellantTank::new(100.0, 100.0, Dimension3::new(1.0, 0.0, 0.0)),
+        PropellantTank::new(100.0, 100.0, Dimension3::new(-1.0, 0.0, 0.0)),
+    ];
+
+    // 9806.65N / (100s * 9.80665m/s^2) = 10kg/s at full throttle, split across both tanks
+    let usage = consume_propellant(&thruster_suite, &[0.5, 1.0], &mut propellant_tanks, 2.0);
+    assert!((usage.mass_flow() - 5.0).abs() < 0.001);
+    assert!((usage.mass_flow_per_mount_point()[0] - 5.0).abs() < 0.001);
+    assert!((propellant_tanks[0].level() - 95.0).abs() < 0.001);
+    assert!((propellant_tanks[1].level() - 95.0).abs() < 0.001);
+    // second mount point has no feed
+    assert_eq!(usage.starved(), &[1]);
+
+    // once flagged, a starved mount point produces no thrust or authority
+    assert!(update_propellant_starvation(&mut thruster_suite, &propellant_tanks));
+    assert!(!thruster_suite[0].propellant_starved());
+    assert!(thruster_suite[1].propellant_starved());
+    assert!(calculate_available_thrust(&thruster_suite, &Dimension3::default(0.0)).linear().x().negative().abs() < 0.001);
+    let delivered = ThrusterDynamics::new().update(&thruster_suite, &[0.0, 1.0], &[None, None], &Dimension3::default(0.0), 1.0);
+    assert!(delivered.wrench().linear().x().abs() < 0.001);
+    assert!(!update_propellant_starvation(&mut thruster_suite, &propellant_tanks));
+
+    // running the tanks dry starves the first mount point too
+    propellant_tanks[0].set_level(0.0);
+    propellant_tanks[1].set_level(0.0);
+    assert!(update_propellant_starvation(&mut thruster_suite, &propellant_tanks));
+    assert!(thruster_suite[0].propellant_starved());
+    let usage = consume_propellant(&thruster_suite, &[1.0, 1.0], &mut propellant_tanks, 1.0);
+    assert_eq!(usage.starved(), &[0, 1]);
+}
+
+#[test]
+fn test_calculate_wet_mass_properties(){
+    let dry = MassProperties::new(
+        1_000.0, 
+        Dimension3::default(0.0), 
+        [[100.0, 0.0, 0.0], [0.0, 100.0, 0.0], [0.0, 0.0, 100.0]]
+    ).unwrap();
+    let mut propellant_tanks = [PropellantTank::new(1_000.0, 1_000.0, Dimension3::new(2.0, 0.0, 0.0))];
+
+    let wet = calculate_wet_mass_properties(&dry, &propellant_tanks).unwrap();
+    assert!((wet.mass() - 2_000.0).abs() < 0.001);
+    assert!((wet.center_of_mass().x() - 1.0).abs() < 0.001);
+    // each 1000kg mass is 1m from the combined center, adding 1000kg·m² about y and z, none about x
+    assert!((wet.inertia_tensor()[0][0] - 100.0).abs() < 0.001);
+    assert!((wet.inertia_tensor()[1][1] - 2_100.0).abs() < 0.001);
+    assert!((wet.inertia_tensor()[2][2] - 2_100.0).abs() < 0.001);
+
+    // a lighter ship accelerates harder with the same thrust
+    propellant_tanks[0].set_level(0.0);
+    let empty = calculate_wet_mass_properties(&dry, &propellant_tanks).unwrap();
+    let available_thrust = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(1_000.0, 1_000.0)),
+        Dimension3::default(AxisContribution::new(0.0, 0.0))
+    );
+    assert!(
+        calculate_available_acceleration(&available_thrust, &empty).linear().x().positive() >
+        calculate_available_acceleration(&available_thrust, &wet).linear().x().positive()
+    );
+}
+
+#[cfg(test)]
+fn gimbaled_main_engine() -> [ThrusterMountPoint<f64>; 1]{
+    let mut mount_point = ThrusterMountPoint::new(
+        Some(Thruster::new(2_000.0, ThrusterSize::Large)),
+        Vector3::new(1.0, 0.0, 0.0, 1.0),
+        ThrusterSize::Large,
+        Dimension3::new(-2.0, 0.0, 0.0),
+    );
+    mount_point.set_gimbal(Some(Gimbal::new(30.0_f64.to_radians(), 10.0_f64.to_radians(), 0.1)));
+    [mount_point]
+}
+
+#[test]
+fn test_calculate_available_thrust_gimbal_envelope(){
+    let available_thrust = calculate_available_thrust(&gimbaled_main_engine(), &Dimension3::default(0.0));
+
+    assert!((available_thrust.linear().x().positive() - 2_000.0).abs() < 0.001);
+    assert!((available_thrust.linear().x().negative() - 0.0).abs() < 0.001);
+    // sin(30°) of max thrust can be redirected either way along the pitch axis
+    assert!((available_thrust.linear().y().positive() - 1_000.0).abs() < 0.001);
+    assert!((available_thrust.linear().y().negative() - 1_000.0).abs() < 0.001);
+    assert!((available_thrust.linear().z().positive() - (2_000.0 * 10.0_f64.to_radians().sin())).abs() < 0.001);
+    // 2m behind center of mass, lateral thrust produces torque about y and z
+    assert!((available_thrust.rotational().z().positive() - 2_000.0).abs() < 0.001);
+    assert!((available_thrust.rotational().z().negative() - 2_000.0).abs() < 0.001);
+    assert!(available_thrust.rotational().y().positive() > 0.0);
+    assert!((available_thrust.rotational().x().positive() - 0.0).abs() < 0.001);
+}
+
+#[test]
+fn test_allocate_thrust_steers_gimbal(){
+    let thruster_suite = gimbaled_main_engine();
+    // τz = r × f = -2 * fy, so +200 τz needs -100N of lateral force alongside 1000N forward
+    let desired_wrench = ControlAxis::new(Dimension3::new(1_000.0, -100.0, 0.0), Dimension3::new(0.0, 0.0, 200.0));
+
+    for output in [
+        allocate_thrust(&desired_wrench, &thruster_suite, &Dimension3::default(0.0)),
+        allocate_thrust_minimum_cost(&desired_wrench, &thruster_suite, &Dimension3::default(0.0), &[1.0]).unwrap(),
+    ]{
+        let angles = output.gimbal_angles()[0].unwrap();
+        assert!((angles.pitch().abs() - 0.1_f64.atan()).abs() < 0.001);
+        assert!(angles.yaw().abs() < 0.001);
+        assert!((output.throttles()[0] - (1_000.0_f64.hypot(100.0) / 2_000.0)).abs() < 0.001);
+        assert!(output.residual().linear().x().abs() < 0.1);
+        assert!(output.residual().linear().y().abs() < 0.1);
+        assert!(output.residual().rotational().z().abs() < 0.1);
+    }
+
+    // deflection beyond the gimbal range is held at the edge of the range while allocating
+    let output = allocate_thrust(
+        &ControlAxis::new(Dimension3::new(100.0, -1_000.0, 0.0), Dimension3::new(0.0, 0.0, 2_000.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0)
+    );
+    assert!((output.gimbal_angles()[0].unwrap().pitch().abs() - 30.0_f64.to_radians()).abs() < 0.001);
+    let achieved = output.achieved_wrench().linear();
+    assert!(((-achieved.y() / achieved.x()) - 30.0_f64.to_radians().tan()).abs() < 0.001);
+
+    // the whole gimbal range is reachable, not just atan(sin(range))
+    let angle = 29.0_f64.to_radians();
+    let force = 1_500.0;
+    let desired_wrench = ControlAxis::new(
+        Dimension3::new(force * angle.cos(), -force * angle.sin(), 0.0), 
+        Dimension3::new(0.0, 0.0, 2.0 * force * angle.sin())
+    );
+    for output in [
+        allocate_thrust(&desired_wrench, &thruster_suite, &Dimension3::default(0.0)),
+        allocate_thrust_minimum_cost(&desired_wrench, &thruster_suite, &Dimension3::default(0.0), &[1.0]).unwrap(),
+    ]{
+        assert!((output.gimbal_angles()[0].unwrap().pitch().abs() - angle).abs() < 0.001);
+        assert!((output.throttles()[0] - 0.75).abs() < 0.001);
+    }
+}
+
+#[test]
+fn test_allocate_thrust_minimum_cost_charges_for_deflection(){
+    let mut main_engine = ThrusterMountPoint::new(
+        Some(Thruster::new(2_000.0, ThrusterSize::Large)),
+        Vector3::new(1.0, 0.0, 0.0, 1.0),
+        ThrusterSize::Large,
+        Dimension3::default(0.0),
+    );
+    main_engine.set_gimbal(Some(Gimbal::new(30.0_f64.to_radians(), 30.0_f64.to_radians(), 0.1)));
+    let thruster_suite = [
+        main_engine,
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            Vector3::new(0.0, 1.0, 0.0, 1.0),
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        ),
+    ];
+    let desired_wrench = ControlAxis::new(Dimension3::new(1_000.0, 100.0, 0.0), Dimension3::default(0.0));
+
+    // deflecting the main engine costs more throttle than the cheap side thruster
+    let output = allocate_thrust_minimum_cost(&desired_wrench, &thruster_suite, &Dimension3::default(0.0), &[1.0, 0.1]).unwrap();
+    assert!((output.throttles()[1] - 0.1).abs() < 0.001);
+    assert!(output.gimbal_angles()[0].unwrap().pitch().abs() < 0.001);
+
+    // an expensive side thruster makes deflection the cheaper option
+    let output = allocate_thrust_minimum_cost(&desired_wrench, &thruster_suite, &Dimension3::default(0.0), &[1.0, 10.0]).unwrap();
+    assert!(output.throttles()[1].abs() < 0.001);
+    assert!(output.gimbal_angles()[0].unwrap().pitch().abs() > 0.0);
+    assert!(output.residual().linear().y().abs() < 0.1);
+}
+
+#[test]
+fn test_thruster_dynamics_gimbal_slew_rate(){
+    let thruster_suite = gimbaled_main_engine();
+    let mut dynamics = ThrusterDynamics::new();
+
+    let delivered = dynamics.update(
+        &thruster_suite, 
+        &[1.0], 
+        &[Some(GimbalAngles::new(0.5, -0.05))], 
+        &Dimension3::default(0.0), 
+        1.0
+    );
+    let angles = delivered.gimbal_angles()[0].unwrap();
+    assert!((angles.pitch() - 0.1).abs() < 0.001);
+    assert!((angles.yaw() - (-0.05)).abs() < 0.001);
+
+    // no new command holds position
+    let delivered = dynamics.update(&thruster_suite, &[1.0], &[None], &Dimension3::default(0.0), 1.0);
+    assert!((delivered.gimbal_angles()[0].unwrap().pitch() - 0.1).abs() < 0.001);
+    // delivered wrench follows the actual, not commanded, gimbal angle
+    assert!((delivered.wrench().linear().x() - (2_000.0 * 0.1_f64.cos() * 0.05_f64.cos())).abs() < 0.01);
+}
+
+#[test]
+fn test_calculate_blended_output_dumps_saturated_reaction_wheel(){
+    use crate::momentum_devices::ReactionWheel;
+    // two pairs of thrusters producing pure torque about z, one pair each way
+    let thruster_suite: Vec<ThrusterMountPoint<f64>> = [
+        (Vector3::new(0.0, -1.0, 0.0, 1.0), Dimension3::new(1.0, 0.0, 0.0)),
+        (Vector3::new(0.0, 1.0, 0.0, 1.0), Dimension3::new(-1.0, 0.0, 0.0)),
+        (Vector3::new(0.0, 1.0, 0.0, 1.0), Dimension3::new(1.0, 0.0, 0.0)),
+        (Vector3::new(0.0, -1.0, 0.0, 1.0), Dimension3::new(-1.0, 0.0, 0.0)),
+    ].into_iter().map(|(thrust_direction, mount_location)| 
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            thrust_direction,
+            ThrusterSize::Small,
+            mount_location,
+        )
+    ).collect();
+    let mass_properties = MassProperties::new(
+        1_000.0, 
+        Dimension3::default(0.0), 
+        [[100.0, 0.0, 0.0], [0.0, 100.0, 0.0], [0.0, 0.0, 100.0]]
+    ).unwrap();
+    let mut wheel = ReactionWheel::new(Dimension3::new(0.0, 0.0, 1.0), 100.0, 1_000.0);
+
+    // unsaturated wheel takes all of a small rotation, thrusters stay off
+    let mut devices = vec![MomentumDevice::ReactionWheel(wheel)];
+    let output = calculate_blended_output(
+        &ControlAxis::new(Dimension3::default(0.0), Dimension3::new(0.0, 0.0, 0.5)), 
+        &thruster_suite, 
+        &devices, 
+        &mass_properties, 
+        &AllocationMethod::LeastSquares, 
+        0.8, 
+        1.0
+    ).unwrap();
+    assert!((output.momentum_device_torques()[0] - 50.0).abs() < 0.001);
+    assert!(output.thruster_output().throttles().iter().all(|throttle| throttle.abs() < 0.001));
+
+    // beyond what the wheel can do, thrusters make up the difference
+    let output = calculate_blended_output(
+        &ControlAxis::new(Dimension3::default(0.0), Dimension3::new(0.0, 0.0, 3.0)), 
+        &thruster_suite, 
+        &devices, 
+        &mass_properties, 
+        &AllocationMethod::LeastSquares, 
+        0.8, 
+        1.0
+    ).unwrap();
+    assert!((output.momentum_device_torques()[0] - 100.0).abs() < 0.001);
+    assert!((output.thruster_output().achieved_wrench().rotational().z() - 200.0).abs() < 0.01);
+
+    // nearly saturated wheel dumps momentum while holding attitude, thrusters counter the dump torque
+    wheel = ReactionWheel::new(Dimension3::new(0.0, 0.0, 1.0), 100.0, 1_000.0);
+    wheel.set_momentum(900.0);
+    devices[0] = MomentumDevice::ReactionWheel(wheel);
+    let output = calculate_blended_output(
+        &ControlAxis::new(Dimension3::default(0.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &devices, 
+        &mass_properties, 
+        &AllocationMethod::LeastSquares, 
+        0.8, 
+        1.0
+    ).unwrap();
+    assert!((output.momentum_device_torques()[0] - 50.0).abs() < 0.001);
+    assert!((output.thruster_output().achieved_wrench().rotational().z() - (-50.0)).abs() < 0.01);
+    assert!(output.thruster_output().residual().rotational().z().abs() < 0.01);
+    devices[0].apply_torque(output.momentum_device_torques()[0], 1.0);
+    assert!((devices[0].saturation() - 0.85).abs() < 0.001);
+}
+
+#[test]
+fn test_calculate_controllability_report(){
+    let mass_properties = MassProperties::new(
+        100.0, 
+        Dimension3::default(0.0), 
+        [[10.0, 0.0, 0.0], [0.0, 10.0, 0.0], [0.0, 0.0, 10.0]]
+    ).unwrap();
+    // +x thruster mounted off center, so it also yaws the ship
+    let mut thruster_suite: Vec<ThrusterMountPoint<f64>> = [
+        (Vector3::new(1.0, 0.0, 0.0, 1.0), Dimension3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(-1.0, 0.0, 0.0, 1.0), Dimension3::default(0.0)),
+        (Vector3::new(0.0, 1.0, 0.0, 1.0), Dimension3::default(0.0)),
+        (Vector3::new(0.0, -1.0, 0.0, 1.0), Dimension3::default(0.0)),
+    ].into_iter().map(|(thrust_direction, mount_location)| 
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            thrust_direction,
+            ThrusterSize::Small,
+            mount_location,
+        )
+    ).collect();
+    thruster_suite.push(ThrusterMountPoint::new(None, Vector3::new(0.0, 0.0, 1.0, 1.0), ThrusterSize::Small, Dimension3::default(0.0)));
+
+    let report = calculate_controllability_report(&thruster_suite, &mass_properties);
+    assert!((report.authority().linear().x().positive() - 0.0).abs() < 0.001);
+    assert!((report.authority().linear().x().negative() - 10.0).abs() < 0.001);
+    assert!((report.authority().linear().y().positive() - 10.0).abs() < 0.001);
+    assert_eq!(report.controllability().linear().x(), AxisControllability::NegativeOnly);
+    assert_eq!(report.controllability().linear().y(), AxisControllability::Controllable);
+    assert_eq!(report.controllability().linear().z(), AxisControllability::Uncontrollable);
+    // firing both x thrusters cancels force, leaving pure -z torque
+    assert_eq!(report.controllability().rotational().z(), AxisControllability::NegativeOnly);
+    assert!((report.authority().rotational().z().negative() - 100.0).abs() < 0.001);
+    assert!(report.uncontrollable_directions().contains(&ThrustDirection::LinXPos));
+    assert!(!report.uncontrollable_directions().contains(&ThrustDirection::LinYNeg));
+    assert!(!report.is_fully_controllable());
+    assert_eq!(report.cross_coupling().len(), 1);
+    assert_eq!(report.cross_coupling()[0].mount_point(), 0);
+    assert!((report.cross_coupling()[0].torque_per_thrust().z() - (-1.0)).abs() < 0.001);
+    assert_eq!(report.empty_mount_points(), &[4]);
+}
+
+#[test]
+fn test_calculate_attainable_wrench_set(){
+    // one thruster each way on x and y, only +z. force set is a 2 x 2 x 1 box sitting on z = 0
+    let thruster_suite: Vec<ThrusterMountPoint<f64>> = [
+        Vector3::new(1.0, 0.0, 0.0, 1.0),
+        Vector3::new(-1.0, 0.0, 0.0, 1.0),
+        Vector3::new(0.0, 1.0, 0.0, 1.0),
+        Vector3::new(0.0, -1.0, 0.0, 1.0),
+        Vector3::new(0.0, 0.0, 1.0, 1.0),
+    ].into_iter().map(|thrust_direction| 
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            thrust_direction,
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        )
+    ).collect();
+
+    let force_set = calculate_attainable_wrench_set(&thruster_suite, &Dimension3::default(0.0), WrenchSubspace::Force);
+    assert_eq!(force_set.vertices().len(), 8);
+    assert_eq!(force_set.faces().len(), 6);
+    assert!(force_set.faces().iter().all(|face| face.len() == 4));
+    assert!(force_set.contains(&Dimension3::new(500.0, -1_000.0, 200.0)));
+    assert!(!force_set.contains(&Dimension3::new(0.0, 0.0, -1.0)));
+    assert!((force_set.attainable_fraction(&Dimension3::new(2_000.0, 500.0, 0.0)) - 0.5).abs() < 0.001);
+    assert!((force_set.attainable_fraction(&Dimension3::new(0.0, 0.0, -1.0)) - 0.0).abs() < 0.001);
+
+    // thrusters all act through the center of mass, so no torque at all
+    let torque_set = calculate_attainable_wrench_set(&thruster_suite, &Dimension3::default(0.0), WrenchSubspace::Torque);
+    assert_eq!(torque_set.vertices().len(), 1);
+    assert!(torque_set.faces().is_empty());
+    assert!(torque_set.contains(&Dimension3::default(0.0)));
+    assert!(!torque_set.contains(&Dimension3::new(0.0, 0.0, 1.0)));
+
+    assert!((calculate_attainable_fraction(
+        &ControlAxis::new(Dimension3::new(250.0, 0.0, 250.0), Dimension3::default(0.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0)
+    ) - 4.0).abs() < 0.001);
+    assert!((calculate_attainable_fraction(
+        &ControlAxis::new(Dimension3::default(0.0), Dimension3::new(0.0, 0.0, 1.0)), 
+        &thruster_suite, 
+        &Dimension3::default(0.0)
+    ) - 0.0).abs() < 0.001);
+}
+
+#[test]
+fn test_calculate_attainable_wrench_set_planar_layout(){
+    // no z thruster, so the force set is a 2 x 2 square in the x/y plane
+    let thruster_suite: Vec<ThrusterMountPoint<f64>> = [
+        Vector3::new(1.0, 0.0, 0.0, 1.0),
+        Vector3::new(-1.0, 0.0, 0.0, 1.0),
+        Vector3::new(0.0, 1.0, 0.0, 1.0),
+        Vector3::new(0.0, -1.0, 0.0, 1.0),
+    ].into_iter().map(|thrust_direction| 
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            thrust_direction,
+            ThrusterSize::Small,
+            Dimension3::default(0.0),
+        )
+    ).collect();
+
+    let force_set = calculate_attainable_wrench_set(&thruster_suite, &Dimension3::default(0.0), WrenchSubspace::Force);
+    assert!(force_set.faces().is_empty());
+    assert_eq!(force_set.vertices().len(), 4);
+    assert!(!force_set.contains(&Dimension3::new(1e9, 0.0, 0.0)));
+    assert!(!force_set.contains(&Dimension3::new(0.0, 0.0, 1.0)));
+    assert!(force_set.contains(&Dimension3::new(500.0, -1_000.0, 0.0)));
+    assert!((force_set.attainable_fraction(&Dimension3::new(2_000.0, 500.0, 0.0)) - 0.5).abs() < 0.001);
+    assert!((force_set.attainable_fraction(&Dimension3::new(2_000.0, 0.0, 1.0)) - 0.0).abs() < 0.001);
+    assert!(force_set.attainable_fraction(&Dimension3::default(0.0)).is_infinite());
+}
+
+#[test]
+fn test_saturate_to_attainable(){
+    use crate::saturation::SaturationStrategy;
+    // thrusters offset along x, so each one pushes in y and yaws the ship at the same time
+    let thruster_suite: Vec<ThrusterMountPoint<f64>> = [
+        (Vector3::new(0.0, 1.0, 0.0, 1.0), Dimension3::new(1.0, 0.0, 0.0)),
+        (Vector3::new(0.0, 1.0, 0.0, 1.0), Dimension3::new(-1.0, 0.0, 0.0)),
+        (Vector3::new(0.0, -1.0, 0.0, 1.0), Dimension3::new(1.0, 0.0, 0.0)),
+        (Vector3::new(0.0, -1.0, 0.0, 1.0), Dimension3::new(-1.0, 0.0, 0.0)),
+    ].into_iter().map(|(thrust_direction, mount_location)| 
+        ThrusterMountPoint::new(
+            Some(Thruster::new(1_000.0, ThrusterSize::Small)),
+            thrust_direction,
+            ThrusterSize::Small,
+            mount_location,
+        )
+    ).collect();
+    let mass_properties = MassProperties::new(
+        1_000.0, 
+        Dimension3::default(0.0), 
+        [[1_000.0, 0.0, 0.0], [0.0, 1_000.0, 0.0], [0.0, 0.0, 1_000.0]]
+    ).unwrap();
+    // 2m/s^2 in +y uses both +y thrusters fully, leaving nothing to yaw with
+    let desired_acceleration = ControlAxis::new(Dimension3::new(0.0, 2.0, 0.0), Dimension3::new(0.0, 0.0, 1.0));
+
+    let uniform = saturate_to_attainable(&desired_acceleration, &thruster_suite, &[], &mass_properties, SaturationStrategy::UniformScaling);
+    // y·1000 + z·1000 <= 2000 for thrusters 1 and 2 combined, so 2s + s <= 2
+    assert!((uniform.linear().y() - (4.0 / 3.0)).abs() < 0.001);
+    assert!((uniform.rotational().z() - (2.0 / 3.0)).abs() < 0.001);
+
+    let rotation_priority = saturate_to_attainable(&desired_acceleration, &thruster_suite, &[], &mass_properties, SaturationStrategy::RotationPriority);
+    assert!((rotation_priority.rotational().z() - 1.0).abs() < 0.001);
+    assert!((rotation_priority.linear().y() - 1.0).abs() < 0.001);
+
+    let daisy_chain = saturate_to_attainable(&desired_acceleration, &thruster_suite, &[], &mass_properties, SaturationStrategy::DaisyChain);
+    assert!((daisy_chain.rotational().z() - 1.0).abs() < 0.001);
+    assert!((daisy_chain.linear().y() - 1.0).abs() < 0.001);
+
+    let per_axis = saturate_to_attainable(&desired_acceleration, &thruster_suite, &[], &mass_properties, SaturationStrategy::PerAxis);
+    assert!((per_axis.linear().y() - 2.0).abs() < 0.001);
+}
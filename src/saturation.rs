@@ -0,0 +1,113 @@
+//! # Saturation
+//! what to do with a 6 dof acceleration command that exceeds what the ship is allowed, or able, to do.
+//! clamping each axis on its own changes the direction of the command as soon as one axis saturates,
+//! so the ship drifts off of the intended path. the other strategies keep the direction, at the cost of
+//! giving up some acceleration on the axes that weren't saturated.
+use game_utils::{
+    control_axis::{ControlAxis, AxisContribution},
+    dimension3::Dimension3,
+};
+use num::Float;
+
+
+
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SaturationStrategy{
+    /// each axis is clamped independently. can change the direction of the command
+    PerAxis,
+    /// the whole 6 dof command is scaled down together, keeping its direction
+    UniformScaling,
+    /// rotation is scaled down to fit first, translation is then scaled down to fit in whatever authority is left
+    RotationPriority,
+    /// axes get authority one at a time, rotational x, y, z then linear x, y, z. each axis gets as much of its command
+    /// as fits alongside the axes before it
+    DaisyChain,
+}
+
+
+
+/// limits desired acceleration to per axis max acceleration(g-force safety limits, available acceleration, etc.)
+/// using the given strategy. limits on separate axes are independent of each other, so RotationPriority scales rotation
+/// and translation separately, and DaisyChain is the same as PerAxis. see propulsion_control::saturate_to_attainable
+/// for limits where axes compete for the same thrusters
+pub fn saturate<T>(
+    desired_acceleration: &ControlAxis<Dimension3<T>>,
+    max_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    strategy: SaturationStrategy,
+) -> ControlAxis<Dimension3<T>>
+    where T: Float
+{
+    match strategy{
+        SaturationStrategy::PerAxis | SaturationStrategy::DaisyChain => {
+            crate::g_force_safety::process(desired_acceleration, max_acceleration)
+        },
+        SaturationStrategy::UniformScaling => {
+            let scale = scale_to_fit(desired_acceleration.linear(), max_acceleration.linear())
+                .min(scale_to_fit(desired_acceleration.rotational(), max_acceleration.rotational()));
+            crate::map_control_axes(desired_acceleration, desired_acceleration, |acceleration, _| acceleration * scale)
+        },
+        SaturationStrategy::RotationPriority => {
+            let linear_scale = scale_to_fit(desired_acceleration.linear(), max_acceleration.linear());
+            let rotational_scale = scale_to_fit(desired_acceleration.rotational(), max_acceleration.rotational());
+            ControlAxis::new(
+                Dimension3::new(
+                    desired_acceleration.linear().x() * linear_scale,
+                    desired_acceleration.linear().y() * linear_scale,
+                    desired_acceleration.linear().z() * linear_scale
+                ),
+                Dimension3::new(
+                    desired_acceleration.rotational().x() * rotational_scale,
+                    desired_acceleration.rotational().y() * rotational_scale,
+                    desired_acceleration.rotational().z() * rotational_scale
+                )
+            )
+        },
+    }
+}
+
+/// largest scale(up to 1) that brings every axis of the command within its limit
+fn scale_to_fit<T>(desired_acceleration: &Dimension3<T>, max_acceleration: &Dimension3<AxisContribution<T>>) -> T
+    where T: Float
+{
+    let mut scale = T::one();
+    for (desired, max) in [
+        (desired_acceleration.x(), max_acceleration.x()),
+        (desired_acceleration.y(), max_acceleration.y()),
+        (desired_acceleration.z(), max_acceleration.z()),
+    ]{
+        let limit = if desired > num::zero(){max.positive()}else{max.negative()};
+        if desired.abs() > limit{
+            scale = scale.min(limit.max(num::zero()) / desired.abs());
+        }
+    }
+    scale
+}
+
+
+
+
+
+#[test]
+fn test_saturate_uniform_scaling_keeps_direction(){
+    let max_acceleration = ControlAxis::new(
+        Dimension3::default(AxisContribution::new(10.0, 5.0)),
+        Dimension3::default(AxisContribution::new(1.0, 1.0))
+    );
+    let desired_acceleration = ControlAxis::new(Dimension3::new(20.0, -5.0, 0.0), Dimension3::new(0.0, 0.0, 0.5));
+
+    let per_axis = saturate(&desired_acceleration, &max_acceleration, SaturationStrategy::PerAxis);
+    assert!((per_axis.linear().x() - 10.0).abs() < 0.001);
+    assert!((per_axis.linear().y() - (-5.0)).abs() < 0.001);
+
+    let uniform = saturate(&desired_acceleration, &max_acceleration, SaturationStrategy::UniformScaling);
+    assert!((uniform.linear().x() - 10.0).abs() < 0.001);
+    assert!((uniform.linear().y() - (-2.5)).abs() < 0.001);
+    assert!((uniform.rotational().z() - 0.25).abs() < 0.001);
+
+    // rotation was within limits, so it is left alone
+    let rotation_priority = saturate(&desired_acceleration, &max_acceleration, SaturationStrategy::RotationPriority);
+    assert!((rotation_priority.linear().y() - (-2.5)).abs() < 0.001);
+    assert!((rotation_priority.rotational().z() - 0.5).abs() < 0.001);
+}
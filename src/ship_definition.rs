@@ -0,0 +1,424 @@
+//! # Ship definition
+//! flight control configuration in a form that can be authored in, and saved to, a data file.
+//! format agnostic, any serde format(TOML, RON, JSON, etc.) works. for example, with the toml crate:
+//! `let ship: ShipDefinition<f64> = toml::from_str(&file_contents)?;`
+//!
+//! a deserialized definition is only guaranteed to be well formed, call validate(or any of the conversions,
+//! which validate first) before using it.
+use game_utils::{
+    control_axis::{ControlAxis, AxisContribution},
+    dimension3::{Dimension3, Vector3},
+};
+use pid_controller::PID;
+use num::Float;
+use serde::{Serialize, Deserialize};
+use crate::{
+    FcsError,
+    feedback_controller::PidGains,
+    propulsion_control::{
+        Thruster,
+        ThrusterSize,
+        ThrusterMountPoint,
+        ThrusterResponse,
+        ThrottleResponse,
+        PropellantConsumption,
+        Gimbal,
+    },
+};
+
+
+
+
+
+#[derive(Serialize, Deserialize)]
+pub struct ShipDefinition<T>{
+    thruster_mount_points: Vec<MountPointDefinition<T>>,
+    pid_gains: AxesDefinition<PidGains<T>>,
+    max_velocity: AxesDefinition<T>,
+    gsafety_max_acceleration: AxesDefinition<LimitDefinition<T>>,
+}
+impl<T> ShipDefinition<T>
+    where T: Float
+{
+    /// captures the current configuration so it can be saved. PID doesn't expose its gains, so they are passed in separately
+    pub fn new(
+        thruster_mount_points: &[ThrusterMountPoint<T>],
+        pid_gains: &ControlAxis<Dimension3<PidGains<T>>>,
+        max_velocity: &ControlAxis<Dimension3<T>>,
+        gsafety_max_acceleration: &ControlAxis<Dimension3<AxisContribution<T>>>,
+    ) -> Self{
+        Self{
+            thruster_mount_points: thruster_mount_points.iter()
+                .map(|mount_point| {
+                    let thrust_direction = mount_point.thrust_direction();
+                    let mount_location = mount_point.mount_location();
+                    MountPointDefinition{
+                        thruster: mount_point.attached_thruster().map(|thruster| ThrusterDefinition{
+                            max_thrust: thruster.max_thrust(),
+                            size: thruster.size(),
+                            response: *thruster.response(),
+                            propellant_consumption: thruster.propellant_consumption(),
+                        }),
+                        max_thruster_size: mount_point.max_thruster_size(),
+                        thrust_direction: [thrust_direction.x(), thrust_direction.y(), thrust_direction.z()],
+                        mount_location: [mount_location.x(), mount_location.y(), mount_location.z()],
+                        gimbal: mount_point.gimbal().map(|gimbal| GimbalDefinition{
+                            pitch_range: gimbal.pitch_range(),
+                            yaw_range: gimbal.yaw_range(),
+                            slew_rate: gimbal.slew_rate(),
+                        }),
+                        propellant_feed: mount_point.propellant_feed().to_vec(),
+                    }
+                })
+                .collect(),
+            pid_gains: AxesDefinition::from_control_axis(pid_gains, |gains| *gains),
+            max_velocity: AxesDefinition::from_control_axis(max_velocity, |max_velocity| *max_velocity),
+            gsafety_max_acceleration: AxesDefinition::from_control_axis(
+                gsafety_max_acceleration,
+                |limit| LimitDefinition{positive: limit.positive(), negative: limit.negative()}
+            ),
+        }
+    }
+
+    /// checks every value is in range, reporting the first one that isn't
+    pub fn validate(&self) -> Result<(), FcsError<'static>>{
+        for (index, mount_point) in self.thruster_mount_points.iter().enumerate(){
+            mount_point.validate(&format!("thruster_mount_points[{}]", index))?;
+        }
+        for (field, gains) in self.pid_gains.iter(){
+            let field = format!("pid_gains.{}", field);
+            require_non_negative(gains.kp(), &field, "kp")?;
+            require_non_negative(gains.ki(), &field, "ki")?;
+            require_non_negative(gains.kd(), &field, "kd")?;
+        }
+        for (field, max_velocity) in self.max_velocity.iter(){
+            if !max_velocity.is_finite() || *max_velocity < num::zero(){
+                return Err(FcsError::with_field("Must be a finite, non negative value.", format!("max_velocity.{}", field)))
+            }
+        }
+        for (field, limit) in self.gsafety_max_acceleration.iter(){
+            let field = format!("gsafety_max_acceleration.{}", field);
+            require_non_negative(limit.positive, &field, "positive")?;
+            require_non_negative(limit.negative, &field, "negative")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn thruster_mount_points(&self) -> Result<Vec<ThrusterMountPoint<T>>, FcsError<'static>>{
+        self.validate()?;
+        Ok(self.thruster_mount_points.iter().map(|mount_point| mount_point.to_mount_point()).collect())
+    }
+
+    /// PID controllers for each axis, starting with no accumulated error
+    pub fn pid6dof(&self) -> Result<ControlAxis<Dimension3<PID<T>>>, FcsError<'static>>{
+        self.validate()?;
+        Ok(self.pid_gains.to_control_axis(|gains| PID::new(gains.kp(), gains.ki(), gains.kd())))
+    }
+
+    pub fn max_velocity(&self) -> Result<ControlAxis<Dimension3<T>>, FcsError<'static>>{
+        self.validate()?;
+        Ok(self.max_velocity.to_control_axis(|max_velocity| *max_velocity))
+    }
+
+    pub fn gsafety_max_acceleration(&self) -> Result<ControlAxis<Dimension3<AxisContribution<T>>>, FcsError<'static>>{
+        self.validate()?;
+        Ok(self.gsafety_max_acceleration.to_control_axis(|limit| AxisContribution::new(limit.positive, limit.negative)))
+    }
+}
+
+
+
+#[derive(Serialize, Deserialize)]
+struct MountPointDefinition<T>{
+    // omitted for an empty mount point
+    #[serde(default = "Option::default")]
+    thruster: Option<ThrusterDefinition<T>>,
+    max_thruster_size: ThrusterSize,
+    // normalized when converted to a mount point
+    thrust_direction: [T; 3],
+    mount_location: [T; 3],
+    #[serde(default = "Option::default")]
+    gimbal: Option<GimbalDefinition<T>>,
+    // tanks aren't part of the definition, so these are checked against them by FlightControlSystem::set_propellant_tanks
+    #[serde(default)]
+    propellant_feed: Vec<usize>,
+}
+impl<T> MountPointDefinition<T>
+    where T: Float
+{
+    fn validate(&self, field: &str) -> Result<(), FcsError<'static>>{
+        if self.thrust_direction.iter().any(|component| !component.is_finite())
+        || self.thrust_direction.iter().all(|component| *component == num::zero()){
+            return Err(FcsError::with_field("Must be a finite, non zero vector.", format!("{}.thrust_direction", field)))
+        }
+        if self.mount_location.iter().any(|component| !component.is_finite()){
+            return Err(FcsError::with_field("Must be a finite vector.", format!("{}.mount_location", field)))
+        }
+        if let Some(gimbal) = &self.gimbal{
+            let field = format!("{}.gimbal", field);
+            require_non_negative(gimbal.pitch_range, &field, "pitch_range")?;
+            require_non_negative(gimbal.yaw_range, &field, "yaw_range")?;
+            require_non_negative(gimbal.slew_rate, &field, "slew_rate")?;
+        }
+        if let Some(thruster) = &self.thruster{
+            let field = format!("{}.thruster", field);
+            if thruster.size > self.max_thruster_size{
+                return Err(FcsError::with_field("Thruster is too large for mount point.", format!("{}.size", field)))
+            }
+            require_non_negative(thruster.max_thrust, &field, "max_thrust")?;
+
+            let response = thruster.response;
+            let response_field = format!("{}.response", field);
+            match response.throttle_response(){
+                ThrottleResponse::Instant => {},
+                ThrottleResponse::FirstOrderLag{spool_up, spool_down} | ThrottleResponse::RateLimited{spool_up, spool_down} => {
+                    let field = format!("{}.throttle_response", response_field);
+                    require_non_negative(spool_up, &field, "spool_up")?;
+                    require_non_negative(spool_down, &field, "spool_down")?;
+                }
+            }
+            require_non_negative(response.dead_time(), &response_field, "dead_time")?;
+            require_non_negative(response.minimum_on_time(), &response_field, "minimum_on_time")?;
+            if let Some(period) = response.pulse_width_modulation(){
+                if !period.is_finite() || period <= num::zero(){
+                    return Err(FcsError::with_field(
+                        "Must be a finite value greater than zero.",
+                        format!("{}.pulse_width_modulation", response_field)
+                    ))
+                }
+            }
+
+            let consumption_field = format!("{}.propellant_consumption", field);
+            match thruster.propellant_consumption{
+                Some(PropellantConsumption::SpecificImpulse(specific_impulse)) 
+                    if !specific_impulse.is_finite() || specific_impulse <= num::zero() => {
+                    return Err(FcsError::with_field("Must be a finite value greater than zero.", format!("{}.SpecificImpulse", consumption_field)))
+                },
+                Some(PropellantConsumption::FuelFlow{minimum, maximum}) => {
+                    let field = format!("{}.FuelFlow", consumption_field);
+                    require_non_negative(minimum, &field, "minimum")?;
+                    require_non_negative(maximum, &field, "maximum")?;
+                    if minimum > maximum{
+                        return Err(FcsError::with_field("Must not be greater than maximum.", format!("{}.minimum", field)))
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_mount_point(&self) -> ThrusterMountPoint<T>{
+        let [x, y, z] = self.thrust_direction;
+        let mut mount_point = ThrusterMountPoint::new(
+            self.thruster.as_ref().map(|thruster| {
+                let mut attached_thruster = Thruster::new(thruster.max_thrust, thruster.size);
+                attached_thruster.set_response(thruster.response);
+                attached_thruster.set_propellant_consumption(thruster.propellant_consumption);
+                attached_thruster
+            }),
+            Vector3::new(x, y, z, num::one()),
+            self.max_thruster_size,
+            Dimension3::new(self.mount_location[0], self.mount_location[1], self.mount_location[2])
+        );
+        mount_point.set_gimbal(self.gimbal.as_ref().map(|gimbal| Gimbal::new(gimbal.pitch_range, gimbal.yaw_range, gimbal.slew_rate)));
+        mount_point.set_propellant_feed(self.propellant_feed.clone());
+        mount_point
+    }
+}
+
+
+
+/// Thruster, in a form serde can handle. Thruster itself is only built from a validated definition
+#[derive(Serialize, Deserialize)]
+struct ThrusterDefinition<T>{
+    max_thrust: T,
+    size: ThrusterSize,
+    response: ThrusterResponse<T>,
+    propellant_consumption: Option<PropellantConsumption<T>>,
+}
+
+/// Gimbal, in a form serde can handle
+#[derive(Serialize, Deserialize)]
+struct GimbalDefinition<T>{
+    pitch_range: T,
+    yaw_range: T,
+    slew_rate: T,
+}
+
+/// ControlAxis<Dimension3<V>>, in a form serde can handle
+#[derive(Serialize, Deserialize)]
+struct AxesDefinition<V>{
+    linear: AxisDefinition<V>,
+    rotational: AxisDefinition<V>,
+}
+impl<V> AxesDefinition<V>{
+    fn from_control_axis<U, F: Fn(&U) -> V>(control_axis: &ControlAxis<Dimension3<U>>, f: F) -> Self
+        where U: Copy
+    {
+        Self{
+            linear: AxisDefinition{
+                x: f(&control_axis.linear().x()),
+                y: f(&control_axis.linear().y()),
+                z: f(&control_axis.linear().z())
+            },
+            rotational: AxisDefinition{
+                x: f(&control_axis.rotational().x()),
+                y: f(&control_axis.rotational().y()),
+                z: f(&control_axis.rotational().z())
+            },
+        }
+    }
+
+    fn to_control_axis<U, F: Fn(&V) -> U>(&self, f: F) -> ControlAxis<Dimension3<U>>{
+        ControlAxis::new(
+            Dimension3::new(f(&self.linear.x), f(&self.linear.y), f(&self.linear.z)),
+            Dimension3::new(f(&self.rotational.x), f(&self.rotational.y), f(&self.rotational.z))
+        )
+    }
+
+    /// each value with its field path, e.g. ("linear.x", value)
+    fn iter(&self) -> impl Iterator<Item = (&'static str, &V)>{
+        [
+            ("linear.x", &self.linear.x),
+            ("linear.y", &self.linear.y),
+            ("linear.z", &self.linear.z),
+            ("rotational.x", &self.rotational.x),
+            ("rotational.y", &self.rotational.y),
+            ("rotational.z", &self.rotational.z),
+        ].into_iter()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AxisDefinition<V>{
+    x: V,
+    y: V,
+    z: V,
+}
+
+/// AxisContribution, in a form serde can handle
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct LimitDefinition<T>{
+    positive: T,
+    negative: T,
+}
+
+
+
+fn require_non_negative<T: Float>(value: T, parent: &str, field: &'static str) -> Result<(), FcsError<'static>>{
+    if !value.is_finite() || value < num::zero(){
+        return Err(FcsError::with_field("Must be a finite, non negative value.", format!("{}.{}", parent, field)))
+    }
+
+    Ok(())
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn ship_json(max_thrust: &str) -> String{
+        format!(r#"{{
+            "thruster_mount_points": [
+                {{
+                    "thruster": {{
+                        "max_thrust": {},
+                        "size": "Small",
+                        "response": {{
+                            "throttle_response": {{"FirstOrderLag": {{"spool_up": 0.5, "spool_down": 0.25}}}},
+                            "dead_time": 0.0,
+                            "minimum_on_time": 0.0,
+                            "pulse_width_modulation": null
+                        }},
+                        "propellant_consumption": {{"SpecificImpulse": 300.0}}
+                    }},
+                    "max_thruster_size": "Medium",
+                    "thrust_direction": [1.0, 0.0, 0.0],
+                    "mount_location": [0.0, 0.0, -2.0],
+                    "propellant_feed": [0]
+                }},
+                {{
+                    "max_thruster_size": "Small",
+                    "thrust_direction": [-1.0, 0.0, 0.0],
+                    "mount_location": [0.0, 0.0, 2.0]
+                }}
+            ],
+            "pid_gains": {{
+                "linear": {{"x": {{"kp": 1.0, "ki": 0.0, "kd": 0.1}}, "y": {{"kp": 1.0, "ki": 0.0, "kd": 0.1}}, "z": {{"kp": 1.0, "ki": 0.0, "kd": 0.1}}}},
+                "rotational": {{"x": {{"kp": 2.0, "ki": 0.0, "kd": 0.0}}, "y": {{"kp": 2.0, "ki": 0.0, "kd": 0.0}}, "z": {{"kp": 2.0, "ki": 0.0, "kd": 0.0}}}}
+            }},
+            "max_velocity": {{
+                "linear": {{"x": 100.0, "y": 50.0, "z": 50.0}},
+                "rotational": {{"x": 1.0, "y": 1.0, "z": 1.0}}
+            }},
+            "gsafety_max_acceleration": {{
+                "linear": {{"x": {{"positive": 30.0, "negative": 20.0}}, "y": {{"positive": 10.0, "negative": 10.0}}, "z": {{"positive": 10.0, "negative": 10.0}}}},
+                "rotational": {{"x": {{"positive": 2.0, "negative": 2.0}}, "y": {{"positive": 2.0, "negative": 2.0}}, "z": {{"positive": 2.0, "negative": 2.0}}}}
+            }}
+        }}"#, max_thrust)
+    }
+
+    #[test]
+    fn test_load_and_save_ship_definition(){
+        let ship: ShipDefinition<f64> = serde_json::from_str(&ship_json("1000.0")).unwrap();
+
+        let thruster_mount_points = ship.thruster_mount_points().unwrap();
+        assert_eq!(thruster_mount_points.len(), 2);
+        let thruster = thruster_mount_points[0].attached_thruster().unwrap();
+        assert!((thruster.max_thrust() - 1_000.0).abs() < 0.001);
+        assert!(thruster.size() == ThrusterSize::Small);
+        assert!(thruster_mount_points[0].max_thruster_size() == ThrusterSize::Medium);
+        assert!((thruster_mount_points[0].mount_location().z() - (-2.0)).abs() < 0.001);
+        assert_eq!(thruster_mount_points[0].propellant_feed(), &[0]);
+        assert!(thruster_mount_points[1].attached_thruster().is_none());
+        assert!((ship.max_velocity().unwrap().linear().x() - 100.0).abs() < 0.001);
+        assert!((ship.gsafety_max_acceleration().unwrap().linear().x().negative() - 20.0).abs() < 0.001);
+        assert!(ship.pid6dof().is_ok());
+
+        // saving and reloading gives back the same ship
+        let saved = serde_json::to_string(&ShipDefinition::new(
+            &thruster_mount_points,
+            &ControlAxis::new(Dimension3::default(PidGains::new(1.0, 0.0, 0.1)), Dimension3::default(PidGains::new(2.0, 0.0, 0.0))),
+            &ship.max_velocity().unwrap(),
+            &ship.gsafety_max_acceleration().unwrap()
+        )).unwrap();
+        let reloaded: ShipDefinition<f64> = serde_json::from_str(&saved).unwrap();
+        let reloaded_mount_points = reloaded.thruster_mount_points().unwrap();
+        assert!((reloaded_mount_points[0].attached_thruster().unwrap().max_thrust() - 1_000.0).abs() < 0.001);
+        assert!((reloaded_mount_points[1].thrust_direction().x() - (-1.0)).abs() < 0.001);
+        assert!((reloaded.max_velocity().unwrap().linear().y() - 50.0).abs() < 0.001);
+        assert!((reloaded.pid_gains.rotational.z.kp() - 2.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_ship_definition_normalizes_thrust_direction(){
+        let json = ship_json("1000.0")
+            .replace("[1.0, 0.0, 0.0]", "[2.0, 0.0, 0.0]")
+            .replace("[-1.0, 0.0, 0.0]", "[-1.0, 1.0, 0.0]");
+        let ship: ShipDefinition<f64> = serde_json::from_str(&json).unwrap();
+
+        let thruster_mount_points = ship.thruster_mount_points().unwrap();
+        assert!((thruster_mount_points[0].thrust_direction().x() - 1.0).abs() < 0.001);
+        assert!((thruster_mount_points[1].thrust_direction().x() - (-0.5_f64.sqrt())).abs() < 0.001);
+        assert!((thruster_mount_points[1].thrust_direction().y() - 0.5_f64.sqrt()).abs() < 0.001);
+        // a longer direction vector doesn't mean more thrust
+        let available_thrust = crate::propulsion_control::calculate_available_thrust(&thruster_mount_points, &Dimension3::default(0.0));
+        assert!((available_thrust.linear().x().positive() - 1_000.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_ship_definition_reports_offending_field(){
+        let ship: ShipDefinition<f64> = serde_json::from_str(&ship_json("-1000.0")).unwrap();
+
+        let error = ship.thruster_mount_points().err().unwrap();
+        assert_eq!(error.field(), Some("thruster_mount_points[0].thruster.max_thrust"));
+        assert!(ship.max_velocity().is_err());
+    }
+}